@@ -0,0 +1,130 @@
+/// Engine-independent assessment of a position for the player to move.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Assessment {
+  /// Probability to win the game, from 0 to 1. Note that UCT with dynamic
+  /// komi keeps it around the komi zones, so it only becomes meaningful for
+  /// adjudication when komi is disabled or static.
+  Winrate(f64),
+  /// Score the player is proven to get with the best play of both sides.
+  Score(i32),
+  /// The engine doesn't estimate the position.
+  Unknown,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Decision {
+  /// Continue the game.
+  Play,
+  /// The position is hopeless.
+  Resign,
+  /// The position is balanced and unlikely to change.
+  OfferDraw,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct AdjudicationConfig {
+  /// Resign if the winrate stays below this value. `None` disables
+  /// winrate-based resignation.
+  pub resign_winrate: Option<f64>,
+  /// Resign if the proven score stays at least this many points behind. Zero
+  /// disables score-based resignation.
+  pub resign_score: u32,
+  /// Offer or accept a draw if the winrate stays within this distance from
+  /// 0.5. Zero disables draw offers.
+  pub draw_margin: f64,
+  /// Number of consecutive moves the condition should hold before acting.
+  pub moves: usize,
+}
+
+impl Default for AdjudicationConfig {
+  fn default() -> Self {
+    Self {
+      resign_winrate: None,
+      resign_score: 0,
+      draw_margin: 0.0,
+      moves: 3,
+    }
+  }
+}
+
+/// Decides when an engine should resign, offer or accept a draw based on the
+/// assessments of its consecutive analyses.
+#[derive(Clone, Debug)]
+pub struct Adjudicator {
+  config: AdjudicationConfig,
+  hopeless_moves: usize,
+  drawish_moves: usize,
+  last_winrate: Option<f64>,
+}
+
+impl Adjudicator {
+  pub fn new(config: AdjudicationConfig) -> Self {
+    Adjudicator {
+      config,
+      hopeless_moves: 0,
+      drawish_moves: 0,
+      last_winrate: None,
+    }
+  }
+
+  pub fn clear(&mut self) {
+    self.hopeless_moves = 0;
+    self.drawish_moves = 0;
+    self.last_winrate = None;
+  }
+
+  fn is_hopeless(&self, assessment: Assessment) -> bool {
+    match assessment {
+      Assessment::Winrate(winrate) => self
+        .config
+        .resign_winrate
+        .is_some_and(|resign_winrate| winrate < resign_winrate),
+      Assessment::Score(score) => self.config.resign_score > 0 && score <= -(self.config.resign_score as i32),
+      Assessment::Unknown => false,
+    }
+  }
+
+  fn is_drawish(&self, assessment: Assessment) -> bool {
+    match assessment {
+      Assessment::Winrate(winrate) => (winrate - 0.5).abs() <= self.config.draw_margin,
+      Assessment::Score(_) | Assessment::Unknown => false,
+    }
+  }
+
+  /// Takes into account the assessment of the last analysis and returns the
+  /// recommended decision.
+  pub fn decide(&mut self, assessment: Assessment) -> Decision {
+    if let Assessment::Winrate(winrate) = assessment {
+      self.last_winrate = Some(winrate);
+    }
+    if assessment == Assessment::Unknown {
+      return Decision::Play;
+    }
+    self.hopeless_moves = if self.is_hopeless(assessment) {
+      self.hopeless_moves + 1
+    } else {
+      0
+    };
+    self.drawish_moves = if self.config.draw_margin > 0.0 && self.is_drawish(assessment) {
+      self.drawish_moves + 1
+    } else {
+      0
+    };
+    if self.hopeless_moves >= self.config.moves {
+      Decision::Resign
+    } else if self.drawish_moves >= self.config.moves {
+      Decision::OfferDraw
+    } else {
+      Decision::Play
+    }
+  }
+
+  /// Whether the draw offered by the opponent should be accepted, i.e. the
+  /// last known winrate doesn't promise a win.
+  pub fn accept_draw(&self) -> bool {
+    self.hopeless_moves > 0
+      || self
+        .last_winrate
+        .is_some_and(|winrate| winrate <= 0.5 + self.config.draw_margin)
+  }
+}
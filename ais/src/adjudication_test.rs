@@ -0,0 +1,129 @@
+use crate::adjudication::{AdjudicationConfig, Adjudicator, Assessment, Decision};
+
+fn adjudicator() -> Adjudicator {
+  Adjudicator::new(AdjudicationConfig {
+    resign_winrate: Some(0.1),
+    resign_score: 5,
+    draw_margin: 0.05,
+    moves: 3,
+  })
+}
+
+#[test]
+fn resign_by_winrate() {
+  let mut adjudicator = adjudicator();
+  assert_eq!(adjudicator.decide(Assessment::Winrate(0.05)), Decision::Play);
+  assert_eq!(adjudicator.decide(Assessment::Winrate(0.09)), Decision::Play);
+  assert_eq!(adjudicator.decide(Assessment::Winrate(0.0)), Decision::Resign);
+  assert_eq!(adjudicator.decide(Assessment::Winrate(0.0)), Decision::Resign);
+}
+
+#[test]
+fn resign_winrate_threshold() {
+  let mut adjudicator = adjudicator();
+  for _ in 0..5 {
+    assert_eq!(adjudicator.decide(Assessment::Winrate(0.1)), Decision::Play);
+  }
+}
+
+#[test]
+fn resign_by_winrate_disabled_by_default() {
+  let mut adjudicator = Adjudicator::new(AdjudicationConfig::default());
+  for _ in 0..5 {
+    assert_eq!(adjudicator.decide(Assessment::Winrate(0.0)), Decision::Play);
+  }
+}
+
+#[test]
+fn resign_by_score() {
+  let mut adjudicator = adjudicator();
+  for _ in 0..5 {
+    assert_eq!(adjudicator.decide(Assessment::Score(-4)), Decision::Play);
+  }
+  assert_eq!(adjudicator.decide(Assessment::Score(-5)), Decision::Play);
+  assert_eq!(adjudicator.decide(Assessment::Score(-6)), Decision::Play);
+  assert_eq!(adjudicator.decide(Assessment::Score(-5)), Decision::Resign);
+}
+
+#[test]
+fn resign_by_score_disabled() {
+  let mut adjudicator = Adjudicator::new(AdjudicationConfig {
+    resign_score: 0,
+    ..AdjudicationConfig::default()
+  });
+  for _ in 0..5 {
+    assert_eq!(adjudicator.decide(Assessment::Score(-100)), Decision::Play);
+  }
+}
+
+#[test]
+fn resign_streak_reset() {
+  let mut adjudicator = adjudicator();
+  assert_eq!(adjudicator.decide(Assessment::Winrate(0.0)), Decision::Play);
+  assert_eq!(adjudicator.decide(Assessment::Winrate(0.0)), Decision::Play);
+  assert_eq!(adjudicator.decide(Assessment::Winrate(0.3)), Decision::Play);
+  assert_eq!(adjudicator.decide(Assessment::Winrate(0.0)), Decision::Play);
+  assert_eq!(adjudicator.decide(Assessment::Winrate(0.0)), Decision::Play);
+  assert_eq!(adjudicator.decide(Assessment::Winrate(0.0)), Decision::Resign);
+}
+
+#[test]
+fn resign_streak_unknown() {
+  let mut adjudicator = adjudicator();
+  assert_eq!(adjudicator.decide(Assessment::Winrate(0.0)), Decision::Play);
+  assert_eq!(adjudicator.decide(Assessment::Unknown), Decision::Play);
+  assert_eq!(adjudicator.decide(Assessment::Winrate(0.0)), Decision::Play);
+  assert_eq!(adjudicator.decide(Assessment::Unknown), Decision::Play);
+  assert_eq!(adjudicator.decide(Assessment::Winrate(0.0)), Decision::Resign);
+}
+
+#[test]
+fn resign_streak_clear() {
+  let mut adjudicator = adjudicator();
+  assert_eq!(adjudicator.decide(Assessment::Winrate(0.0)), Decision::Play);
+  assert_eq!(adjudicator.decide(Assessment::Winrate(0.0)), Decision::Play);
+  adjudicator.clear();
+  assert_eq!(adjudicator.decide(Assessment::Winrate(0.0)), Decision::Play);
+  assert_eq!(adjudicator.decide(Assessment::Winrate(0.0)), Decision::Play);
+  assert_eq!(adjudicator.decide(Assessment::Winrate(0.0)), Decision::Resign);
+}
+
+#[test]
+fn offer_draw() {
+  let mut adjudicator = adjudicator();
+  assert_eq!(adjudicator.decide(Assessment::Winrate(0.46)), Decision::Play);
+  assert_eq!(adjudicator.decide(Assessment::Winrate(0.54)), Decision::Play);
+  assert_eq!(adjudicator.decide(Assessment::Winrate(0.5)), Decision::OfferDraw);
+  assert_eq!(adjudicator.decide(Assessment::Winrate(0.6)), Decision::Play);
+  assert_eq!(adjudicator.decide(Assessment::Winrate(0.5)), Decision::Play);
+}
+
+#[test]
+fn offer_draw_disabled() {
+  let mut adjudicator = Adjudicator::new(AdjudicationConfig::default());
+  for _ in 0..5 {
+    assert_eq!(adjudicator.decide(Assessment::Winrate(0.5)), Decision::Play);
+  }
+}
+
+#[test]
+fn offer_draw_by_score() {
+  let mut adjudicator = adjudicator();
+  for _ in 0..5 {
+    assert_eq!(adjudicator.decide(Assessment::Score(0)), Decision::Play);
+  }
+}
+
+#[test]
+fn accept_draw() {
+  let mut adjudicator = adjudicator();
+  assert!(!adjudicator.accept_draw());
+  adjudicator.decide(Assessment::Winrate(0.54));
+  assert!(adjudicator.accept_draw());
+  adjudicator.decide(Assessment::Winrate(0.56));
+  assert!(!adjudicator.accept_draw());
+  adjudicator.decide(Assessment::Score(3));
+  assert!(!adjudicator.accept_draw());
+  adjudicator.decide(Assessment::Score(-5));
+  assert!(adjudicator.accept_draw());
+}
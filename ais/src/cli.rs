@@ -1,16 +1,45 @@
-use crate::oppai::{Config, Solver};
+use crate::{
+  adjudication::AdjudicationConfig,
+  evaluation::EvaluationType,
+  oppai::{Config, Solver},
+  playout::PlayoutType,
+  prior::PriorType,
+  strength::Strength,
+};
 use clap::{value_parser, Arg, ArgAction, ArgGroup, ArgMatches};
-use oppai_minimax::minimax::{MinimaxConfig, MinimaxType};
+use oppai_minimax::{
+  evaluation::EvaluationWeights,
+  minimax::{MinimaxConfig, MinimaxType},
+};
 use oppai_uct::uct::{UcbType, UctConfig, UctKomiType};
+use std::fs;
 
-pub fn groups() -> [ArgGroup; 2] {
+fn parse_evaluation_weights(path: &str) -> Result<EvaluationWeights, String> {
+  fs::read_to_string(path)
+    .map_err(|e| format!("Failed to read evaluation weights file: {}", e))?
+    .parse()
+}
+
+pub fn groups() -> [ArgGroup; 4] {
   [
     ArgGroup::new("Minimax")
-      .args(["minimax-type", "rebuild-trajectories"])
+      .args([
+        "minimax-type",
+        "rebuild-trajectories",
+        "multi-pv",
+        "split-trajectories",
+        "no-move-ordering",
+        "quiescence-depth",
+        "forbid-losing-ladders",
+        "evaluation-type",
+        "evaluation-weights",
+      ])
       .multiple(true),
     ArgGroup::new("UCT")
       .args([
         "radius",
+        "empty-radius",
+        "recent-radius",
         "uct-depth",
         "when-create-children",
         "ucb-type",
@@ -21,12 +50,29 @@ pub fn groups() -> [ArgGroup; 2] {
         "komi-type",
         "komi-min-iterations",
         "fpu",
+        "rave-equivalence",
+        "score-weight",
+        "score-scale",
+        "playout-type",
+        "prior-type",
+        "prior-weight",
+        "widening-base",
+        "widening-exponent",
+        "uct-memory",
+        "virtual-loss",
+        "uct-forbid-losing-ladders",
       ])
       .multiple(true),
+    ArgGroup::new("Ensemble")
+      .args(["ensemble-uct-factor", "ensemble-zero-factor", "ensemble-veto-margin"])
+      .multiple(true),
+    ArgGroup::new("Adjudication")
+      .args(["resign-winrate", "resign-score", "draw-margin", "adjudication-moves"])
+      .multiple(true),
   ]
 }
 
-pub fn args() -> [Arg; 20] {
+pub fn args() -> [Arg; 50] {
   [
     Arg::new("solver")
       .short('s')
@@ -46,12 +92,12 @@ pub fn args() -> [Arg; 20] {
       )
       .num_args(1)
       .value_parser(value_parser!(usize)),
-    Arg::new("hash-table-size")
-      .long("hash-table-size")
-      .help("Count of elements that hash table for Minimax can contain")
+    Arg::new("hash-table-memory")
+      .long("hash-table-memory")
+      .help("Maximum memory of the hash table for Minimax in megabytes")
       .num_args(1)
       .value_parser(value_parser!(usize))
-      .default_value("10000"),
+      .default_value("16"),
     Arg::new("minimax-type")
       .long("minimax-type")
       .help("Minimax type")
@@ -66,6 +112,48 @@ pub fn args() -> [Arg; 20] {
          reduces speed dramatically",
       )
       .action(ArgAction::SetTrue),
+    Arg::new("multi-pv")
+      .long("multi-pv")
+      .help("Number of best moves to evaluate with exact estimations during minimax search")
+      .num_args(1)
+      .value_parser(value_parser!(usize))
+      .default_value("1"),
+    Arg::new("split-trajectories")
+      .long("split-trajectories")
+      .help(
+        "Solve independent groups of trajectories separately during minimax search and \
+         combine their results",
+      )
+      .action(ArgAction::SetTrue),
+    Arg::new("no-move-ordering")
+      .long("no-move-ordering")
+      .help("Disable killer, history and counter-move heuristics for minimax moves ordering")
+      .action(ArgAction::SetFalse),
+    Arg::new("quiescence-depth")
+      .long("quiescence-depth")
+      .help("Maximum number of capturing and escaping moves to search beyond the minimax depth")
+      .num_args(1)
+      .value_parser(value_parser!(u32))
+      .default_value("0"),
+    Arg::new("forbid-losing-ladders")
+      .long("forbid-losing-ladders")
+      .help("Exclude minimax root moves that start or extend a ladder losing for the player")
+      .action(ArgAction::SetTrue),
+    Arg::new("evaluation-type")
+      .long("evaluation-type")
+      .help("Estimation of minimax leaves. Positional one isn't stronger than material yet")
+      .num_args(1)
+      .value_parser(value_parser!(EvaluationType))
+      .ignore_case(true)
+      .default_value("Material"),
+    Arg::new("evaluation-weights")
+      .long("evaluation-weights")
+      .help(
+        "File with weights of the positional evaluation. Every line has the form \
+         'name value' where name is one of threats, connectivity, grounding or territory",
+      )
+      .num_args(1)
+      .value_parser(parse_evaluation_weights),
     Arg::new("radius")
       .long("radius")
       .help(
@@ -77,6 +165,18 @@ pub fn args() -> [Arg; 20] {
       .num_args(1)
       .value_parser(value_parser!(u32))
       .default_value("3"),
+    Arg::new("empty-radius")
+      .long("empty-radius")
+      .help("Radius added to the UCT radius on an empty field, decreasing as the field fills up")
+      .num_args(1)
+      .value_parser(value_parser!(u32))
+      .default_value("0"),
+    Arg::new("recent-radius")
+      .long("recent-radius")
+      .help("Radius added to the UCT radius around the last moves")
+      .num_args(1)
+      .value_parser(value_parser!(u32))
+      .default_value("0"),
     Arg::new("uct-depth")
       .long("uct-depth")
       .help("Maximum depth of the UCT tree")
@@ -152,10 +252,83 @@ pub fn args() -> [Arg; 20] {
       .num_args(1)
       .value_parser(value_parser!(f64))
       .default_value("1.1"),
+    Arg::new("rave-equivalence")
+      .long("rave-equivalence")
+      .help("Number of visits at which UCT and AMAF winrates have equal weights with RAVE UCB type")
+      .num_args(1)
+      .value_parser(value_parser!(f64))
+      .default_value("300"),
+    Arg::new("score-weight")
+      .long("score-weight")
+      .help("Weight of the expected score utility added to the UCT winrate")
+      .num_args(1)
+      .value_parser(value_parser!(f64))
+      .default_value("0"),
+    Arg::new("score-scale")
+      .long("score-scale")
+      .help("Score difference at which the score utility reaches half of its weight")
+      .num_args(1)
+      .value_parser(value_parser!(f64))
+      .default_value("10"),
+    Arg::new("playout-type")
+      .long("playout-type")
+      .help("Type of random games played by UCT. Guided ones are not tuned yet and lose to uniform ones")
+      .num_args(1)
+      .value_parser(value_parser!(PlayoutType))
+      .ignore_case(true)
+      .default_value("Uniform"),
+    Arg::new("prior-type")
+      .long("prior-type")
+      .help("Source of UCT priors used to order and favour children")
+      .num_args(1)
+      .value_parser(value_parser!(PriorType))
+      .ignore_case(true)
+      .default_value("None"),
+    Arg::new("prior-weight")
+      .long("prior-weight")
+      .help("Weight of the PUCT term of UCT priors")
+      .num_args(1)
+      .value_parser(value_parser!(f64))
+      .default_value("1"),
+    Arg::new("widening-base")
+      .long("widening-base")
+      .help("Progressive widening multiplier, 0 to disable")
+      .num_args(1)
+      .value_parser(value_parser!(f64))
+      .default_value("0"),
+    Arg::new("widening-exponent")
+      .long("widening-exponent")
+      .help("Progressive widening exponent of node visits")
+      .num_args(1)
+      .value_parser(value_parser!(f64))
+      .default_value("0.5"),
+    Arg::new("uct-memory")
+      .long("uct-memory")
+      .help("Maximum memory of the UCT tree in megabytes")
+      .num_args(1)
+      .value_parser(value_parser!(usize))
+      .default_value("1024"),
+    Arg::new("virtual-loss")
+      .long("virtual-loss")
+      .help("Number of losses that a UCT thread temporarily adds to nodes of its path to divert other threads")
+      .num_args(1)
+      .value_parser(value_parser!(usize))
+      .default_value("3"),
+    Arg::new("uct-forbid-losing-ladders")
+      .long("uct-forbid-losing-ladders")
+      .help("Exclude UCT root moves that start or extend a ladder losing for the player")
+      .action(ArgAction::SetTrue),
     Arg::new("no-ladders-solver")
       .long("no-ladders-solver")
       .help("Disable ladders solver")
       .action(ArgAction::SetFalse),
+    Arg::new("ladders-defense")
+      .long("ladders-defense")
+      .help(
+        "Search escapes and breakers for enemy ladders when there is no own ladder. \
+         The defense is played without the main solver, so it's worth raising ladders limits with it",
+      )
+      .action(ArgAction::SetTrue),
     Arg::new("ladders-score-limit")
       .long("ladders-score-limit")
       .help("Score that a ladder should have to be accepted")
@@ -174,14 +347,91 @@ pub fn args() -> [Arg; 20] {
       .num_args(1)
       .value_parser(value_parser!(humantime::Duration))
       .default_value("1s"),
+    Arg::new("strength")
+      .long("strength")
+      .help(
+        "Playing strength. Weaker levels limit the search depth and iterations, \
+         choose moves randomly with a temperature and sometimes ignore ladders",
+      )
+      .num_args(1)
+      .value_parser(value_parser!(Strength))
+      .ignore_case(true)
+      .default_value("Full"),
+    Arg::new("seed")
+      .long("seed")
+      .help(
+        "Seed for the random number generator. Enables deterministic mode: analyses \
+         limited by complexity rather than time are reproducible regardless of the \
+         number of threads",
+      )
+      .num_args(1)
+      .value_parser(value_parser!(u64)),
+    Arg::new("ensemble-uct-factor")
+      .long("ensemble-uct-factor")
+      .help("Factor of UCT move weights in the ensemble")
+      .num_args(1)
+      .value_parser(value_parser!(f64))
+      .default_value("1"),
+    Arg::new("ensemble-zero-factor")
+      .long("ensemble-zero-factor")
+      .help("Factor of Zero move weights in the ensemble")
+      .num_args(1)
+      .value_parser(value_parser!(f64))
+      .default_value("1"),
+    Arg::new("ensemble-veto-margin")
+      .long("ensemble-veto-margin")
+      .help(
+        "Minimal score gain or loss found by Minimax for its moves to be the only \
+         ones considered by the ensemble",
+      )
+      .num_args(1)
+      .value_parser(value_parser!(i32))
+      .default_value("1"),
+    Arg::new("resign-winrate")
+      .long("resign-winrate")
+      .help(
+        "Resign when the winrate stays below this value. Should be fractional number \
+         between 0 and 1. Works best with static komi or without it since dynamic komi \
+         keeps the winrate balanced. Winrate-based resignation is disabled if not \
+         specified",
+      )
+      .num_args(1)
+      .value_parser(value_parser!(f64)),
+    Arg::new("resign-score")
+      .long("resign-score")
+      .help(
+        "Resign when minimax proves that the score stays at least this many points \
+         behind. 0 disables score-based resignation",
+      )
+      .num_args(1)
+      .value_parser(value_parser!(u32))
+      .default_value("0"),
+    Arg::new("draw-margin")
+      .long("draw-margin")
+      .help(
+        "Offer and accept a draw when the winrate stays within this distance from 0.5. \
+         0 disables draw offers",
+      )
+      .num_args(1)
+      .value_parser(value_parser!(f64))
+      .default_value("0"),
+    Arg::new("adjudication-moves")
+      .long("adjudication-moves")
+      .help("Number of consecutive moves the resign or draw condition should hold")
+      .num_args(1)
+      .value_parser(value_parser!(usize))
+      .default_value("3"),
   ]
 }
 
 pub fn parse_config(matches: &ArgMatches) -> Config {
   let threads_count = matches.get_one("threads-count").copied();
+  let seed = matches.get_one("seed").copied();
   let uct_config = UctConfig {
     threads_count: threads_count.unwrap_or_else(num_cpus::get),
     radius: matches.get_one("radius").copied().unwrap(),
+    empty_radius: matches.get_one("empty-radius").copied().unwrap(),
+    recent_radius: matches.get_one("recent-radius").copied().unwrap(),
     ucb_type: matches.get_one("ucb-type").copied().unwrap(),
     draw_weight: matches.get_one("draw-weight").copied().unwrap(),
     uctk: matches.get_one("uctk").copied().unwrap(),
@@ -192,24 +442,56 @@ pub fn parse_config(matches: &ArgMatches) -> Config {
     green: matches.get_one("green").copied().unwrap(),
     komi_min_iterations: matches.get_one("komi-min-iterations").copied().unwrap(),
     fpu: matches.get_one("fpu").copied().unwrap(),
+    rave_equivalence: matches.get_one("rave-equivalence").copied().unwrap(),
+    score_weight: matches.get_one("score-weight").copied().unwrap(),
+    score_scale: matches.get_one("score-scale").copied().unwrap(),
+    prior_weight: matches.get_one("prior-weight").copied().unwrap(),
+    widening_base: matches.get_one("widening-base").copied().unwrap(),
+    widening_exponent: matches.get_one("widening-exponent").copied().unwrap(),
+    memory: matches.get_one("uct-memory").copied().unwrap(),
+    deterministic: seed.is_some(),
+    virtual_loss: matches.get_one("virtual-loss").copied().unwrap(),
+    forbid_losing_ladders: matches.get_flag("uct-forbid-losing-ladders"),
   };
   let minimax_config = MinimaxConfig {
     threads_count: threads_count.unwrap_or_else(num_cpus::get_physical),
     minimax_type: matches.get_one("minimax-type").copied().unwrap(),
-    hash_table_size: matches.get_one("hash-table-size").copied().unwrap(),
+    hash_table_memory: matches.get_one("hash-table-memory").copied().unwrap(),
     rebuild_trajectories: matches.get_flag("rebuild-trajectories"),
+    deterministic: seed.is_some(),
+    multi_pv: matches.get_one("multi-pv").copied().unwrap(),
+    split_trajectories: matches.get_flag("split-trajectories"),
+    move_ordering: matches.get_flag("no-move-ordering"),
+    quiescence_depth: matches.get_one("quiescence-depth").copied().unwrap(),
+    forbid_losing_ladders: matches.get_flag("forbid-losing-ladders"),
   };
   Config {
     uct: uct_config,
     minimax: minimax_config,
     solver: matches.get_one("solver").copied().unwrap(),
+    playout_type: matches.get_one("playout-type").copied().unwrap(),
+    prior_type: matches.get_one("prior-type").copied().unwrap(),
+    evaluation_type: matches.get_one("evaluation-type").copied().unwrap(),
+    evaluation_weights: matches.get_one("evaluation-weights").copied().unwrap_or_default(),
+    ensemble_uct_factor: matches.get_one("ensemble-uct-factor").copied().unwrap(),
+    ensemble_zero_factor: matches.get_one("ensemble-zero-factor").copied().unwrap(),
+    ensemble_veto_margin: matches.get_one("ensemble-veto-margin").copied().unwrap(),
     ladders: matches.get_flag("no-ladders-solver"),
     ladders_score_limit: matches.get_one("ladders-score-limit").copied().unwrap(),
     ladders_depth_limit: matches.get_one("ladders-depth-limit").copied().unwrap(),
+    ladders_defense: matches.get_flag("ladders-defense"),
     ladders_time_limit: matches
       .get_one::<humantime::Duration>("ladders-time-limit")
       .copied()
       .unwrap()
       .into(),
+    strength: matches.get_one("strength").copied().unwrap(),
+    seed,
+    adjudication: AdjudicationConfig {
+      resign_winrate: matches.get_one("resign-winrate").copied(),
+      resign_score: matches.get_one("resign-score").copied().unwrap(),
+      draw_margin: matches.get_one("draw-margin").copied().unwrap(),
+      moves: matches.get_one("adjudication-moves").copied().unwrap(),
+    },
   }
 }
@@ -0,0 +1,301 @@
+use oppai_ai::{ai::AI, analysis::Analysis};
+use oppai_field::{field::Field, field::Pos, player::Player};
+use rand::{distributions::Standard, prelude::Distribution, Rng, SeedableRng};
+use std::{any::TypeId, collections::BTreeMap};
+
+/// Weight of the analysis that can be merged with weights of other engines.
+pub trait NumericWeight {
+  fn value(&self) -> f64;
+}
+
+impl NumericWeight for () {
+  fn value(&self) -> f64 {
+    1.0
+  }
+}
+
+impl NumericWeight for i32 {
+  fn value(&self) -> f64 {
+    *self as f64
+  }
+}
+
+impl NumericWeight for u64 {
+  fn value(&self) -> f64 {
+    *self as f64
+  }
+}
+
+impl NumericWeight for f64 {
+  fn value(&self) -> f64 {
+    *self
+  }
+}
+
+/// Scales weights of the analysis moves to `[0, 1]`.
+fn normalize<A: Analysis>(analysis: &A) -> Vec<(Pos, f64)>
+where
+  A::Weight: NumericWeight,
+{
+  let moves = analysis
+    .moves()
+    .map(|(pos, weight)| (pos, weight.value()))
+    .collect::<Vec<_>>();
+  let min = moves.iter().map(|&(_, weight)| weight).fold(f64::INFINITY, f64::min);
+  let max = moves
+    .iter()
+    .map(|&(_, weight)| weight)
+    .fold(f64::NEG_INFINITY, f64::max);
+  moves
+    .into_iter()
+    .map(|(pos, weight)| (pos, if max > min { (weight - min) / (max - min) } else { 1.0 }))
+    .collect()
+}
+
+/// Runs both AIs at the same time so that they share the time budget.
+#[cfg(not(target_arch = "wasm32"))]
+fn analyze_both<A, B, S, R, SS>(
+  a: &mut A,
+  b: &mut B,
+  rng: &mut R,
+  field: &mut Field,
+  player: Player,
+  confidence: Option<(A::Confidence, B::Confidence)>,
+  should_stop: &SS,
+) -> (A::Analysis, B::Analysis)
+where
+  A: AI + Send,
+  B: AI,
+  A::Analysis: Send,
+  A::Confidence: Send,
+  R: Rng + SeedableRng<Seed = S> + Send,
+  Standard: Distribution<S>,
+  SS: Fn() -> bool + Sync,
+{
+  let (a_confidence, b_confidence) = confidence.map_or((None, None), |(a, b)| (Some(a), Some(b)));
+  let mut a_rng = R::from_seed(rng.gen());
+  let mut a_field = field.clone();
+  crossbeam::scope(|scope| {
+    let handle = scope.spawn(|_| a.analyze(&mut a_rng, &mut a_field, player, a_confidence, should_stop));
+    let b_analysis = b.analyze(rng, field, player, b_confidence, should_stop);
+    (handle.join().expect("Ensemble member panic"), b_analysis)
+  })
+  .expect("Ensemble panic")
+}
+
+/// Without threads AIs are run one after another, so the first one should be
+/// limited by the confidence to leave time for the second one.
+#[cfg(target_arch = "wasm32")]
+fn analyze_both<A, B, S, R, SS>(
+  a: &mut A,
+  b: &mut B,
+  rng: &mut R,
+  field: &mut Field,
+  player: Player,
+  confidence: Option<(A::Confidence, B::Confidence)>,
+  should_stop: &SS,
+) -> (A::Analysis, B::Analysis)
+where
+  A: AI + Send,
+  B: AI,
+  A::Analysis: Send,
+  A::Confidence: Send,
+  R: Rng + SeedableRng<Seed = S> + Send,
+  Standard: Distribution<S>,
+  SS: Fn() -> bool + Sync,
+{
+  let (a_confidence, b_confidence) = confidence.map_or((None, None), |(a, b)| (Some(a), Some(b)));
+  let a_analysis = a.analyze(rng, field, player, a_confidence, should_stop);
+  let b_analysis = b.analyze(rng, field, player, b_confidence, should_stop);
+  (a_analysis, b_analysis)
+}
+
+/// Analysis merged from two engines.
+pub struct EnsembleAnalysis<A: Analysis, B: Analysis> {
+  first: A,
+  second: B,
+  /// Moves with weighted normalized contributions of the first and the second
+  /// engine.
+  moves: Vec<(Pos, f64, f64)>,
+}
+
+impl<A: Analysis, B: Analysis> EnsembleAnalysis<A, B> {
+  pub fn first(&self) -> &A {
+    &self.first
+  }
+
+  pub fn second(&self) -> &B {
+    &self.second
+  }
+
+  /// How each engine contributed to the weight of every move.
+  pub fn contributions(&self) -> &[(Pos, f64, f64)] {
+    &self.moves
+  }
+}
+
+impl<A: Analysis, B: Analysis> Analysis for EnsembleAnalysis<A, B> {
+  type Weight = f64;
+  type Estimation = (A::Estimation, B::Estimation);
+  type Confidence = (A::Confidence, B::Confidence);
+
+  fn moves(&self) -> impl Iterator<Item = (Pos, Self::Weight)> {
+    self.moves.iter().map(|&(pos, first, second)| (pos, first + second))
+  }
+
+  fn estimation(&self) -> Self::Estimation {
+    (self.first.estimation(), self.second.estimation())
+  }
+
+  fn confidence(&self) -> Self::Confidence {
+    (self.first.confidence(), self.second.confidence())
+  }
+
+  fn origin(&self) -> TypeId {
+    TypeId::of::<Ensemble<(), ()>>()
+  }
+
+  fn is_empty(&self) -> bool {
+    self.moves.is_empty()
+  }
+}
+
+/// Runs two AIs within a shared time budget and sums their normalized move
+/// weights multiplied by the given factors. Ensembles of more than two AIs can
+/// be built by nesting.
+pub struct Ensemble<A, B> {
+  pub first: A,
+  pub second: B,
+  pub first_factor: f64,
+  pub second_factor: f64,
+}
+
+impl<A, B> AI for Ensemble<A, B>
+where
+  A: AI + Send,
+  B: AI,
+  A::Analysis: Send,
+  A::Confidence: Send,
+  <A::Analysis as Analysis>::Weight: NumericWeight,
+  <B::Analysis as Analysis>::Weight: NumericWeight,
+{
+  type Analysis = EnsembleAnalysis<A::Analysis, B::Analysis>;
+  type Confidence = (A::Confidence, B::Confidence);
+
+  fn analyze<S, R, SS>(
+    &mut self,
+    rng: &mut R,
+    field: &mut Field,
+    player: Player,
+    confidence: Option<Self::Confidence>,
+    should_stop: &SS,
+  ) -> Self::Analysis
+  where
+    R: Rng + SeedableRng<Seed = S> + Send,
+    Standard: Distribution<S>,
+    SS: Fn() -> bool + Sync,
+  {
+    let (first, second) = analyze_both(
+      &mut self.first,
+      &mut self.second,
+      rng,
+      field,
+      player,
+      confidence,
+      should_stop,
+    );
+    let mut moves = BTreeMap::new();
+    for (pos, weight) in normalize(&first) {
+      moves.entry(pos).or_insert((0.0, 0.0)).0 = weight * self.first_factor;
+    }
+    for (pos, weight) in normalize(&second) {
+      moves.entry(pos).or_insert((0.0, 0.0)).1 = weight * self.second_factor;
+    }
+    EnsembleAnalysis {
+      first,
+      second,
+      moves: moves
+        .into_iter()
+        .map(|(pos, (first, second))| (pos, first, second))
+        .collect(),
+    }
+  }
+}
+
+/// Runs two AIs within a shared time budget and lets the first one veto moves
+/// of the second one. The veto AI estimates the score, and only when it proves
+/// a gain or a loss of at least `margin` points compared to the current score,
+/// for instance minimax found a tactic, its suggested moves are the only ones
+/// kept, weighted by the second AI. Contributions of the veto AI are reported
+/// as its normalized weights.
+pub struct Veto<V, A> {
+  pub veto: V,
+  pub ai: A,
+  pub margin: i32,
+}
+
+impl<V, A> AI for Veto<V, A>
+where
+  V: AI + Send,
+  A: AI,
+  V::Analysis: Send,
+  V::Confidence: Send,
+  <V::Analysis as Analysis>::Weight: NumericWeight,
+  <V::Analysis as Analysis>::Estimation: NumericWeight,
+  <A::Analysis as Analysis>::Weight: NumericWeight,
+{
+  type Analysis = EnsembleAnalysis<V::Analysis, A::Analysis>;
+  type Confidence = (V::Confidence, A::Confidence);
+
+  fn analyze<S, R, SS>(
+    &mut self,
+    rng: &mut R,
+    field: &mut Field,
+    player: Player,
+    confidence: Option<Self::Confidence>,
+    should_stop: &SS,
+  ) -> Self::Analysis
+  where
+    R: Rng + SeedableRng<Seed = S> + Send,
+    Standard: Distribution<S>,
+    SS: Fn() -> bool + Sync,
+  {
+    let (veto, ai) = analyze_both(
+      &mut self.veto,
+      &mut self.ai,
+      rng,
+      field,
+      player,
+      confidence,
+      should_stop,
+    );
+    let is_decisive = (veto.estimation().value() - field.score(player) as f64).abs() >= self.margin as f64;
+    let allowed = if is_decisive {
+      normalize(&veto).into_iter().collect::<BTreeMap<_, _>>()
+    } else {
+      BTreeMap::new()
+    };
+    let ai_moves = normalize(&ai);
+    let moves = if allowed.is_empty() {
+      ai_moves.into_iter().map(|(pos, weight)| (pos, 0.0, weight)).collect()
+    } else {
+      let moves = ai_moves
+        .into_iter()
+        .filter_map(|(pos, weight)| allowed.get(&pos).map(|&veto_weight| (pos, veto_weight, weight)))
+        .collect::<Vec<_>>();
+      if moves.is_empty() {
+        allowed
+          .into_iter()
+          .map(|(pos, veto_weight)| (pos, veto_weight, 0.0))
+          .collect()
+      } else {
+        moves
+      }
+    };
+    EnsembleAnalysis {
+      first: veto,
+      second: ai,
+      moves,
+    }
+  }
+}
@@ -0,0 +1,157 @@
+use crate::ensemble::{Ensemble, Veto};
+use oppai_ai::{
+  ai::AI,
+  analysis::{Analysis, SimpleAnalysis},
+};
+use oppai_field::{
+  field::{Field, Pos},
+  player::Player,
+};
+use rand::{distributions::Standard, prelude::Distribution, Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+use std::any::TypeId;
+
+const SEED: u64 = 7;
+
+/// AI that always returns the same moves and estimation.
+struct Fixed {
+  moves: Vec<(Pos, i32)>,
+  estimation: i32,
+}
+
+impl AI for Fixed {
+  type Analysis = SimpleAnalysis<i32, i32, ()>;
+  type Confidence = ();
+
+  fn analyze<S, R, SS>(
+    &mut self,
+    _: &mut R,
+    _: &mut Field,
+    _: Player,
+    _: Option<Self::Confidence>,
+    _: &SS,
+  ) -> Self::Analysis
+  where
+    R: Rng + SeedableRng<Seed = S> + Send,
+    Standard: Distribution<S>,
+    SS: Fn() -> bool + Sync,
+  {
+    SimpleAnalysis {
+      moves: self.moves.clone(),
+      estimation: self.estimation,
+      confidence: (),
+      origin: TypeId::of::<Self>(),
+    }
+  }
+}
+
+fn fixed(moves: &[(Pos, i32)], estimation: i32) -> Fixed {
+  Fixed {
+    moves: moves.to_vec(),
+    estimation,
+  }
+}
+
+fn analyze<A: AI>(ai: &mut A) -> A::Analysis {
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+  let mut field = Field::new_from_rng(8, 8, &mut rng);
+  ai.analyze(&mut rng, &mut field, Player::Red, None, &|| false)
+}
+
+#[test]
+fn ensemble_merge() {
+  let mut ensemble = Ensemble {
+    first: fixed(&[(10, 0), (11, 4)], 0),
+    second: fixed(&[(11, 10), (12, 20)], 0),
+    first_factor: 1.0,
+    second_factor: 2.0,
+  };
+  let analysis = analyze(&mut ensemble);
+  assert_eq!(
+    analysis.contributions(),
+    &[(10, 0.0, 0.0), (11, 1.0, 0.0), (12, 0.0, 2.0)]
+  );
+  assert_eq!(
+    analysis.moves().collect::<Vec<_>>(),
+    vec![(10, 0.0), (11, 1.0), (12, 2.0)]
+  );
+  assert_eq!(analysis.estimation(), (0, 0));
+}
+
+#[test]
+fn ensemble_equal_weights() {
+  let mut ensemble = Ensemble {
+    first: fixed(&[(10, 3), (11, 3)], 0),
+    second: fixed(&[], 0),
+    first_factor: 0.5,
+    second_factor: 1.0,
+  };
+  let analysis = analyze(&mut ensemble);
+  assert_eq!(analysis.contributions(), &[(10, 0.5, 0.0), (11, 0.5, 0.0)]);
+}
+
+#[test]
+fn veto_gain() {
+  let mut veto = Veto {
+    veto: fixed(&[(11, 2)], 2),
+    ai: fixed(&[(10, 1), (11, 2), (12, 3)], 0),
+    margin: 1,
+  };
+  let analysis = analyze(&mut veto);
+  assert_eq!(analysis.contributions(), &[(11, 1.0, 0.5)]);
+}
+
+#[test]
+fn veto_loss() {
+  let mut veto = Veto {
+    veto: fixed(&[(11, -1), (12, -1)], -1),
+    ai: fixed(&[(10, 1), (11, 2), (12, 3)], 0),
+    margin: 1,
+  };
+  let analysis = analyze(&mut veto);
+  assert_eq!(analysis.contributions(), &[(11, 1.0, 0.5), (12, 1.0, 1.0)]);
+}
+
+#[test]
+fn veto_not_decisive() {
+  let mut veto = Veto {
+    veto: fixed(&[(11, 0)], 0),
+    ai: fixed(&[(10, 1), (11, 3)], 0),
+    margin: 1,
+  };
+  let analysis = analyze(&mut veto);
+  assert_eq!(analysis.contributions(), &[(10, 0.0, 0.0), (11, 0.0, 1.0)]);
+}
+
+#[test]
+fn veto_below_margin() {
+  let mut veto = Veto {
+    veto: fixed(&[(11, 2)], 2),
+    ai: fixed(&[(10, 1), (11, 3)], 0),
+    margin: 3,
+  };
+  let analysis = analyze(&mut veto);
+  assert_eq!(analysis.contributions(), &[(10, 0.0, 0.0), (11, 0.0, 1.0)]);
+}
+
+#[test]
+fn veto_unknown_moves() {
+  let mut veto = Veto {
+    veto: fixed(&[(13, 5)], 5),
+    ai: fixed(&[(10, 1), (11, 3)], 0),
+    margin: 1,
+  };
+  let analysis = analyze(&mut veto);
+  assert_eq!(analysis.contributions(), &[(13, 1.0, 0.0)]);
+}
+
+#[test]
+fn veto_no_moves() {
+  let mut veto = Veto {
+    veto: fixed(&[], -3),
+    ai: fixed(&[(10, 1), (11, 3)], 0),
+    margin: 1,
+  };
+  let analysis = analyze(&mut veto);
+  assert_eq!(analysis.contributions(), &[(10, 0.0, 0.0), (11, 0.0, 1.0)]);
+}
@@ -0,0 +1,33 @@
+use oppai_field::{field::Field, player::Player};
+use oppai_minimax::evaluation::{Evaluation as EvaluationTrait, MaterialEvaluation, PositionalEvaluation};
+use strum::{EnumString, VariantNames};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, EnumString, VariantNames)]
+pub enum EvaluationType {
+  /// Leaves are estimated by the score only.
+  Material,
+  /// Leaves are estimated by the score and positional features.
+  Positional,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Evaluation {
+  Material(MaterialEvaluation),
+  Positional(PositionalEvaluation),
+}
+
+impl EvaluationTrait for Evaluation {
+  fn scale(&self) -> i32 {
+    match self {
+      Evaluation::Material(evaluation) => evaluation.scale(),
+      Evaluation::Positional(evaluation) => evaluation.scale(),
+    }
+  }
+
+  fn evaluate(&self, field: &mut Field, player: Player) -> i32 {
+    match self {
+      Evaluation::Material(evaluation) => evaluation.evaluate(field, player),
+      Evaluation::Positional(evaluation) => evaluation.evaluate(field, player),
+    }
+  }
+}
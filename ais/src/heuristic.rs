@@ -6,7 +6,7 @@ use std::any::TypeId;
 
 static CG_SUM: [i32; 9] = [-5, -1, 0, 0, 1, 2, 5, 20, 30];
 
-fn heuristic_estimation(field: &Field, pos: Pos, player: Player) -> i32 {
+pub(crate) fn heuristic_estimation(field: &Field, pos: Pos, player: Player) -> i32 {
   let enemy = player.next();
   let g1 = field.number_near_groups(pos, player) as i32;
   let g2 = field.number_near_groups(pos, enemy) as i32;
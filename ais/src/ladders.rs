@@ -1,10 +1,27 @@
 use oppai_ai::{ai::AI, analysis::SingleAnalysis};
-use oppai_field::{field::Field, player::Player};
-use oppai_ladders::ladders::ladders;
+use oppai_field::{
+  field::{Field, NonZeroPos},
+  player::Player,
+};
+use oppai_ladders::ladders::{ladder_defense, ladders};
 use std::any::TypeId;
 
+/// Ladders solver that accepts only ladders capturing at least `score_limit`
+/// points with at least `depth_limit` moves before the capture. If the player
+/// has no such ladder and `defense` is set, it escapes from or breaks enemy
+/// ladders that pass the same limits.
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub struct Ladders;
+pub struct Ladders {
+  pub score_limit: u32,
+  pub depth_limit: u32,
+  pub defense: bool,
+}
+
+impl Ladders {
+  fn is_accepted(&self, gain: i32, capture_depth: u32) -> bool {
+    gain >= self.score_limit as i32 && capture_depth >= self.depth_limit
+  }
+}
 
 impl AI for Ladders {
   type Analysis = SingleAnalysis<i32, ()>;
@@ -21,10 +38,28 @@ impl AI for Ladders {
   where
     SS: Fn() -> bool + Sync,
   {
-    let (pos, score, _) = ladders(field, player, should_stop);
+    let ladder = ladders(field, player, should_stop);
+    let (best_move, estimation) =
+      if ladder.best_move.is_some() && self.is_accepted(ladder.score - field.score(player), ladder.capture_depth) {
+        (ladder.best_move, ladder.score)
+      } else if let Some(defense) = self
+        .defense
+        .then(|| ladder_defense(field, player, should_stop))
+        .flatten()
+        .filter(|defense| {
+          self.is_accepted(
+            defense.threat.score - field.score(player.next()),
+            defense.threat.capture_depth,
+          )
+        })
+      {
+        (NonZeroPos::new(defense.best_move), -defense.remaining.score)
+      } else {
+        (None, field.score(player))
+      };
     SingleAnalysis {
-      best_move: pos,
-      estimation: score,
+      best_move,
+      estimation,
       confidence: (),
       origin: TypeId::of::<Self>(),
     }
@@ -0,0 +1,105 @@
+use crate::ladders::Ladders;
+use oppai_ai::{ai::AI, analysis::SingleAnalysis};
+use oppai_field::{
+  construct_field::construct_field,
+  field::{Field, NonZeroPos},
+  player::Player,
+};
+use rand::SeedableRng;
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+const SEED: u64 = 7;
+
+/// Red captures 3 points in a ladder with 5 moves before the capture.
+const LADDER: &str = "
+  .........
+  ......a..
+  .........
+  .........
+  ..aA.....
+  .aAAa....
+  ..aa.....
+  .........
+  ";
+
+fn analyze(ladders: Ladders) -> (Field, SingleAnalysis<i32, ()>) {
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+  let mut field = construct_field(&mut rng, LADDER);
+  let mut ladders = ladders;
+  let analysis = ladders.analyze(&mut rng, &mut field, Player::Red, None, &|| false);
+  (field, analysis)
+}
+
+#[test]
+fn ladders_within_limits() {
+  let (field, analysis) = analyze(Ladders {
+    score_limit: 3,
+    depth_limit: 5,
+    defense: false,
+  });
+  assert_eq!(analysis.best_move, NonZeroPos::new(field.to_pos(3, 3)));
+  assert_eq!(analysis.estimation, 3);
+}
+
+#[test]
+fn ladders_score_limit() {
+  let (_, analysis) = analyze(Ladders {
+    score_limit: 4,
+    depth_limit: 0,
+    defense: false,
+  });
+  assert_eq!(analysis.best_move, None);
+  assert_eq!(analysis.estimation, 0);
+}
+
+#[test]
+fn ladders_depth_limit() {
+  let (_, analysis) = analyze(Ladders {
+    score_limit: 0,
+    depth_limit: 6,
+    defense: false,
+  });
+  assert_eq!(analysis.best_move, None);
+  assert_eq!(analysis.estimation, 0);
+}
+
+fn defend(ladders: Ladders) -> (Field, SingleAnalysis<i32, ()>) {
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+  let mut field = construct_field(&mut rng, LADDER);
+  let mut ladders = ladders;
+  let analysis = ladders.analyze(&mut rng, &mut field, Player::Black, None, &|| false);
+  (field, analysis)
+}
+
+#[test]
+fn ladders_defense() {
+  let (field, analysis) = defend(Ladders {
+    score_limit: 3,
+    depth_limit: 5,
+    defense: true,
+  });
+  assert_eq!(analysis.best_move, NonZeroPos::new(field.to_pos(3, 3)));
+  assert_eq!(analysis.estimation, 0);
+}
+
+#[test]
+fn ladders_defense_disabled() {
+  let (_, analysis) = defend(Ladders {
+    score_limit: 0,
+    depth_limit: 0,
+    defense: false,
+  });
+  assert_eq!(analysis.best_move, None);
+  assert_eq!(analysis.estimation, 0);
+}
+
+#[test]
+fn ladders_defense_score_limit() {
+  let (_, analysis) = defend(Ladders {
+    score_limit: 4,
+    depth_limit: 0,
+    defense: true,
+  });
+  assert_eq!(analysis.best_move, None);
+  assert_eq!(analysis.estimation, 0);
+}
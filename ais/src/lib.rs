@@ -1,10 +1,24 @@
+pub mod adjudication;
+#[cfg(test)]
+mod adjudication_test;
 pub mod cli;
+pub mod ensemble;
+#[cfg(test)]
+mod ensemble_test;
+pub mod evaluation;
 pub mod heuristic;
 pub mod initial;
 pub mod ladders;
+#[cfg(test)]
+mod ladders_test;
 pub mod minimax;
 pub mod oppai;
 pub mod patterns;
+pub mod playout;
+pub mod prior;
+pub mod strength;
+#[cfg(test)]
+mod strength_test;
 pub mod time_limited_ai;
 pub mod uct;
 pub mod zero;
@@ -1,12 +1,13 @@
-use oppai_ai::{ai::AI, analysis::SingleAnalysis};
+use crate::evaluation::Evaluation;
+use oppai_ai::{ai::AI, analysis::SimpleAnalysis};
 use oppai_field::{field::Field, player::Player};
 use oppai_minimax::minimax::Minimax as InnerMinimax;
 use std::any::TypeId;
 
-pub struct Minimax(pub InnerMinimax);
+pub struct Minimax(pub InnerMinimax<Evaluation>);
 
 impl AI for Minimax {
-  type Analysis = SingleAnalysis<i32, u32>;
+  type Analysis = SimpleAnalysis<i32, i32, u32>;
   type Confidence = u32;
 
   fn analyze<S, R, SS>(
@@ -20,15 +21,31 @@ impl AI for Minimax {
   where
     SS: Fn() -> bool + Sync,
   {
-    let (pos, estimation, confidence) = match confidence {
-      Some(confidence) => {
-        let (pos, estimation) = self.0.minimax(field, player, confidence, should_stop);
-        (pos, estimation, confidence)
-      }
-      None => self.0.minimax_with_time(field, player, should_stop),
+    let (moves, estimation, confidence) = if self.0.config().multi_pv > 1 {
+      let (moves, confidence) = match confidence {
+        Some(confidence) => (self.0.multi_pv(field, player, confidence, should_stop), confidence),
+        None => self.0.multi_pv_with_time(field, player, should_stop),
+      };
+      let estimation = moves
+        .first()
+        .map_or_else(|| field.score(player), |&(_, estimation)| estimation);
+      (moves, estimation, confidence)
+    } else {
+      let (pos, estimation, confidence) = match confidence {
+        Some(confidence) => {
+          let (pos, estimation) = self.0.minimax(field, player, confidence, should_stop);
+          (pos, estimation, confidence)
+        }
+        None => self.0.minimax_with_time(field, player, should_stop),
+      };
+      (
+        pos.into_iter().map(|pos| (pos.get(), estimation)).collect(),
+        estimation,
+        confidence,
+      )
     };
-    SingleAnalysis {
-      best_move: pos,
+    SimpleAnalysis {
+      moves,
       estimation,
       confidence,
       origin: TypeId::of::<Self>(),
@@ -1,6 +1,18 @@
 use crate::{
-  heuristic::Heuristic, initial::Initial, ladders::Ladders, minimax::Minimax, patterns::Patterns,
-  time_limited_ai::TimeLimitedAI, uct::Uct, zero::Zero,
+  adjudication::{AdjudicationConfig, Assessment},
+  ensemble::{Ensemble, EnsembleAnalysis, Veto},
+  evaluation::{Evaluation, EvaluationType},
+  heuristic::Heuristic,
+  initial::Initial,
+  ladders::Ladders,
+  minimax::Minimax,
+  patterns::Patterns,
+  playout::{HeuristicPolicy, NeuralPolicy, Playout, PlayoutType},
+  prior::{HeuristicPrior, NeuralPrior, Prior, PriorType},
+  strength::{sample_move, Strength},
+  time_limited_ai::TimeLimitedAI,
+  uct::Uct,
+  zero::Zero,
 };
 use either::Either;
 use num_traits::Float;
@@ -9,12 +21,20 @@ use oppai_ai::{
   analysis::{Analysis, FlatAnalysis, SimpleAnalysis, SingleAnalysis},
 };
 use oppai_field::{
-  field::{length, Field},
+  field::{length, Field, Pos},
   player::Player,
 };
-use oppai_minimax::minimax::{Minimax as InnerMinimax, MinimaxConfig};
+use oppai_minimax::{
+  evaluation::{EvaluationWeights, MaterialEvaluation, PositionalEvaluation},
+  hash_table::HashTable,
+  minimax::{Minimax as InnerMinimax, MinimaxConfig},
+};
 use oppai_patterns::patterns::Patterns as InnerPatterns;
-use oppai_uct::uct::{UctConfig, UctRoot};
+use oppai_uct::{
+  playout::{GuidedPolicy, UniformPolicy},
+  prior::{NoPrior, PatternPrior},
+  uct::{UctConfig, UctRoot, UctTreeInfo},
+};
 use oppai_zero::{model::Model, zero::Zero as InnerZero};
 use rand::{distributions::Standard, prelude::Distribution, Rng, SeedableRng};
 use std::{
@@ -32,6 +52,7 @@ pub enum Solver {
   Minimax,
   Uct,
   Zero,
+  Ensemble,
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -39,10 +60,29 @@ pub struct Config {
   pub uct: UctConfig,
   pub minimax: MinimaxConfig,
   pub solver: Solver,
+  pub playout_type: PlayoutType,
+  pub prior_type: PriorType,
+  pub evaluation_type: EvaluationType,
+  pub evaluation_weights: EvaluationWeights,
+  /// Factor of UCT weights in the ensemble.
+  pub ensemble_uct_factor: f64,
+  /// Factor of Zero weights in the ensemble.
+  pub ensemble_zero_factor: f64,
+  /// Minimal score gain or loss found by Minimax for it to veto the ensemble
+  /// moves.
+  pub ensemble_veto_margin: i32,
   pub ladders: bool,
   pub ladders_score_limit: u32,
   pub ladders_depth_limit: u32,
+  /// Defend against enemy ladders when there is no own ladder to play. The
+  /// defense move is played without the main solver, so it's off by default.
+  pub ladders_defense: bool,
   pub ladders_time_limit: Duration,
+  pub strength: Strength,
+  /// Seed for reproducible analysis. The search algorithms should be
+  /// configured as deterministic, and ladders aren't limited by time.
+  pub seed: Option<u64>,
+  pub adjudication: AdjudicationConfig,
 }
 
 impl Default for Config {
@@ -51,10 +91,21 @@ impl Default for Config {
       uct: Default::default(),
       minimax: Default::default(),
       solver: Solver::Uct,
+      playout_type: PlayoutType::Uniform,
+      prior_type: PriorType::None,
+      evaluation_type: EvaluationType::Material,
+      evaluation_weights: Default::default(),
+      ensemble_uct_factor: 1.0,
+      ensemble_zero_factor: 1.0,
+      ensemble_veto_margin: 1,
       ladders: true,
       ladders_score_limit: 0,
       ladders_depth_limit: 0,
+      ladders_defense: false,
       ladders_time_limit: Duration::from_secs(1),
+      strength: Strength::Full,
+      seed: None,
+      adjudication: Default::default(),
     }
   }
 }
@@ -66,6 +117,17 @@ pub struct InConfidence {
   pub zero_iterations: usize,
 }
 
+impl InConfidence {
+  /// Lowest confidence of the two for every engine.
+  pub fn min(self, other: InConfidence) -> InConfidence {
+    InConfidence {
+      minimax_depth: self.minimax_depth.min(other.minimax_depth),
+      uct_iterations: self.uct_iterations.min(other.uct_iterations),
+      zero_iterations: self.zero_iterations.min(other.zero_iterations),
+    }
+  }
+}
+
 pub struct Oppai<N: Float + Sum + Display + Debug, M: Model<N>> {
   config: Config,
   initial: Initial,
@@ -73,10 +135,12 @@ pub struct Oppai<N: Float + Sum + Display + Debug, M: Model<N>> {
   ladders: Ladders,
   heuristic: Heuristic,
   minimax: Minimax,
-  uct: Uct,
+  uct: Uct<Playout<N, M>, Prior<N, M>>,
   zero: Zero<N, M>,
 }
 
+type UctZeroAnalysis<N> = EnsembleAnalysis<SimpleAnalysis<f64, f64, usize>, SimpleAnalysis<u64, N, usize>>;
+
 type InnerAnalysis<N> = Either<
   SingleAnalysis<(), ()>,
   Either<
@@ -84,8 +148,11 @@ type InnerAnalysis<N> = Either<
     Either<
       SingleAnalysis<i32, ()>,
       Either<
-        Either<SimpleAnalysis<i32, (), ()>, Either<SingleAnalysis<i32, u32>, SimpleAnalysis<i32, (), ()>>>,
-        Either<SimpleAnalysis<f64, f64, usize>, SimpleAnalysis<u64, N, usize>>,
+        Either<SimpleAnalysis<i32, (), ()>, Either<SimpleAnalysis<i32, i32, u32>, SimpleAnalysis<i32, (), ()>>>,
+        Either<
+          SimpleAnalysis<f64, f64, usize>,
+          Either<SimpleAnalysis<u64, N, usize>, EnsembleAnalysis<SimpleAnalysis<i32, i32, u32>, UctZeroAnalysis<N>>>,
+        >,
       >,
     >,
   >,
@@ -101,10 +168,11 @@ impl<N: Float + Sum + Display + Debug + 'static> OppaiWeight<N> {
       Either::Right(Either::Left(())) => None,
       Either::Right(Either::Right(Either::Left(()))) => None,
       Either::Right(Either::Right(Either::Right(Either::Left(Either::Left(w))))) => Some(w as f64),
-      Either::Right(Either::Right(Either::Right(Either::Left(Either::Right(Either::Left(())))))) => None,
+      Either::Right(Either::Right(Either::Right(Either::Left(Either::Right(Either::Left(w)))))) => Some(w as f64),
       Either::Right(Either::Right(Either::Right(Either::Left(Either::Right(Either::Right(w)))))) => Some(w as f64),
       Either::Right(Either::Right(Either::Right(Either::Right(Either::Left(w))))) => Some(w),
-      Either::Right(Either::Right(Either::Right(Either::Right(Either::Right(w))))) => Some(w as f64),
+      Either::Right(Either::Right(Either::Right(Either::Right(Either::Right(Either::Left(w)))))) => Some(w as f64),
+      Either::Right(Either::Right(Either::Right(Either::Right(Either::Right(Either::Right(w)))))) => Some(w),
     }
   }
 }
@@ -122,7 +190,8 @@ impl<N: Float + Sum + Display + Debug + 'static> OppaiEstimation<N> {
       Either::Right(Either::Right(Either::Right(Either::Left(Either::Right(Either::Left(e)))))) => Some(e as f64),
       Either::Right(Either::Right(Either::Right(Either::Left(Either::Right(Either::Right(())))))) => None,
       Either::Right(Either::Right(Either::Right(Either::Right(Either::Left(e))))) => Some(e),
-      Either::Right(Either::Right(Either::Right(Either::Right(Either::Right(e))))) => e.to_f64(),
+      Either::Right(Either::Right(Either::Right(Either::Right(Either::Right(Either::Left(e)))))) => e.to_f64(),
+      Either::Right(Either::Right(Either::Right(Either::Right(Either::Right(Either::Right((_, (e, _)))))))) => Some(e),
     }
   }
 }
@@ -140,36 +209,88 @@ impl<N: Float + Sum + Display + Debug + 'static> OppaiConfidence<N> {
       Either::Right(Either::Right(Either::Right(Either::Left(Either::Right(Either::Left(c)))))) => Some(c as f64),
       Either::Right(Either::Right(Either::Right(Either::Left(Either::Right(Either::Right(())))))) => None,
       Either::Right(Either::Right(Either::Right(Either::Right(Either::Left(c))))) => Some(c as f64),
-      Either::Right(Either::Right(Either::Right(Either::Right(Either::Right(c))))) => Some(c as f64),
+      Either::Right(Either::Right(Either::Right(Either::Right(Either::Right(Either::Left(c)))))) => Some(c as f64),
+      Either::Right(Either::Right(Either::Right(Either::Right(Either::Right(Either::Right((_, (c, _)))))))) => {
+        Some(c as f64)
+      }
     }
   }
 }
 
-pub struct OppaiAnalysis<N: Float + Sum + Display + Debug + 'static>(InnerAnalysis<N>);
+pub struct OppaiAnalysis<N: Float + Sum + Display + Debug + 'static> {
+  analysis: InnerAnalysis<N>,
+  /// Move sampled according to the strength level. When present it's the only
+  /// suggested move.
+  sampled_move: Option<Pos>,
+}
+
+impl<N: Float + Sum + Display + Debug + 'static> OppaiAnalysis<N> {
+  /// Assessment of the position for the player to move. UCT and Zero estimate
+  /// the position from the point of view of the player who made the last move,
+  /// so their estimations are inverted.
+  pub fn assessment(&self) -> Assessment {
+    match &self.analysis {
+      Either::Right(Either::Right(Either::Left(ladders))) => Assessment::Score(ladders.estimation),
+      Either::Right(Either::Right(Either::Right(Either::Left(Either::Right(Either::Left(minimax)))))) => {
+        Assessment::Score(minimax.estimation)
+      }
+      Either::Right(Either::Right(Either::Right(Either::Right(Either::Left(uct))))) if uct.confidence > 0 => {
+        Assessment::Winrate(1.0 - uct.estimation)
+      }
+      Either::Right(Either::Right(Either::Right(Either::Right(Either::Right(Either::Left(zero))))))
+        if zero.confidence > 0 =>
+      {
+        zero
+          .estimation
+          .to_f64()
+          .map_or(Assessment::Unknown, |value| Assessment::Winrate((1.0 - value) / 2.0))
+      }
+      Either::Right(Either::Right(Either::Right(Either::Right(Either::Right(Either::Right(ensemble)))))) => {
+        let uct = ensemble.second().first();
+        if !ensemble.first().is_empty() {
+          Assessment::Score(ensemble.first().estimation)
+        } else if uct.confidence > 0 {
+          Assessment::Winrate(1.0 - uct.estimation)
+        } else {
+          Assessment::Unknown
+        }
+      }
+      _ => Assessment::Unknown,
+    }
+  }
+}
 
 impl<N: Float + Sum + Display + Debug + 'static> Analysis for OppaiAnalysis<N> {
   type Weight = OppaiWeight<N>;
   type Estimation = OppaiEstimation<N>;
   type Confidence = OppaiConfidence<N>;
 
-  fn moves(&self) -> impl Iterator<Item = (oppai_field::field::Pos, Self::Weight)> {
-    self.0.moves().map(|(pos, weight)| (pos, OppaiWeight(weight)))
+  fn moves(&self) -> impl Iterator<Item = (Pos, Self::Weight)> {
+    self
+      .analysis
+      .moves()
+      .filter(|&(pos, _)| self.sampled_move.is_none_or(|sampled_move| sampled_move == pos))
+      .map(|(pos, weight)| (pos, OppaiWeight(weight)))
   }
 
   fn estimation(&self) -> Self::Estimation {
-    OppaiEstimation(self.0.estimation())
+    OppaiEstimation(self.analysis.estimation())
   }
 
   fn confidence(&self) -> Self::Confidence {
-    OppaiConfidence(self.0.confidence())
+    OppaiConfidence(self.analysis.confidence())
   }
 
   fn origin(&self) -> std::any::TypeId {
-    self.0.origin()
+    self.analysis.origin()
   }
 }
 
-impl<N: Float + Sum + Display + Debug + 'static, M: Model<N> + 'static> AI for Oppai<N, M> {
+impl<N, M> AI for Oppai<N, M>
+where
+  N: Float + Sum + Display + Debug + 'static,
+  M: Model<N> + Send + Sync + 'static,
+{
   type Analysis = OppaiAnalysis<N>;
   type Confidence = InConfidence;
 
@@ -186,14 +307,35 @@ impl<N: Float + Sum + Display + Debug + 'static, M: Model<N> + 'static> AI for O
     Standard: Distribution<S>,
     SS: Fn() -> bool + Sync,
   {
+    let limits = self.config.strength.limits();
+    let ladders = self.config.ladders
+      && limits
+        .as_ref()
+        .is_none_or(|limits| !rng.gen_bool(limits.ladders_skip_probability));
+
     let ai = match self.config.solver {
       Solver::Heuristic => Either::Left(Either::Left(&mut self.heuristic)),
       Solver::Minimax => Either::Left(Either::Right((&mut self.minimax, &mut self.heuristic))),
       Solver::Uct => Either::Right(Either::Left(&mut self.uct)),
-      Solver::Zero => Either::Right(Either::Right(&mut self.zero)),
+      Solver::Zero => Either::Right(Either::Right(Either::Left(&mut self.zero))),
+      Solver::Ensemble => Either::Right(Either::Right(Either::Right(Veto {
+        veto: &mut self.minimax,
+        ai: Ensemble {
+          first: &mut self.uct,
+          second: &mut self.zero,
+          first_factor: self.config.ensemble_uct_factor,
+          second_factor: self.config.ensemble_zero_factor,
+        },
+        margin: self.config.ensemble_veto_margin,
+      }))),
+    };
+    let ladders_time_limit = if self.config.seed.is_some() {
+      Duration::MAX
+    } else {
+      self.config.ladders_time_limit
     };
-    let ai = if self.config.ladders {
-      Either::Left((TimeLimitedAI(self.config.ladders_time_limit, self.ladders), ai))
+    let ai = if ladders {
+      Either::Left((TimeLimitedAI(ladders_time_limit, self.ladders), ai))
     } else {
       Either::Right(ai)
     }
@@ -201,6 +343,11 @@ impl<N: Float + Sum + Display + Debug + 'static, M: Model<N> + 'static> AI for O
     let ai = (&mut self.patterns, ai);
     let mut ai = (self.initial, ai);
 
+    let confidence = match (confidence, limits.as_ref()) {
+      (Some(confidence), Some(limits)) => Some(confidence.min(limits.confidence.clone())),
+      (None, Some(limits)) => Some(limits.confidence.clone()),
+      (confidence, None) => confidence,
+    };
     let confidence = confidence.map(|confidence| {
       (
         (),
@@ -208,32 +355,99 @@ impl<N: Float + Sum + Display + Debug + 'static, M: Model<N> + 'static> AI for O
           (),
           (
             ((), (confidence.minimax_depth, ())),
-            (confidence.uct_iterations, confidence.zero_iterations),
+            (
+              confidence.uct_iterations,
+              (
+                confidence.zero_iterations,
+                (
+                  confidence.minimax_depth,
+                  (confidence.uct_iterations, confidence.zero_iterations),
+                ),
+              ),
+            ),
           ),
         ),
       )
     });
 
-    OppaiAnalysis(ai.analyze(rng, field, player, confidence, should_stop))
+    let analysis = ai.analyze(rng, field, player, confidence, should_stop);
+    let sampled_move = limits.and_then(|limits| {
+      sample_move(
+        analysis
+          .moves()
+          .map(|(pos, weight)| (pos, OppaiWeight::<N>(weight).to_f64())),
+        limits.temperature,
+        rng,
+      )
+    });
+
+    OppaiAnalysis { analysis, sampled_move }
   }
 }
 
-impl<N: Float + Sum + Display + Debug + 'static, M: Model<N> + 'static> Oppai<N, M> {
+impl<N, M> Oppai<N, M>
+where
+  N: Float + Sum + Display + Debug + 'static,
+  M: Model<N> + Clone + Send + Sync + 'static,
+{
   pub fn new(width: u32, height: u32, config: Config, patterns: Arc<InnerPatterns>, model: M) -> Self {
     let minimax_config = config.minimax.clone();
     let uct_config = config.uct.clone();
+    let ladders = Ladders {
+      score_limit: config.ladders_score_limit,
+      depth_limit: config.ladders_depth_limit,
+      defense: config.ladders_defense,
+    };
+    let playout = match config.playout_type {
+      PlayoutType::Uniform => Playout::Uniform(UniformPolicy),
+      PlayoutType::Guided => Playout::Guided(GuidedPolicy::new(patterns.clone())),
+      PlayoutType::Heuristic => Playout::Heuristic(HeuristicPolicy),
+      PlayoutType::Neural => Playout::Neural(NeuralPolicy::new(Arc::new(model.clone()))),
+    };
+    let prior = match config.prior_type {
+      PriorType::None => Prior::None(NoPrior),
+      PriorType::Patterns => Prior::Patterns(PatternPrior::new(patterns.clone())),
+      PriorType::Heuristic => Prior::Heuristic(HeuristicPrior),
+      PriorType::Neural => Prior::Neural(NeuralPrior::new(Arc::new(model.clone()))),
+    };
+    let evaluation = match config.evaluation_type {
+      EvaluationType::Material => Evaluation::Material(MaterialEvaluation),
+      EvaluationType::Positional => Evaluation::Positional(PositionalEvaluation::new(config.evaluation_weights)),
+    };
     Oppai {
       config,
       initial: Initial,
-      patterns: Patterns(patterns),
-      ladders: Ladders,
+      patterns: Patterns(patterns.clone()),
+      ladders,
       heuristic: Heuristic,
-      minimax: Minimax(InnerMinimax::new(minimax_config)),
-      uct: Uct(UctRoot::new(uct_config, length(width, height))),
+      minimax: Minimax(InnerMinimax::with_evaluation(minimax_config, evaluation)),
+      uct: Uct(UctRoot::with_policies(
+        uct_config,
+        length(width, height),
+        playout,
+        prior,
+      )),
       zero: Zero(InnerZero::new(model)),
     }
   }
 
+  /// Hash table of the Minimax search.
+  pub fn minimax_hash_table(&self) -> &Arc<HashTable> {
+    self.minimax.0.hash_table()
+  }
+
+  /// Makes the Minimax search use the given hash table, e.g. to share it with
+  /// another instance. Returns `false` if the table is used by an evaluation
+  /// with another scale.
+  pub fn set_minimax_hash_table(&mut self, hash_table: Arc<HashTable>) -> bool {
+    self.minimax.0.set_hash_table(hash_table)
+  }
+
+  /// Statistics of the UCT tree from the last analysis.
+  pub fn inspect_uct(&self, depth: u32) -> Option<UctTreeInfo> {
+    self.uct.0.inspect(depth)
+  }
+
   // pub fn weight_descr(weight: <<Self as AI>::Analysis as Analysis>::Weight) -> (String, f32) {
   //   todo!()
   // }
@@ -0,0 +1,119 @@
+use crate::prior::{heuristic_weights, neural_weights};
+use num_traits::Float;
+use oppai_field::{
+  field::{Field, Pos},
+  player::Player,
+};
+use oppai_uct::playout::{is_playout_move_allowed, GuidedPolicy, PlayoutPolicy, UniformPolicy};
+use oppai_zero::model::Model;
+use rand::{distributions::WeightedIndex, prelude::Distribution, seq::SliceRandom, Rng};
+use std::{marker::PhantomData, sync::Arc};
+use strum::{EnumString, VariantNames};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, EnumString, VariantNames)]
+pub enum PlayoutType {
+  /// Moves are played in random order.
+  Uniform,
+  /// Captures, escapes, patterns and moves near the last one are preferred.
+  Guided,
+  /// Moves are sampled according to their heuristic estimation.
+  Heuristic,
+  /// Moves are sampled according to the Zero policy network. It's evaluated
+  /// on every move, so playouts are orders of magnitude slower.
+  Neural,
+}
+
+/// Plays the game till the end sampling every move among allowed
+/// `possible_moves` with weights returned by `weights`. When weights can't be
+/// computed the rest of the moves are played in random order.
+fn play_weighted_game<R, F>(
+  field: &mut Field,
+  player: Player,
+  possible_moves: &mut [Pos],
+  rng: &mut R,
+  mut weights: F,
+) -> i32
+where
+  R: Rng,
+  F: FnMut(&Field, Player, &[Pos]) -> Option<Vec<f64>>,
+{
+  let mut cur_player = player;
+  loop {
+    let moves = possible_moves
+      .iter()
+      .copied()
+      .filter(|&pos| is_playout_move_allowed(field, pos))
+      .collect::<Vec<_>>();
+    if moves.is_empty() {
+      break;
+    }
+    let pos = match weights(field, cur_player, &moves).and_then(|weights| WeightedIndex::new(weights).ok()) {
+      Some(distribution) => moves[distribution.sample(rng)],
+      None => *moves.choose(rng).unwrap(),
+    };
+    field.put_point(pos, cur_player);
+    cur_player = cur_player.next();
+  }
+  field.score(player)
+}
+
+/// Samples moves with probabilities proportional to the exponent of their
+/// heuristic estimation.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct HeuristicPolicy;
+
+impl PlayoutPolicy for HeuristicPolicy {
+  fn play<R: Rng>(&self, field: &mut Field, player: Player, possible_moves: &mut [Pos], rng: &mut R) -> i32 {
+    play_weighted_game(field, player, possible_moves, rng, |field, player, moves| {
+      Some(heuristic_weights(field, player, moves))
+    })
+  }
+}
+
+/// Samples moves according to the policy of the Zero model.
+pub struct NeuralPolicy<N, M> {
+  model: Arc<M>,
+  phantom: PhantomData<fn() -> N>,
+}
+
+impl<N, M> NeuralPolicy<N, M> {
+  pub fn new(model: Arc<M>) -> Self {
+    NeuralPolicy {
+      model,
+      phantom: PhantomData,
+    }
+  }
+}
+
+impl<N, M> Clone for NeuralPolicy<N, M> {
+  fn clone(&self) -> Self {
+    NeuralPolicy::new(self.model.clone())
+  }
+}
+
+impl<N: Float, M: Model<N> + Send + Sync> PlayoutPolicy for NeuralPolicy<N, M> {
+  fn play<R: Rng>(&self, field: &mut Field, player: Player, possible_moves: &mut [Pos], rng: &mut R) -> i32 {
+    play_weighted_game(field, player, possible_moves, rng, |field, player, moves| {
+      neural_weights(self.model.as_ref(), field, player, moves)
+    })
+  }
+}
+
+/// Playout policy selected by `PlayoutType`.
+pub enum Playout<N, M> {
+  Uniform(UniformPolicy),
+  Guided(GuidedPolicy),
+  Heuristic(HeuristicPolicy),
+  Neural(NeuralPolicy<N, M>),
+}
+
+impl<N: Float, M: Model<N> + Send + Sync> PlayoutPolicy for Playout<N, M> {
+  fn play<R: Rng>(&self, field: &mut Field, player: Player, possible_moves: &mut [Pos], rng: &mut R) -> i32 {
+    match self {
+      Playout::Uniform(policy) => policy.play(field, player, possible_moves, rng),
+      Playout::Guided(policy) => policy.play(field, player, possible_moves, rng),
+      Playout::Heuristic(policy) => policy.play(field, player, possible_moves, rng),
+      Playout::Neural(policy) => policy.play(field, player, possible_moves, rng),
+    }
+  }
+}
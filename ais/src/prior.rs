@@ -0,0 +1,116 @@
+use crate::heuristic::heuristic_estimation;
+use ndarray::Axis;
+use num_traits::Float;
+use oppai_field::{
+  field::{Field, Pos},
+  player::Player,
+};
+use oppai_uct::prior::{NoPrior, PatternPrior, PriorPolicy};
+use oppai_zero::{field_features::field_features, model::Model};
+use std::{marker::PhantomData, sync::Arc};
+use strum::{EnumString, VariantNames};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, EnumString, VariantNames)]
+pub enum PriorType {
+  /// All moves are equal.
+  None,
+  /// Moves matched by patterns near the last move are preferred.
+  Patterns,
+  /// Moves are weighted by their heuristic estimation.
+  Heuristic,
+  /// Moves are weighted by the Zero policy network.
+  Neural,
+}
+
+/// Temperature of the softmax over heuristic estimations.
+const HEURISTIC_TEMPERATURE: f64 = 10.0;
+
+/// Softmax over heuristic estimations of `moves`.
+pub(crate) fn heuristic_weights(field: &Field, player: Player, moves: &[Pos]) -> Vec<f64> {
+  let estimations = moves
+    .iter()
+    .map(|&pos| heuristic_estimation(field, pos, player) as f64)
+    .collect::<Vec<_>>();
+  let max = estimations.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+  estimations
+    .into_iter()
+    .map(|estimation| ((estimation - max) / HEURISTIC_TEMPERATURE).exp())
+    .collect()
+}
+
+/// Policy of the Zero model for `moves`.
+pub(crate) fn neural_weights<N: Float, M: Model<N>>(
+  model: &M,
+  field: &Field,
+  player: Player,
+  moves: &[Pos],
+) -> Option<Vec<f64>> {
+  let features = field_features::<N>(field, player, 0).insert_axis(Axis(0));
+  let (policies, _) = model.predict(features).ok()?;
+  Some(
+    moves
+      .iter()
+      .map(|&pos| {
+        policies[(0, field.to_y(pos) as usize, field.to_x(pos) as usize)]
+          .to_f64()
+          .unwrap_or(0.0)
+      })
+      .collect(),
+  )
+}
+
+/// Weights moves by their heuristic estimation.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct HeuristicPrior;
+
+impl PriorPolicy for HeuristicPrior {
+  fn priors(&self, field: &Field, player: Player, moves: &[Pos]) -> Option<Vec<f64>> {
+    Some(heuristic_weights(field, player, moves))
+  }
+}
+
+/// Weights moves by the policy of the Zero model.
+pub struct NeuralPrior<N, M> {
+  model: Arc<M>,
+  phantom: PhantomData<fn() -> N>,
+}
+
+impl<N, M> NeuralPrior<N, M> {
+  pub fn new(model: Arc<M>) -> Self {
+    NeuralPrior {
+      model,
+      phantom: PhantomData,
+    }
+  }
+}
+
+impl<N, M> Clone for NeuralPrior<N, M> {
+  fn clone(&self) -> Self {
+    NeuralPrior::new(self.model.clone())
+  }
+}
+
+impl<N: Float, M: Model<N> + Send + Sync> PriorPolicy for NeuralPrior<N, M> {
+  fn priors(&self, field: &Field, player: Player, moves: &[Pos]) -> Option<Vec<f64>> {
+    neural_weights(self.model.as_ref(), field, player, moves)
+  }
+}
+
+/// Prior policy selected by `PriorType`.
+pub enum Prior<N, M> {
+  None(NoPrior),
+  Patterns(PatternPrior),
+  Heuristic(HeuristicPrior),
+  Neural(NeuralPrior<N, M>),
+}
+
+impl<N: Float, M: Model<N> + Send + Sync> PriorPolicy for Prior<N, M> {
+  fn priors(&self, field: &Field, player: Player, moves: &[Pos]) -> Option<Vec<f64>> {
+    match self {
+      Prior::None(prior) => prior.priors(field, player, moves),
+      Prior::Patterns(prior) => prior.priors(field, player, moves),
+      Prior::Heuristic(prior) => prior.priors(field, player, moves),
+      Prior::Neural(prior) => prior.priors(field, player, moves),
+    }
+  }
+}
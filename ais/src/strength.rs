@@ -0,0 +1,79 @@
+use crate::oppai::InConfidence;
+use oppai_field::field::Pos;
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+use strum::{EnumString, VariantNames};
+
+/// Playing strength of the engine. Weaker levels limit the search and add
+/// randomness to the move choice so that the engine makes natural-looking
+/// mistakes instead of random blunders.
+///
+/// The presets are calibrated with `oppai-versus` on one thread, 1 second per
+/// move and default UCT: each level scores 0.90-0.94 against the previous one
+/// in 40 games. Strength depends on the temperature the most, so it gets
+/// smaller steps for stronger levels. Minimax and Zero limits aren't calibrated.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, EnumString, VariantNames)]
+pub enum Strength {
+  Beginner,
+  Casual,
+  Intermediate,
+  Advanced,
+  Full,
+}
+
+#[derive(Clone, Debug)]
+pub struct StrengthLimits {
+  /// Temperature for sampling from the analysis moves. Zero means that the
+  /// best move is always chosen.
+  pub temperature: f64,
+  /// Upper bounds for the analysis confidence.
+  pub confidence: InConfidence,
+  /// Probability to skip the ladders solver for a move.
+  pub ladders_skip_probability: f64,
+}
+
+impl Strength {
+  pub fn limits(self) -> Option<StrengthLimits> {
+    let (temperature, minimax_depth, uct_iterations, zero_iterations, ladders_skip_probability) = match self {
+      Strength::Beginner => (0.5, 2, 2_000, 20, 0.75),
+      Strength::Casual => (0.15, 4, 10_000, 100, 0.5),
+      Strength::Intermediate => (0.08, 6, 50_000, 300, 0.25),
+      Strength::Advanced => (0.04, 8, 200_000, 600, 0.1),
+      Strength::Full => return None,
+    };
+    Some(StrengthLimits {
+      temperature,
+      confidence: InConfidence {
+        minimax_depth,
+        uct_iterations,
+        zero_iterations,
+      },
+      ladders_skip_probability,
+    })
+  }
+}
+
+/// Samples a move from the analysis moves with the given temperature.
+///
+/// Weights are normalized to `[0, 1]` first since different engines use
+/// different scales for them. Returns `None` if sampling is impossible, i.e.
+/// there are no moves or some of them don't have a numeric weight.
+pub fn sample_move<R: Rng, I: Iterator<Item = (Pos, Option<f64>)>>(
+  moves: I,
+  temperature: f64,
+  rng: &mut R,
+) -> Option<Pos> {
+  if temperature <= 0.0 {
+    return None;
+  }
+  let moves = moves
+    .map(|(pos, weight)| weight.map(|weight| (pos, weight)))
+    .collect::<Option<Vec<_>>>()?;
+  let min = moves.iter().map(|&(_, weight)| weight).reduce(f64::min)?;
+  let max = moves.iter().map(|&(_, weight)| weight).reduce(f64::max)?;
+  let range = if max > min { max - min } else { 1.0 };
+  let weights = moves
+    .iter()
+    .map(|&(_, weight)| ((weight - max) / range / temperature).exp());
+  let distribution = WeightedIndex::new(weights).ok()?;
+  Some(moves[distribution.sample(rng)].0)
+}
@@ -0,0 +1,60 @@
+use crate::strength::sample_move;
+use rand::SeedableRng;
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+const SEED: u64 = 7;
+
+#[test]
+fn sample_move_zero_temperature() {
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+  let moves = [(1, Some(0.5)), (2, Some(0.7))];
+  assert_eq!(sample_move(moves.into_iter(), 0.0, &mut rng), None);
+}
+
+#[test]
+fn sample_move_missing_weight() {
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+  let moves = [(1, Some(0.5)), (2, None)];
+  assert_eq!(sample_move(moves.into_iter(), 0.5, &mut rng), None);
+}
+
+#[test]
+fn sample_move_nan_weight() {
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+  let moves = [(1, Some(0.5)), (2, Some(f64::NAN))];
+  assert_eq!(sample_move(moves.into_iter(), 0.5, &mut rng), None);
+}
+
+#[test]
+fn sample_move_no_moves() {
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+  assert_eq!(sample_move(std::iter::empty(), 0.5, &mut rng), None);
+}
+
+#[test]
+fn sample_move_single() {
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+  for _ in 0..10 {
+    assert_eq!(sample_move([(3, Some(-2.0))].into_iter(), 1.0, &mut rng), Some(3));
+  }
+}
+
+#[test]
+fn sample_move_low_temperature() {
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+  let moves = [(1, Some(0.0)), (2, Some(1.0)), (3, Some(0.2))];
+  for _ in 0..100 {
+    assert_eq!(sample_move(moves.into_iter(), 0.01, &mut rng), Some(2));
+  }
+}
+
+#[test]
+fn sample_move_high_temperature() {
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+  let moves = [(1, Some(0.0)), (2, Some(1.0))];
+  let worse = (0..1000)
+    .filter(|_| sample_move(moves.into_iter(), 10.0, &mut rng) == Some(1))
+    .count();
+  // Weights differ by exp(0.1) at this temperature.
+  assert!((400..500).contains(&worse));
+}
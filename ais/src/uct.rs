@@ -2,12 +2,12 @@ use std::any::TypeId;
 
 use oppai_ai::{ai::AI, analysis::SimpleAnalysis};
 use oppai_field::{field::Field, player::Player};
-use oppai_uct::uct::UctRoot;
+use oppai_uct::{playout::PlayoutPolicy, prior::PriorPolicy, uct::UctRoot};
 use rand::{distributions::Standard, prelude::Distribution, Rng, SeedableRng};
 
-pub struct Uct(pub UctRoot);
+pub struct Uct<P, Q>(pub UctRoot<P, Q>);
 
-impl AI for Uct {
+impl<P: PlayoutPolicy + 'static, Q: PriorPolicy + 'static> AI for Uct<P, Q> {
   type Analysis = SimpleAnalysis<f64, f64, usize>;
   type Confidence = usize;
 
@@ -8,6 +8,7 @@ pub struct Config {
   pub ai: AIConfig,
   pub patterns: Vec<String>,
   pub patterns_cache: Option<String>,
+  pub hash_table_file: Option<String>,
   pub uct_iterations: usize,
   pub minimax_depth: u32,
   pub time_gap: Duration,
@@ -19,7 +20,7 @@ pub fn cli_parse() -> Config {
     .author(clap::crate_authors!("\n"))
     .about(clap::crate_description!())
     .groups(groups())
-    .args(&args())
+    .args(args())
     .arg(
       Arg::new("patterns-file")
         .short('p')
@@ -34,6 +35,15 @@ pub fn cli_parse() -> Config {
         .help("Patterns cache file to use")
         .num_args(1),
     )
+    .arg(
+      Arg::new("hash-table-file")
+        .long("hash-table-file")
+        .help(
+          "Minimax hash table file to load on start of a game and to save when the game is over or on exit. \
+         It's used only for the same field size, seed and evaluation",
+        )
+        .num_args(1),
+    )
     .arg(
       Arg::new("minimax-depth")
         .long("minimax-depth")
@@ -72,6 +82,7 @@ pub fn cli_parse() -> Config {
       .get_many("patterns-file")
       .map_or_else(Vec::new, |patterns| patterns.cloned().collect()),
     patterns_cache: matches.get_one("patterns-cache-file").cloned(),
+    hash_table_file: matches.get_one("hash-table-file").cloned(),
     uct_iterations: matches.get_one("uct-iterations").copied().unwrap(),
     minimax_depth: matches.get_one("minimax-depth").copied().unwrap(),
     time_gap: matches
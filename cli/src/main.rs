@@ -5,31 +5,126 @@ mod config;
 
 use crate::config::cli_parse;
 use anyhow::Result;
+use log::{info, warn};
 use oppai_ai::{ai::AI, analysis::Analysis};
 use oppai_ais::{
+  adjudication::{Adjudicator, Decision as AIDecision},
   oppai::{InConfidence, Oppai},
   time_limited_ai::TimeLimitedAI,
 };
 use oppai_field::{
+  extended_field::ExtendedField,
   field::{length, Field},
+  player::Player,
   zobrist::Zobrist,
 };
+use oppai_minimax::{
+  df_pn::{DfPn, DfPnConfig, Proof as DfPnProof, ProofNode as DfPnNode},
+  hash_table::HashTableKey,
+};
 use oppai_patterns::patterns::Patterns;
-use oppai_protocol::{Constraint, Coords, Move, Request, Response};
+use oppai_protocol::{
+  Constraint, Coords, Decision, Move, Proof, ProofNode, Request, Response, Tree, TreeFormat, UctNode, UctTree,
+};
+use oppai_sgf::{to_sgf_with_variations_str, Variation};
+use oppai_uct::uct::{Proof as UctProof, UctNodeInfo};
 use rand::rngs::SmallRng;
 use rand::SeedableRng;
 use std::{
   default::Default,
   fs::File,
-  io::{self, BufRead, BufReader, Read, Write},
+  io::{self, BufRead, BufReader, BufWriter, Read, Write},
   path::Path,
   sync::Arc,
 };
 
+fn uct_node(field: &Field, node: &UctNodeInfo, root: bool) -> UctNode {
+  UctNode {
+    coords: (!root).then(|| Coords {
+      x: field.to_x(node.pos),
+      y: field.to_y(node.pos),
+    }),
+    visits: node.visits,
+    wins: node.wins,
+    draws: node.draws,
+    winrate: node.winrate,
+    ucb: node.ucb,
+    prior: node.prior,
+    score: node.score,
+    proof: node.proof.map(|proof| match proof {
+      UctProof::Win => Proof::Win,
+      UctProof::Loss => Proof::Loss,
+      UctProof::Draw => Proof::Draw,
+    }),
+    children: node
+      .children
+      .iter()
+      .map(|child| uct_node(field, child, false))
+      .collect(),
+  }
+}
+
+fn proof_node(field: &Field, node: &DfPnNode) -> ProofNode {
+  ProofNode {
+    coords: node.pos.map(|pos| Coords {
+      x: field.to_x(pos),
+      y: field.to_y(pos),
+    }),
+    player: node.player,
+    children: node.children.iter().map(|child| proof_node(field, child)).collect(),
+  }
+}
+
+fn uct_variation(node: &UctNodeInfo, player: Player) -> Variation {
+  let mut children = node.children.iter().collect::<Vec<_>>();
+  children.sort_by_key(|child| std::cmp::Reverse(child.visits));
+  Variation {
+    pos: node.pos,
+    player,
+    comment: format!(
+      "visits {}, wins {}, draws {}, winrate {:.3}, ucb {:.3}, prior {:.3}, score {:.2}, proof {:?}",
+      node.visits, node.wins, node.draws, node.winrate, node.ucb, node.prior, node.score, node.proof
+    ),
+    children: children
+      .into_iter()
+      .map(|child| uct_variation(child, player.next()))
+      .collect(),
+  }
+}
+
+fn hash_table_key(state: &State) -> HashTableKey {
+  HashTableKey::new(&state.field, state.oppai.minimax_hash_table().scale())
+}
+
+/// Loads the Minimax hash table for the field if the file exists. A file saved
+/// for another field size, seed or evaluation is ignored.
+fn load_hash_table(path: &str, state: &State) {
+  if !Path::new(path).exists() {
+    return;
+  }
+  let key = hash_table_key(state);
+  match File::open(path).and_then(|file| state.oppai.minimax_hash_table().load(key, BufReader::new(file))) {
+    Ok(count) => info!("Loaded {} entries of the hash table.", count),
+    Err(e) => warn!("Failed to load the hash table file: {}.", e),
+  }
+}
+
+/// Saves the Minimax hash table. It takes a while for big tables, so it's done
+/// only when a game is over rather than after every analysis.
+fn save_hash_table(path: &str, state: &State) {
+  let key = hash_table_key(state);
+  match File::create(path).and_then(|file| state.oppai.minimax_hash_table().save(key, BufWriter::new(file))) {
+    Ok(()) => info!("Saved the hash table."),
+    Err(e) => warn!("Failed to save the hash table file: {}.", e),
+  }
+}
+
 struct State {
   field: Field,
   rng: SmallRng,
   oppai: Oppai<f32, ()>,
+  adjudicator: Adjudicator,
+  decision: AIDecision,
 }
 
 fn main() -> Result<()> {
@@ -70,22 +165,46 @@ fn main() -> Result<()> {
   let patterns_arc = Arc::new(patterns);
   let mut input = BufReader::new(io::stdin());
   let mut output = io::stdout();
-  let mut state_option = None;
+  let mut state_option: Option<State> = None;
   let mut s = String::new();
   loop {
     s.clear();
-    input.read_line(&mut s)?;
+    if input.read_line(&mut s)? == 0 {
+      if let (Some(state), Some(hash_table_file)) = (state_option.as_ref(), config.hash_table_file.as_ref()) {
+        save_hash_table(hash_table_file, state);
+      }
+      return Ok(());
+    }
     let request = serde_json::from_str(&s)?;
 
     let response = match request {
       Request::Init { width, height } => {
-        let mut rng = SmallRng::from_entropy();
+        let mut rng = config
+          .ai
+          .seed
+          .map_or_else(SmallRng::from_entropy, SmallRng::seed_from_u64);
         let zobrist = Arc::new(Zobrist::new(length(width, height) * 2, &mut rng));
-        state_option = Some(State {
+        let mut oppai = Oppai::new(width, height, config.ai.clone(), patterns_arc.clone(), ());
+        // Keep positions of previous games since they might repeat.
+        if let Some(state) = state_option.as_ref() {
+          if let Some(hash_table_file) = config.hash_table_file.as_ref() {
+            save_hash_table(hash_table_file, state);
+          }
+          if !oppai.set_minimax_hash_table(state.oppai.minimax_hash_table().clone()) {
+            warn!("Failed to share the hash table with the previous game.");
+          }
+        }
+        let state = State {
           field: Field::new(width, height, zobrist),
           rng,
-          oppai: Oppai::new(width, height, config.ai.clone(), patterns_arc.clone(), ()),
-        });
+          oppai,
+          adjudicator: Adjudicator::new(config.ai.adjudication.clone()),
+          decision: AIDecision::Play,
+        };
+        if let Some(hash_table_file) = config.hash_table_file.as_ref() {
+          load_hash_table(hash_table_file, &state);
+        }
+        state_option = Some(state);
         Response::Init
       }
       Request::PutPoint { coords, player } => {
@@ -97,6 +216,11 @@ fn main() -> Result<()> {
       Request::Undo => {
         let state = state_option.as_mut().ok_or(anyhow::anyhow!("Not initialized"))?;
         let undone = state.field.undo();
+        if undone {
+          // Assessments of the undone positions are no longer relevant.
+          state.adjudicator.clear();
+          state.decision = AIDecision::Play;
+        }
         Response::Undo { undone }
       }
       Request::Analyze {
@@ -106,6 +230,7 @@ fn main() -> Result<()> {
         let state = state_option.as_mut().ok_or(anyhow::anyhow!("Not initialized"))?;
         let mut oppai = TimeLimitedAI(time, &mut state.oppai);
         let analysis = oppai.analyze(&mut state.rng, &mut state.field, player, None, &|| false);
+        state.decision = state.adjudicator.decide(analysis.assessment());
         let moves = analysis
           .moves()
           .map(|(pos, weight)| Move {
@@ -131,6 +256,7 @@ fn main() -> Result<()> {
         let analysis = state
           .oppai
           .analyze(&mut state.rng, &mut state.field, player, Some(confidence), &|| false);
+        state.decision = state.adjudicator.decide(analysis.assessment());
         let moves = analysis
           .moves()
           .map(|(pos, weight)| Move {
@@ -143,6 +269,57 @@ fn main() -> Result<()> {
           .collect();
         Response::Analyze { moves }
       }
+      Request::Decision => {
+        let state = state_option.as_ref().ok_or(anyhow::anyhow!("Not initialized"))?;
+        let decision = match state.decision {
+          AIDecision::Play => Decision::Play,
+          AIDecision::Resign => Decision::Resign,
+          AIDecision::OfferDraw => Decision::OfferDraw,
+        };
+        Response::Decision { decision }
+      }
+      Request::DrawOffer => {
+        let state = state_option.as_ref().ok_or(anyhow::anyhow!("Not initialized"))?;
+        Response::DrawOffer {
+          accept: state.adjudicator.accept_draw(),
+        }
+      }
+      Request::InspectUct { depth, format } => {
+        let state = state_option.as_ref().ok_or(anyhow::anyhow!("Not initialized"))?;
+        let tree = state.oppai.inspect_uct(depth).and_then(|tree| match format {
+          TreeFormat::Json => Some(Tree::Json(UctTree {
+            player: tree.player,
+            komi: tree.komi as i32,
+            root: uct_node(&state.field, &tree.root, true),
+          })),
+          TreeFormat::Sgf => {
+            // The tree belongs to the position of the last analysis.
+            let mut field = state.field.clone();
+            while field.moves_count() > tree.moves_count {
+              field.undo();
+            }
+            let variations = uct_variation(&tree.root, tree.player.next()).children;
+            to_sgf_with_variations_str(&ExtendedField::from(field), &variations).map(Tree::Sgf)
+          }
+        });
+        Response::InspectUct { tree }
+      }
+      Request::Prove { player, target, depth } => {
+        let state = state_option.as_mut().ok_or(anyhow::anyhow!("Not initialized"))?;
+        let mut df_pn = DfPn::new(DfPnConfig {
+          rebuild_trajectories: config.ai.minimax.rebuild_trajectories,
+          ..Default::default()
+        });
+        let (proof, moves) = df_pn.prove(&mut state.field, player, target, depth, &|| false);
+        Response::Prove {
+          proven: match proof {
+            DfPnProof::Proven => Some(true),
+            DfPnProof::Disproven => Some(false),
+            DfPnProof::Unknown => None,
+          },
+          moves: moves.iter().map(|node| proof_node(&state.field, node)).collect(),
+        }
+      }
     };
 
     writeln!(&mut output, "{}", serde_json::to_string(&response)?)?;
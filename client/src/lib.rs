@@ -8,6 +8,7 @@ use futures::{
 use log::debug;
 use oppai_field::player::Player;
 pub use oppai_protocol::Constraint;
+pub use oppai_protocol::Decision;
 use oppai_protocol::{Coords, Move, Request, Response};
 
 pub struct Client {
@@ -100,4 +101,28 @@ impl Client {
       Err(Error::other(format!("Wrong response type: {:?}", response)))
     }
   }
+
+  pub async fn decision(&mut self) -> Result<Decision> {
+    self.request(Request::Decision).await?;
+
+    let response = self.response().await?;
+
+    if let Response::Decision { decision } = response {
+      Ok(decision)
+    } else {
+      Err(Error::other(format!("Wrong response type: {:?}", response)))
+    }
+  }
+
+  pub async fn draw_offer(&mut self) -> Result<bool> {
+    self.request(Request::DrawOffer).await?;
+
+    let response = self.response().await?;
+
+    if let Response::DrawOffer { accept } = response {
+      Ok(accept)
+    } else {
+      Err(Error::other(format!("Wrong response type: {:?}", response)))
+    }
+  }
 }
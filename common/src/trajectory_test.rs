@@ -22,7 +22,7 @@ fn build_trajectories_1() {
     ",
   );
 
-  let mut empty_board = iter::repeat(0u32).take(field.length()).collect::<Vec<_>>();
+  let mut empty_board = iter::repeat_n(0u32, field.length()).collect::<Vec<_>>();
 
   let trajectories = build_trajectories(&mut field, Player::Red, 1, &mut empty_board, &|| false);
 
@@ -44,7 +44,7 @@ fn build_trajectories_2() {
     ",
   );
 
-  let mut empty_board = iter::repeat(0u32).take(field.length()).collect::<Vec<_>>();
+  let mut empty_board = iter::repeat_n(0u32, field.length()).collect::<Vec<_>>();
 
   let trajectories = build_trajectories(&mut field, Player::Red, 2, &mut empty_board, &|| false);
 
@@ -68,7 +68,7 @@ fn build_trajectories_3() {
     ",
   );
 
-  let mut empty_board = iter::repeat(0u32).take(field.length()).collect::<Vec<_>>();
+  let mut empty_board = iter::repeat_n(0u32, field.length()).collect::<Vec<_>>();
 
   let trajectories = build_trajectories(&mut field, Player::Red, 3, &mut empty_board, &|| false);
 
@@ -92,7 +92,7 @@ fn build_trajectories_with_no_extra_points() {
     ",
   );
 
-  let mut empty_board = iter::repeat(0u32).take(field.length()).collect::<Vec<_>>();
+  let mut empty_board = iter::repeat_n(0u32, field.length()).collect::<Vec<_>>();
 
   let trajectories = build_trajectories(&mut field, Player::Red, 2, &mut empty_board, &|| false);
 
@@ -116,7 +116,7 @@ fn build_trajectories_through_empty_base() {
     ",
   );
 
-  let mut empty_board = iter::repeat(0u32).take(field.length()).collect::<Vec<_>>();
+  let mut empty_board = iter::repeat_n(0u32, field.length()).collect::<Vec<_>>();
 
   let trajectories = build_trajectories(&mut field, Player::Red, 2, &mut empty_board, &|| false);
 
@@ -137,7 +137,7 @@ fn build_trajectories_crankle_1() {
     ",
   );
 
-  let mut empty_board = iter::repeat(0u32).take(field.length()).collect::<Vec<_>>();
+  let mut empty_board = iter::repeat_n(0u32, field.length()).collect::<Vec<_>>();
 
   let trajectories = build_trajectories(&mut field, Player::Red, 29, &mut empty_board, &|| false);
 
@@ -159,7 +159,7 @@ fn build_trajectories_crankle_2() {
     ",
   );
 
-  let mut empty_board = iter::repeat(0u32).take(field.length()).collect::<Vec<_>>();
+  let mut empty_board = iter::repeat_n(0u32, field.length()).collect::<Vec<_>>();
 
   let trajectories = build_trajectories(&mut field, Player::Red, 27, &mut empty_board, &|| false);
 
@@ -187,7 +187,7 @@ fn build_trajectories_crankle_3() {
     ",
   );
 
-  let mut empty_board = iter::repeat(0u32).take(field.length()).collect::<Vec<_>>();
+  let mut empty_board = iter::repeat_n(0u32, field.length()).collect::<Vec<_>>();
 
   let trajectories = build_trajectories(&mut field, Player::Red, 61, &mut empty_board, &|| false);
 
@@ -225,7 +225,7 @@ fn build_trajectories_maze_1() {
     ",
   );
 
-  let mut empty_board = iter::repeat(0u32).take(field.length()).collect::<Vec<_>>();
+  let mut empty_board = iter::repeat_n(0u32, field.length()).collect::<Vec<_>>();
 
   let trajectories = build_trajectories(&mut field, Player::Red, 39, &mut empty_board, &|| false);
 
@@ -273,7 +273,7 @@ fn build_trajectories_maze_2() {
     ",
   );
 
-  let mut empty_board = iter::repeat(0u32).take(field.length()).collect::<Vec<_>>();
+  let mut empty_board = iter::repeat_n(0u32, field.length()).collect::<Vec<_>>();
 
   let trajectories = build_trajectories(&mut field, Player::Red, 67, &mut empty_board, &|| false);
 
@@ -332,7 +332,7 @@ fn build_trajectories_maze_3() {
     ",
   );
 
-  let mut empty_board = iter::repeat(0u32).take(field.length()).collect::<Vec<_>>();
+  let mut empty_board = iter::repeat_n(0u32, field.length()).collect::<Vec<_>>();
 
   let trajectories = build_trajectories(&mut field, Player::Red, 80, &mut empty_board, &|| false);
 
@@ -353,7 +353,7 @@ fn build_trajectories_from_1() {
     ",
   );
 
-  let mut empty_board = iter::repeat(0u32).take(field.length()).collect::<Vec<_>>();
+  let mut empty_board = iter::repeat_n(0u32, field.length()).collect::<Vec<_>>();
 
   let pos = field.to_pos(2, 1);
   let trajectories = build_trajectories_from(&mut field, pos, Player::Red, 2, &mut empty_board, &|| false);
@@ -101,13 +101,9 @@ impl<E: Extra> canvas::Program<CanvasMessage> for CanvasField<E> {
             }
           }
           mouse::Event::CursorMoved { .. } => {}
-          mouse::Event::CursorLeft => {
-            if state.is_some() {
-              *state = None;
-              return (canvas::event::Status::Captured, Some(CanvasMessage::ClearCoordinates));
-            } else {
-              return (canvas::event::Status::Ignored, None);
-            }
+          mouse::Event::CursorLeft if state.is_some() => {
+            *state = None;
+            return (canvas::event::Status::Captured, Some(CanvasMessage::ClearCoordinates));
           }
           _ => return (canvas::event::Status::Ignored, None),
         }
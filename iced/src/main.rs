@@ -134,7 +134,7 @@ impl Game {
       if self
         .moves
         .get(moves_count - 1)
-        .map_or(true, |&(cur_pos, cur_player, _)| (cur_pos, cur_player) != (pos, player))
+        .is_none_or(|&(cur_pos, cur_player, _)| (cur_pos, cur_player) != (pos, player))
       {
         self.moves.truncate(moves_count - 1);
         self.moves.push((pos, player, Default::default()));
@@ -245,7 +245,10 @@ impl Application for Game {
   type Theme = Theme;
 
   fn new(flags: Config) -> (Self, Command<Self::Message>) {
-    let mut rng = SmallRng::from_entropy();
+    let mut rng = flags
+      .ai_config
+      .seed
+      .map_or_else(SmallRng::from_entropy, SmallRng::seed_from_u64);
     let mut extended_field = ExtendedField::new_from_rng(flags.width, flags.height, &mut rng);
     // TODO: store patterns to use them when new Oppai is created
     #[cfg(not(target_arch = "wasm32"))]
@@ -637,10 +640,12 @@ impl Application for Game {
                     .field
                     .colored_moves()
                     .zip(
-                      iter::repeat(Default::default())
-                        .take(extended_field.field.moves_count() - visits.len() - 1)
-                        .chain(visits)
-                        .chain(iter::repeat(Default::default())),
+                      iter::repeat_n(
+                        Default::default(),
+                        extended_field.field.moves_count() - visits.len() - 1,
+                      )
+                      .chain(visits)
+                      .chain(iter::repeat(Default::default())),
                     )
                     .map(|((pos, player), visits)| (pos, player, visits))
                     .collect();
@@ -114,17 +114,18 @@ fn ladders_rec<SS: Fn() -> bool>(
   empty_board: &mut Vec<u32>,
   should_stop: &SS,
   depth: u32,
-) -> (Option<NonZeroPos>, i32, u32) {
+) -> (Option<NonZeroPos>, i32, u32, Vec<Pos>) {
   match *trajectory.points().as_slice() {
     [pos] => {
       field.put_point(pos, player);
       let cur_score = field.score(player);
       field.undo();
-      (NonZeroPos::new(pos), cur_score, depth)
+      (NonZeroPos::new(pos), cur_score, depth, vec![pos])
     }
     [pos1, pos2] => {
       let mut best_move = None;
       let mut capture_depth = 0;
+      let mut best_line = Vec::new();
 
       for &(our_pos, enemy_pos) in &[(pos1, pos2), (pos2, pos1)] {
         if trajectory.score() <= alpha || alpha >= beta || should_stop() {
@@ -156,6 +157,7 @@ fn ladders_rec<SS: Fn() -> bool>(
             alpha = cur_score;
             best_move = NonZeroPos::new(our_pos);
             capture_depth = depth;
+            best_line = vec![our_pos];
           }
           continue;
         }
@@ -188,7 +190,7 @@ fn ladders_rec<SS: Fn() -> bool>(
             continue;
           }
 
-          let (_, cur_score, cur_capture_depth) = ladders_rec(
+          let (_, cur_score, cur_capture_depth, cur_line) = ladders_rec(
             field,
             player,
             &trajectory,
@@ -204,6 +206,7 @@ fn ladders_rec<SS: Fn() -> bool>(
             alpha = cur_score;
             best_move = NonZeroPos::new(our_pos);
             capture_depth = cur_capture_depth;
+            best_line = [our_pos, enemy_pos].into_iter().chain(cur_line).collect();
           }
         }
 
@@ -215,18 +218,50 @@ fn ladders_rec<SS: Fn() -> bool>(
         field.undo();
       }
 
-      (best_move, alpha, capture_depth)
+      (best_move, alpha, capture_depth, best_line)
     }
     _ => unreachable!("Trajectory with {} points", trajectory.len()),
   }
 }
 
-pub fn ladders<SS: Fn() -> bool>(
-  field: &mut Field,
-  player: Player,
-  should_stop: &SS,
-) -> (Option<NonZeroPos>, i32, u32) {
-  let mut empty_board = iter::repeat(0u32).take(field.length()).collect::<Vec<_>>();
+/// Solution of ladders for a player.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Ladder {
+  /// First move of the ladder.
+  pub best_move: Option<NonZeroPos>,
+  /// Score of the player after the ladder.
+  pub score: i32,
+  /// Number of the player's moves before the capturing one.
+  pub capture_depth: u32,
+  /// Forced sequence of the ladder: the player's moves alternate with the
+  /// enemy's answers, and the last move captures.
+  pub moves: Vec<Pos>,
+  /// Positions that become captured at the end of the ladder.
+  pub captured: Vec<Pos>,
+}
+
+fn captured_area(field: &mut Field, player: Player, moves: &[Pos]) -> Vec<Pos> {
+  let is_captured = |field: &Field, pos: Pos| {
+    let cell = field.cell(pos);
+    cell.is_captured() && cell.is_owner(player)
+  };
+  let before = (0..field.length())
+    .map(|pos| is_captured(field, pos))
+    .collect::<Vec<_>>();
+  for (&pos, cur_player) in moves.iter().zip(iter::successors(Some(player), |p| Some(p.next()))) {
+    field.put_point(pos, cur_player);
+  }
+  let result = (0..field.length())
+    .filter(|&pos| !before[pos] && is_captured(field, pos))
+    .collect();
+  for _ in moves {
+    field.undo();
+  }
+  result
+}
+
+pub fn ladders<SS: Fn() -> bool>(field: &mut Field, player: Player, should_stop: &SS) -> Ladder {
+  let mut empty_board = iter::repeat_n(0u32, field.length()).collect::<Vec<_>>();
 
   let mut trajectories = build_trajectories(field, player, 2, &mut empty_board, should_stop);
   trajectories.sort_unstable_by_key(|trajectory| -trajectory.score());
@@ -236,6 +271,7 @@ pub fn ladders<SS: Fn() -> bool>(
   let mut alpha = field.score(player);
   let mut capture_depth = 0;
   let mut best_move = None;
+  let mut best_line = Vec::new();
 
   for trajectory in trajectories {
     if should_stop() {
@@ -261,7 +297,7 @@ pub fn ladders<SS: Fn() -> bool>(
       Vec::new()
     };
 
-    let (cur_pos, cur_score, cur_capture_depth) = ladders_rec(
+    let (cur_pos, cur_score, cur_capture_depth, cur_line) = ladders_rec(
       field,
       player,
       &trajectory,
@@ -276,6 +312,7 @@ pub fn ladders<SS: Fn() -> bool>(
       alpha = cur_score;
       capture_depth = cur_capture_depth;
       best_move = cur_pos;
+      best_line = cur_line;
     }
 
     for pos in marks {
@@ -283,5 +320,146 @@ pub fn ladders<SS: Fn() -> bool>(
     }
   }
 
-  (best_move, alpha, capture_depth)
+  let captured = captured_area(field, player, &best_line);
+
+  Ladder {
+    best_move,
+    score: alpha,
+    capture_depth,
+    moves: best_line,
+    captured,
+  }
+}
+
+/// Moves of the player that start or extend a ladder losing for the player:
+/// after such a move the enemy captures more with a ladder than it could if
+/// the player skipped the move. Only points of short enemy trajectories are
+/// checked since other moves can't take part in a ladder. Capturing moves are
+/// never forbidden.
+pub fn losing_ladder_moves<SS: Fn() -> bool>(field: &mut Field, player: Player, should_stop: &SS) -> Vec<Pos> {
+  let enemy = player.next();
+  let mut empty_board = iter::repeat_n(0u32, field.length()).collect::<Vec<_>>();
+
+  let trajectories = build_trajectories(field, enemy, 2, &mut empty_board, should_stop);
+  if trajectories.is_empty() {
+    return Vec::new();
+  }
+
+  let mut moves = Vec::new();
+  for &pos in trajectories.iter().flat_map(|trajectory| trajectory.points()) {
+    if empty_board[pos] == 0 {
+      empty_board[pos] = 1;
+      moves.push(pos);
+    }
+  }
+  for &pos in &moves {
+    empty_board[pos] = 0;
+  }
+
+  let enemy_gain = ladders(field, enemy, should_stop).score - field.score(enemy);
+
+  let result = moves
+    .into_iter()
+    .filter(|&pos| {
+      if should_stop() {
+        return false;
+      }
+      field.put_point(pos, player);
+      let result = field.get_delta_score(player) == 0 && {
+        ladders(field, enemy, should_stop).score - field.score(enemy) > enemy_gain && !should_stop()
+      };
+      field.undo();
+      result
+    })
+    .collect::<Vec<_>>();
+
+  if !result.is_empty() {
+    info!(
+      "Moves {:?} lead to losing ladders.",
+      result
+        .iter()
+        .map(|&pos| (field.to_x(pos), field.to_y(pos)))
+        .collect::<Vec<_>>()
+    );
+  }
+
+  result
+}
+
+/// Defense of the player against an enemy ladder.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LadderDefense {
+  /// Move that escapes from the ladder or breaks it.
+  pub best_move: Pos,
+  /// The enemy's best ladder before the move.
+  pub threat: Ladder,
+  /// The enemy's best ladder after the move.
+  pub remaining: Ladder,
+}
+
+/// Searches for a move that defends the player against the enemy's best
+/// ladder. Candidates are points of short enemy trajectories, which include
+/// escapes, and points along the threatened line with their neighbours, which
+/// include ladder breakers. The move that leaves the enemy the smallest ladder
+/// is chosen, and `None` is returned if there is no threat or no candidate
+/// makes it smaller.
+pub fn ladder_defense<SS: Fn() -> bool>(field: &mut Field, player: Player, should_stop: &SS) -> Option<LadderDefense> {
+  let enemy = player.next();
+
+  let threat = ladders(field, enemy, should_stop);
+  if threat.best_move.is_none() || should_stop() {
+    return None;
+  }
+
+  let mut empty_board = iter::repeat_n(0u32, field.length()).collect::<Vec<_>>();
+  let trajectories = build_trajectories(field, enemy, 2, &mut empty_board, should_stop);
+
+  let mut moves = Vec::new();
+  for pos in trajectories
+    .iter()
+    .flat_map(|trajectory| trajectory.points().iter().copied())
+    .chain(
+      threat
+        .moves
+        .iter()
+        .flat_map(|&pos| iter::once(pos).chain(field.directions(pos))),
+    )
+  {
+    if empty_board[pos] == 0 && field.is_putting_allowed(pos) {
+      empty_board[pos] = 1;
+      moves.push(pos);
+    }
+  }
+
+  info!(
+    "Searching defense against a ladder from ({}, {}) among {} moves.",
+    field.to_x(threat.moves[0]),
+    field.to_y(threat.moves[0]),
+    moves.len()
+  );
+
+  let mut best = None;
+  let mut best_score = threat.score;
+
+  for pos in moves {
+    if should_stop() {
+      break;
+    }
+
+    field.put_point(pos, player);
+    if !is_last_move_stupid(field, pos, player) {
+      let remaining = ladders(field, enemy, should_stop);
+      if remaining.score < best_score && !should_stop() {
+        best_score = remaining.score;
+        best = Some((pos, remaining));
+      }
+    }
+    field.undo();
+  }
+
+  best.map(|(best_move, remaining)| LadderDefense {
+    best_move,
+    threat,
+    remaining,
+  })
 }
@@ -1,4 +1,4 @@
-use crate::ladders::ladders;
+use crate::ladders::{ladder_defense, ladders, losing_ladder_moves};
 use oppai_field::construct_field::construct_field;
 use oppai_field::field::NonZeroPos;
 use oppai_field::player::Player;
@@ -24,11 +24,13 @@ fn ladders_escape() {
     ",
   );
 
-  let (pos, score, depth) = ladders(&mut field, Player::Red, &|| false);
+  let ladder = ladders(&mut field, Player::Red, &|| false);
 
-  assert_eq!(pos, None);
-  assert_eq!(score, 0);
-  assert_eq!(depth, 0);
+  assert_eq!(ladder.best_move, None);
+  assert_eq!(ladder.score, 0);
+  assert_eq!(ladder.capture_depth, 0);
+  assert!(ladder.moves.is_empty());
+  assert!(ladder.captured.is_empty());
 }
 
 #[test]
@@ -48,11 +50,32 @@ fn ladders_capture_1() {
     ",
   );
 
-  let (pos, score, depth) = ladders(&mut field, Player::Red, &|| false);
-
-  assert_eq!(pos, NonZeroPos::new(field.to_pos(3, 3)));
-  assert_eq!(score, 3);
-  assert_eq!(depth, 5);
+  let ladder = ladders(&mut field, Player::Red, &|| false);
+
+  assert_eq!(ladder.best_move, NonZeroPos::new(field.to_pos(3, 3)));
+  assert_eq!(ladder.score, 3);
+  assert_eq!(ladder.capture_depth, 5);
+  assert_eq!(
+    ladder.moves,
+    [
+      (3, 3),
+      (4, 4),
+      (5, 4),
+      (4, 3),
+      (4, 2),
+      (5, 3),
+      (6, 3),
+      (5, 2),
+      (5, 1),
+      (6, 2),
+      (7, 2)
+    ]
+    .map(|(x, y)| field.to_pos(x, y))
+  );
+  assert_eq!(
+    ladder.captured,
+    [(5, 2), (6, 2), (4, 3), (5, 3), (3, 4), (4, 4), (2, 5), (3, 5)].map(|(x, y)| field.to_pos(x, y))
+  );
 }
 
 #[test]
@@ -72,11 +95,11 @@ fn ladders_capture_2() {
     ",
   );
 
-  let (pos, score, depth) = ladders(&mut field, Player::Red, &|| false);
+  let ladder = ladders(&mut field, Player::Red, &|| false);
 
-  assert_eq!(pos, NonZeroPos::new(field.to_pos(2, 4)));
-  assert_eq!(score, 2);
-  assert_eq!(depth, 6);
+  assert_eq!(ladder.best_move, NonZeroPos::new(field.to_pos(2, 4)));
+  assert_eq!(ladder.score, 2);
+  assert_eq!(ladder.capture_depth, 6);
 }
 
 #[test]
@@ -96,11 +119,11 @@ fn ladders_capture_3() {
     ",
   );
 
-  let (pos, score, depth) = ladders(&mut field, Player::Red, &|| false);
+  let ladder = ladders(&mut field, Player::Red, &|| false);
 
-  assert_eq!(pos, NonZeroPos::new(field.to_pos(2, 4)));
-  assert_eq!(score, 2);
-  assert_eq!(depth, 7);
+  assert_eq!(ladder.best_move, NonZeroPos::new(field.to_pos(2, 4)));
+  assert_eq!(ladder.score, 2);
+  assert_eq!(ladder.capture_depth, 7);
 }
 
 #[test]
@@ -120,11 +143,11 @@ fn ladders_capture_4() {
     ",
   );
 
-  let (pos, score, depth) = ladders(&mut field, Player::Red, &|| false);
+  let ladder = ladders(&mut field, Player::Red, &|| false);
 
-  assert_eq!(pos, NonZeroPos::new(field.to_pos(2, 4)));
-  assert_eq!(score, 2);
-  assert_eq!(depth, 8);
+  assert_eq!(ladder.best_move, NonZeroPos::new(field.to_pos(2, 4)));
+  assert_eq!(ladder.score, 2);
+  assert_eq!(ladder.capture_depth, 8);
 }
 
 #[test]
@@ -146,11 +169,11 @@ fn ladders_side_capture_1() {
     ",
   );
 
-  let (pos, score, depth) = ladders(&mut field, Player::Red, &|| false);
+  let ladder = ladders(&mut field, Player::Red, &|| false);
 
-  assert_eq!(pos, NonZeroPos::new(field.to_pos(2, 6)));
-  assert_eq!(score, 2);
-  assert_eq!(depth, 7);
+  assert_eq!(ladder.best_move, NonZeroPos::new(field.to_pos(2, 6)));
+  assert_eq!(ladder.score, 2);
+  assert_eq!(ladder.capture_depth, 7);
 }
 
 #[test]
@@ -174,11 +197,11 @@ fn ladders_side_capture_2() {
     ",
   );
 
-  let (pos, score, depth) = ladders(&mut field, Player::Red, &|| false);
+  let ladder = ladders(&mut field, Player::Red, &|| false);
 
-  assert_eq!(pos, NonZeroPos::new(field.to_pos(3, 8)));
-  assert_eq!(score, 2);
-  assert_eq!(depth, 10);
+  assert_eq!(ladder.best_move, NonZeroPos::new(field.to_pos(3, 8)));
+  assert_eq!(ladder.score, 2);
+  assert_eq!(ladder.capture_depth, 10);
 }
 
 #[test]
@@ -198,11 +221,11 @@ fn ladders_shift() {
     ",
   );
 
-  let (pos, score, depth) = ladders(&mut field, Player::Red, &|| false);
+  let ladder = ladders(&mut field, Player::Red, &|| false);
 
-  assert_eq!(pos, NonZeroPos::new(field.to_pos(2, 4)));
-  assert_eq!(score, 2);
-  assert_eq!(depth, 9);
+  assert_eq!(ladder.best_move, NonZeroPos::new(field.to_pos(2, 4)));
+  assert_eq!(ladder.score, 2);
+  assert_eq!(ladder.capture_depth, 9);
 }
 
 #[test]
@@ -221,11 +244,11 @@ fn ladders_rotate() {
     ",
   );
 
-  let (pos, score, depth) = ladders(&mut field, Player::Red, &|| false);
+  let ladder = ladders(&mut field, Player::Red, &|| false);
 
-  assert_eq!(pos, NonZeroPos::new(field.to_pos(2, 3)));
-  assert_eq!(score, 2);
-  assert_eq!(depth, 11);
+  assert_eq!(ladder.best_move, NonZeroPos::new(field.to_pos(2, 3)));
+  assert_eq!(ladder.score, 2);
+  assert_eq!(ladder.capture_depth, 11);
 }
 
 #[test]
@@ -242,11 +265,11 @@ fn ladders_fork() {
     ",
   );
 
-  let (pos, score, depth) = ladders(&mut field, Player::Red, &|| false);
+  let ladder = ladders(&mut field, Player::Red, &|| false);
 
-  assert_eq!(pos, NonZeroPos::new(field.to_pos(3, 2)));
-  assert_eq!(score, 1);
-  assert_eq!(depth, 1);
+  assert_eq!(ladder.best_move, NonZeroPos::new(field.to_pos(3, 2)));
+  assert_eq!(ladder.score, 1);
+  assert_eq!(ladder.capture_depth, 1);
 }
 
 #[test]
@@ -263,11 +286,11 @@ fn ladders_fork_deep() {
     ",
   );
 
-  let (pos, score, depth) = ladders(&mut field, Player::Red, &|| false);
+  let ladder = ladders(&mut field, Player::Red, &|| false);
 
-  assert_eq!(pos, NonZeroPos::new(field.to_pos(2, 2)));
-  assert_eq!(score, 1);
-  assert_eq!(depth, 2);
+  assert_eq!(ladder.best_move, NonZeroPos::new(field.to_pos(2, 2)));
+  assert_eq!(ladder.score, 1);
+  assert_eq!(ladder.capture_depth, 2);
 }
 
 #[test]
@@ -284,11 +307,11 @@ fn ladders_fork_stupid() {
     ",
   );
 
-  let (pos, score, depth) = ladders(&mut field, Player::Red, &|| false);
+  let ladder = ladders(&mut field, Player::Red, &|| false);
 
-  assert_eq!(pos, None);
-  assert_eq!(score, 0);
-  assert_eq!(depth, 0);
+  assert_eq!(ladder.best_move, None);
+  assert_eq!(ladder.score, 0);
+  assert_eq!(ladder.capture_depth, 0);
 }
 
 #[test]
@@ -307,11 +330,11 @@ fn ladders_stupid() {
     ",
   );
 
-  let (pos, score, depth) = ladders(&mut field, Player::Red, &|| false);
+  let ladder = ladders(&mut field, Player::Red, &|| false);
 
-  assert_eq!(pos, None);
-  assert_eq!(score, 0);
-  assert_eq!(depth, 0);
+  assert_eq!(ladder.best_move, None);
+  assert_eq!(ladder.score, 0);
+  assert_eq!(ladder.capture_depth, 0);
 }
 
 #[test]
@@ -330,10 +353,10 @@ fn ladders_not_viable_1() {
     ",
   );
 
-  let (pos, score, depth) = ladders(&mut field, Player::Red, &|| false);
-  assert_eq!(pos, None);
-  assert_eq!(score, 0);
-  assert_eq!(depth, 0);
+  let ladder = ladders(&mut field, Player::Red, &|| false);
+  assert_eq!(ladder.best_move, None);
+  assert_eq!(ladder.score, 0);
+  assert_eq!(ladder.capture_depth, 0);
 }
 
 #[test]
@@ -354,10 +377,10 @@ fn ladders_viable() {
     ",
   );
 
-  let (pos, score, depth) = ladders(&mut field, Player::Red, &|| false);
-  assert_eq!(pos, NonZeroPos::new(field.to_pos(5, 5)));
-  assert_eq!(score, 7);
-  assert_eq!(depth, 5);
+  let ladder = ladders(&mut field, Player::Red, &|| false);
+  assert_eq!(ladder.best_move, NonZeroPos::new(field.to_pos(5, 5)));
+  assert_eq!(ladder.score, 7);
+  assert_eq!(ladder.capture_depth, 5);
 }
 
 #[test]
@@ -378,10 +401,10 @@ fn ladders_not_viable_2() {
     ",
   );
 
-  let (pos, score, depth) = ladders(&mut field, Player::Red, &|| false);
-  assert_eq!(pos, None);
-  assert_eq!(score, 0);
-  assert_eq!(depth, 0);
+  let ladder = ladders(&mut field, Player::Red, &|| false);
+  assert_eq!(ladder.best_move, None);
+  assert_eq!(ladder.score, 0);
+  assert_eq!(ladder.capture_depth, 0);
 }
 
 #[test]
@@ -402,11 +425,11 @@ fn ladders_viable_multi() {
     ",
   );
 
-  let (pos, score, depth) = ladders(&mut field, Player::Red, &|| false);
+  let ladder = ladders(&mut field, Player::Red, &|| false);
 
-  assert_eq!(pos, NonZeroPos::new(field.to_pos(4, 4)));
-  assert_eq!(score, 5);
-  assert_eq!(depth, 5);
+  assert_eq!(ladder.best_move, NonZeroPos::new(field.to_pos(4, 4)));
+  assert_eq!(ladder.score, 5);
+  assert_eq!(ladder.capture_depth, 5);
 }
 
 #[test]
@@ -428,7 +451,7 @@ fn ladders_viable_complex() {
     ",
   );
 
-  let (_, score, _) = ladders(&mut field, Player::Red, &|| false);
+  let score = ladders(&mut field, Player::Red, &|| false).score;
   // It's possible to capture 8 points here but current method is
   // limited - it doesn't consider ladders after captures.
   assert_eq!(score, 6);
@@ -452,8 +475,131 @@ fn ladders_depth_choice() {
     ",
   );
 
-  let (pos, score, depth) = ladders(&mut field, Player::Red, &|| false);
-  assert_eq!(pos, NonZeroPos::new(field.to_pos(6, 6)));
-  assert_eq!(score, 3);
-  assert_eq!(depth, 2);
+  let ladder = ladders(&mut field, Player::Red, &|| false);
+  assert_eq!(ladder.best_move, NonZeroPos::new(field.to_pos(6, 6)));
+  assert_eq!(ladder.score, 3);
+  assert_eq!(ladder.capture_depth, 2);
+  assert_eq!(
+    ladder.moves,
+    [(6, 6), (2, 6), (1, 6), (2, 5), (2, 4)].map(|(x, y)| field.to_pos(x, y))
+  );
+  assert_eq!(
+    ladder.captured,
+    [(2, 5), (2, 6), (3, 6), (4, 6), (5, 6)].map(|(x, y)| field.to_pos(x, y))
+  );
+}
+
+#[test]
+fn losing_ladder_escape() {
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+  let mut field = construct_field(
+    &mut rng,
+    "
+    .........
+    ......a..
+    .........
+    ...a.....
+    ..aA.....
+    .aAAa....
+    ..aa.....
+    .........
+    ",
+  );
+
+  let moves = losing_ladder_moves(&mut field, Player::Black, &|| false);
+
+  assert_eq!(moves, vec![field.to_pos(4, 4)]);
+}
+
+#[test]
+fn winning_ladder_escape() {
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+  let mut field = construct_field(
+    &mut rng,
+    "
+    .........
+    .........
+    .........
+    ...a.....
+    ..aA.....
+    .aAAa....
+    ..aa.....
+    .........
+    ",
+  );
+
+  let moves = losing_ladder_moves(&mut field, Player::Black, &|| false);
+
+  assert!(moves.is_empty());
+}
+
+#[test]
+fn ladder_defense_capture() {
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+  let mut field = construct_field(
+    &mut rng,
+    "
+    .........
+    ......a..
+    .........
+    .........
+    ..aA.....
+    .aAAa....
+    ..aa.....
+    .........
+    ",
+  );
+
+  let defense = ladder_defense(&mut field, Player::Black, &|| false).unwrap();
+
+  assert_eq!(defense.best_move, field.to_pos(3, 3));
+  assert_eq!(defense.threat, ladders(&mut field, Player::Red, &|| false));
+  assert_eq!(defense.remaining.best_move, None);
+  assert_eq!(defense.remaining.score, 0);
+}
+
+#[test]
+fn ladder_defense_depth_choice() {
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+  let mut field = construct_field(
+    &mut rng,
+    "
+    .............
+    .............
+    .............
+    .............
+    .............
+    .a.aaa.......
+    ...AAA.......
+    ..aaaaa......
+    .............
+    ",
+  );
+
+  let defense = ladder_defense(&mut field, Player::Black, &|| false).unwrap();
+
+  assert_eq!(defense.best_move, field.to_pos(2, 6));
+  assert_eq!(defense.threat.best_move, NonZeroPos::new(field.to_pos(6, 6)));
+  assert_eq!(defense.remaining.best_move, None);
+  assert_eq!(defense.remaining.score, 0);
+}
+
+#[test]
+fn ladder_defense_no_threat() {
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+  let mut field = construct_field(
+    &mut rng,
+    "
+    .........
+    .........
+    .........
+    .........
+    ..aA.....
+    .aAAa....
+    ..aa.....
+    .........
+    ",
+  );
+
+  assert_eq!(ladder_defense(&mut field, Player::Black, &|| false), None);
 }
@@ -0,0 +1,431 @@
+use crate::trajectories_pruning::TrajectoriesPruning;
+use oppai_common::common;
+use oppai_field::field::{Field, Pos};
+use oppai_field::player::Player;
+use std::{collections::HashMap, iter};
+
+/// Proof or disproof number of a solved node.
+const INFINITY: u32 = u32::MAX;
+
+const PROVEN: (u32, u32) = (0, INFINITY);
+
+const DISPROVEN: (u32, u32) = (INFINITY, 0);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Proof {
+  Proven,
+  Disproven,
+  /// The search was stopped or ran out of nodes.
+  Unknown,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DfPnConfig {
+  pub rebuild_trajectories: bool,
+  /// Maximum number of nodes visited by one proof.
+  pub max_nodes: usize,
+}
+
+impl Default for DfPnConfig {
+  fn default() -> Self {
+    Self {
+      rebuild_trajectories: false,
+      max_nodes: 1 << 22,
+    }
+  }
+}
+
+/// Node of a solution tree: a move with all answers to it when it's a move of
+/// the winning side, or with one winning answer otherwise. A move without
+/// position is a pass. Lines end at the depth limit, when there are no moves
+/// on trajectories, or after a move that can be captured at once, which is
+/// never good in the Minimax search.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ProofNode {
+  pub pos: Option<Pos>,
+  pub player: Player,
+  pub children: Vec<ProofNode>,
+}
+
+struct Child {
+  pos: Option<Pos>,
+  key: (u64, u32),
+}
+
+/// Depth-first proof-number search. Proves or disproves that the player can
+/// reach the target score within the depth, with moves restricted to
+/// trajectories the same way as in the Minimax search. So the target is
+/// proven if and only if the Minimax estimation with the same depth reaches
+/// it.
+pub struct DfPn {
+  config: DfPnConfig,
+  /// Proof and disproof numbers by the colored hash and the depth.
+  table: HashMap<(u64, u32), (u32, u32)>,
+  empty_board: Vec<u32>,
+  attacker: Player,
+  target: i32,
+  nodes: usize,
+}
+
+impl Default for DfPn {
+  fn default() -> Self {
+    Self::new(DfPnConfig::default())
+  }
+}
+
+impl DfPn {
+  pub fn new(config: DfPnConfig) -> Self {
+    Self {
+      config,
+      table: HashMap::new(),
+      empty_board: Vec::new(),
+      attacker: Player::Red,
+      target: 0,
+      nodes: 0,
+    }
+  }
+
+  /// Sum of proof or disproof numbers that keeps infinity only for solved
+  /// nodes.
+  fn sum(numbers: impl Iterator<Item = u32>) -> u32 {
+    let mut result = 0u32;
+    for number in numbers {
+      if number == INFINITY {
+        return INFINITY;
+      }
+      result = result.saturating_add(number).min(INFINITY - 1);
+    }
+    result
+  }
+
+  fn lookup(&self, key: (u64, u32)) -> (u32, u32) {
+    self.table.get(&key).copied().unwrap_or((1, 1))
+  }
+
+  fn is_stopped<SS: Fn() -> bool>(&self, should_stop: &SS) -> bool {
+    self.nodes >= self.config.max_nodes || should_stop()
+  }
+
+  fn leaf(&self, field: &Field) -> (u32, u32) {
+    if field.score(self.attacker) >= self.target {
+      PROVEN
+    } else {
+      DISPROVEN
+    }
+  }
+
+  fn win(&self, player: Player) -> (u32, u32) {
+    if player == self.attacker {
+      PROVEN
+    } else {
+      DISPROVEN
+    }
+  }
+
+  /// Generates moves of the node or returns its proof and disproof numbers if
+  /// it's solved without moves. The pass is the last move and it's allowed
+  /// only after a move, like the null move in the Minimax search.
+  fn expand(
+    &mut self,
+    field: &mut Field,
+    player: Player,
+    depth: u32,
+    last_pos: Option<Pos>,
+    trajectories_pruning: &TrajectoriesPruning,
+  ) -> Result<Vec<Child>, (u32, u32)> {
+    if depth == 0 || trajectories_pruning.moves().is_empty() {
+      return Err(self.leaf(field));
+    }
+    let enemy = player.next();
+    let mut children = Vec::with_capacity(trajectories_pruning.moves().len() + 1);
+    for &pos in trajectories_pruning.moves() {
+      field.put_point(pos, player);
+      if common::is_penult_move_stupid(field) {
+        field.undo();
+        return Err(self.win(player));
+      }
+      if common::is_last_move_stupid(field, pos, player) {
+        field.undo();
+        continue;
+      }
+      let key = (field.colored_hash(enemy), depth - 1);
+      if depth == 1 {
+        self.table.insert(key, self.leaf(field));
+      }
+      field.undo();
+      children.push(Child { pos: Some(pos), key });
+    }
+    if last_pos.is_some() {
+      children.push(Child {
+        pos: None,
+        key: (field.colored_hash(enemy), depth - 1),
+      });
+    }
+    if children.is_empty() {
+      Err(self.leaf(field))
+    } else {
+      Ok(children)
+    }
+  }
+
+  fn child_trajectories<SS: Fn() -> bool>(
+    &mut self,
+    field: &mut Field,
+    player: Player,
+    depth: u32,
+    pos: Option<Pos>,
+    trajectories_pruning: &TrajectoriesPruning,
+    should_stop: &SS,
+  ) -> TrajectoriesPruning {
+    if let Some(pos) = pos {
+      field.put_point(pos, player);
+      trajectories_pruning.next(field, player.next(), depth - 1, &mut self.empty_board, pos, should_stop)
+    } else {
+      trajectories_pruning.dec_and_swap(depth - 1, &mut self.empty_board)
+    }
+  }
+
+  /// Searches the node until its proof number reaches `proof_threshold` or its
+  /// disproof number reaches `disproof_threshold`.
+  fn mid<SS: Fn() -> bool>(
+    &mut self,
+    field: &mut Field,
+    player: Player,
+    depth: u32,
+    last_pos: Option<Pos>,
+    trajectories_pruning: &TrajectoriesPruning,
+    proof_threshold: u32,
+    disproof_threshold: u32,
+    should_stop: &SS,
+  ) {
+    self.nodes += 1;
+    let key = (field.colored_hash(player), depth);
+    let (proof, disproof) = self.lookup(key);
+    if proof >= proof_threshold || disproof >= disproof_threshold {
+      return;
+    }
+    let children = match self.expand(field, player, depth, last_pos, trajectories_pruning) {
+      Ok(children) => children,
+      Err(numbers) => {
+        self.table.insert(key, numbers);
+        return;
+      }
+    };
+    let attacker = player == self.attacker;
+    loop {
+      let numbers = children.iter().map(|child| self.lookup(child.key)).collect::<Vec<_>>();
+      let (proof, disproof) = if attacker {
+        (
+          numbers.iter().map(|&(proof, _)| proof).min().unwrap_or(INFINITY),
+          Self::sum(numbers.iter().map(|&(_, disproof)| disproof)),
+        )
+      } else {
+        (
+          Self::sum(numbers.iter().map(|&(proof, _)| proof)),
+          numbers.iter().map(|&(_, disproof)| disproof).min().unwrap_or(INFINITY),
+        )
+      };
+      self.table.insert(key, (proof, disproof));
+      if proof >= proof_threshold || disproof >= disproof_threshold || self.is_stopped(should_stop) {
+        return;
+      }
+      // The most promising child for the player to move and the number of the
+      // second best one, which limits the search of the first.
+      let select = |&(proof, disproof): &(u32, u32)| if attacker { proof } else { disproof };
+      let mut best = 0;
+      let mut second = INFINITY;
+      for (i, child_numbers) in numbers.iter().enumerate().skip(1) {
+        if select(child_numbers) < select(&numbers[best]) {
+          second = select(&numbers[best]);
+          best = i;
+        } else if select(child_numbers) < second {
+          second = select(child_numbers);
+        }
+      }
+      let (best_proof, best_disproof) = numbers[best];
+      let (child_proof_threshold, child_disproof_threshold) = if attacker {
+        (
+          proof_threshold.min(second.saturating_add(1)),
+          disproof_threshold
+            .saturating_sub(disproof)
+            .saturating_add(best_disproof),
+        )
+      } else {
+        (
+          proof_threshold.saturating_sub(proof).saturating_add(best_proof),
+          disproof_threshold.min(second.saturating_add(1)),
+        )
+      };
+      let pos = children[best].pos;
+      let next_trajectories_pruning =
+        self.child_trajectories(field, player, depth, pos, trajectories_pruning, should_stop);
+      self.mid(
+        field,
+        player.next(),
+        depth - 1,
+        pos,
+        &next_trajectories_pruning,
+        child_proof_threshold,
+        child_disproof_threshold,
+        should_stop,
+      );
+      if pos.is_some() {
+        field.undo();
+      }
+    }
+  }
+
+  /// Extracts the solution tree of a solved node from the table.
+  fn tree(
+    &mut self,
+    field: &mut Field,
+    player: Player,
+    depth: u32,
+    last_pos: Option<Pos>,
+    trajectories_pruning: &TrajectoriesPruning,
+    proven: bool,
+  ) -> Vec<ProofNode> {
+    let Ok(children) = self.expand(field, player, depth, last_pos, trajectories_pruning) else {
+      return Vec::new();
+    };
+    let is_solved = |numbers: (u32, u32)| if proven { numbers.0 == 0 } else { numbers.1 == 0 };
+    let solved = children
+      .into_iter()
+      .filter(|child| is_solved(self.lookup(child.key)))
+      .collect::<Vec<_>>();
+    // The winning side needs only one move.
+    let count = if (player == self.attacker) == proven {
+      1
+    } else {
+      solved.len()
+    };
+    solved
+      .into_iter()
+      .take(count)
+      .map(|child| {
+        let next_trajectories_pruning =
+          self.child_trajectories(field, player, depth, child.pos, trajectories_pruning, &|| false);
+        let children = self.tree(
+          field,
+          player.next(),
+          depth - 1,
+          child.pos,
+          &next_trajectories_pruning,
+          proven,
+        );
+        if child.pos.is_some() {
+          field.undo();
+        }
+        ProofNode {
+          pos: child.pos,
+          player,
+          children,
+        }
+      })
+      .collect()
+  }
+
+  /// Proves or disproves that the player's score reaches the target after
+  /// `depth` moves. Returns the result with its solution tree, which is empty
+  /// if the result is unknown or follows from trajectories without search.
+  pub fn prove<SS: Fn() -> bool>(
+    &mut self,
+    field: &mut Field,
+    player: Player,
+    target: i32,
+    depth: u32,
+    should_stop: &SS,
+  ) -> (Proof, Vec<ProofNode>) {
+    info!(
+      "Starting df-pn with depth {}, target {} and player {}.",
+      depth, target, player
+    );
+    self.table.clear();
+    self.empty_board = iter::repeat_n(0u32, field.length()).collect();
+    self.attacker = player;
+    self.target = target;
+    self.nodes = 0;
+    let trajectories_pruning = TrajectoriesPruning::new(
+      self.config.rebuild_trajectories,
+      field,
+      player,
+      depth,
+      &mut self.empty_board,
+      should_stop,
+    );
+    let (alpha, beta) = Self::bounds(field, player, &trajectories_pruning);
+    if target <= alpha {
+      return (Proof::Proven, Vec::new());
+    }
+    if target > beta {
+      return (Proof::Disproven, Vec::new());
+    }
+    self.mid(
+      field,
+      player,
+      depth,
+      None,
+      &trajectories_pruning,
+      INFINITY,
+      INFINITY,
+      should_stop,
+    );
+    let proof = match self.lookup((field.colored_hash(player), depth)) {
+      (0, _) => Proof::Proven,
+      (_, 0) => Proof::Disproven,
+      _ => Proof::Unknown,
+    };
+    info!("Df-pn result is {:?} after {} nodes.", proof, self.nodes);
+    let tree = match proof {
+      Proof::Proven => self.tree(field, player, depth, None, &trajectories_pruning, true),
+      Proof::Disproven => self.tree(field, player, depth, None, &trajectories_pruning, false),
+      Proof::Unknown => Vec::new(),
+    };
+    (proof, tree)
+  }
+
+  /// Bounds of the score from trajectories, the same as the initial window of
+  /// the Minimax search.
+  fn bounds(field: &Field, player: Player, trajectories_pruning: &TrajectoriesPruning) -> (i32, i32) {
+    let score = field.score(player);
+    (
+      trajectories_pruning.alpha().unwrap_or(score),
+      trajectories_pruning.beta().unwrap_or(score),
+    )
+  }
+
+  /// Finds the exact estimation of the position by proving targets between
+  /// bounds from trajectories. Returns `None` if a proof was stopped.
+  pub fn solve<SS: Fn() -> bool>(
+    &mut self,
+    field: &mut Field,
+    player: Player,
+    depth: u32,
+    should_stop: &SS,
+  ) -> Option<i32> {
+    let mut empty_board = iter::repeat_n(0u32, field.length()).collect::<Vec<_>>();
+    let trajectories_pruning = TrajectoriesPruning::new(
+      self.config.rebuild_trajectories,
+      field,
+      player,
+      depth,
+      &mut empty_board,
+      should_stop,
+    );
+    let (mut alpha, mut beta) = Self::bounds(field, player, &trajectories_pruning);
+    while alpha < beta {
+      let target = alpha + (beta - alpha + 1) / 2;
+      match self.prove(field, player, target, depth, should_stop).0 {
+        Proof::Proven => alpha = target,
+        Proof::Disproven => beta = target - 1,
+        Proof::Unknown => return None,
+      }
+    }
+    Some(alpha)
+  }
+
+  /// Number of nodes visited by the last proof.
+  pub fn nodes(&self) -> usize {
+    self.nodes
+  }
+}
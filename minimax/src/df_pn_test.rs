@@ -0,0 +1,114 @@
+use crate::df_pn::{DfPn, DfPnConfig, Proof, ProofNode};
+use crate::minimax::{Minimax, MinimaxConfig, MinimaxType};
+use oppai_field::construct_field::construct_field;
+use oppai_field::field::Field;
+use oppai_field::player::Player;
+use oppai_test_images::*;
+use rand::SeedableRng;
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+const SEED: u64 = 7;
+
+const MINIMAX_CONFIG: MinimaxConfig = MinimaxConfig {
+  threads_count: 1,
+  minimax_type: MinimaxType::NegaScout,
+  hash_table_memory: 1,
+  rebuild_trajectories: false,
+  deterministic: false,
+  multi_pv: 1,
+  split_trajectories: false,
+  move_ordering: true,
+  quiescence_depth: 0,
+  forbid_losing_ladders: false,
+};
+
+macro_rules! df_pn_test {
+  ($(#[$($attr:meta),+])* $name:ident, $image:ident, $depth:expr) => {
+    #[test]
+    $(#[$($attr),+])*
+    fn $name() {
+      env_logger::try_init().ok();
+      let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+      let mut field = construct_field(&mut rng, $image.image);
+      let (_, estimation) = Minimax::new(MINIMAX_CONFIG).minimax(&mut field, Player::Red, $depth, &|| false);
+      let mut df_pn = DfPn::default();
+      assert_eq!(df_pn.solve(&mut field, Player::Red, $depth, &|| false), Some(estimation));
+    }
+  }
+}
+
+df_pn_test!(df_pn_1, IMAGE_1, 8);
+df_pn_test!(df_pn_2, IMAGE_2, 8);
+df_pn_test!(df_pn_3, IMAGE_3, 8);
+df_pn_test!(df_pn_4, IMAGE_4, 8);
+df_pn_test!(df_pn_5, IMAGE_5, 8);
+df_pn_test!(df_pn_6, IMAGE_6, 8);
+df_pn_test!(df_pn_8, IMAGE_8, 8);
+df_pn_test!(df_pn_10, IMAGE_10, 8);
+df_pn_test!(df_pn_12, IMAGE_12, 8);
+df_pn_test!(df_pn_13, IMAGE_13, 8);
+df_pn_test!(df_pn_14, IMAGE_14, 8);
+df_pn_test!(df_pn_15, IMAGE_15, 8);
+
+/// Checks that moves of the solution tree are valid and that the winning side
+/// has only one answer to each move. Returns the number of lines.
+fn check_tree(field: &mut Field, nodes: &[ProofNode], winner: Player) -> usize {
+  let mut lines = 0;
+  for node in nodes {
+    if let Some(pos) = node.pos {
+      assert!(field.put_point(pos, node.player));
+    }
+    if node.player != winner {
+      assert!(node.children.len() <= 1);
+    }
+    lines += if node.children.is_empty() {
+      1
+    } else {
+      check_tree(field, &node.children, winner)
+    };
+    if node.pos.is_some() {
+      field.undo();
+    }
+  }
+  lines
+}
+
+#[test]
+fn proof_tree() {
+  env_logger::try_init().ok();
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+  let mut field = construct_field(&mut rng, IMAGE_1.image);
+  let mut df_pn = DfPn::default();
+  let (proof, tree) = df_pn.prove(&mut field, Player::Red, 1, 8, &|| false);
+  assert_eq!(proof, Proof::Proven);
+  assert_eq!(tree.len(), 1);
+  assert_eq!(tree[0].pos, Some(field.to_pos(IMAGE_1.solution.0, IMAGE_1.solution.1)));
+  assert!(check_tree(&mut field, &tree, Player::Red) > 1);
+}
+
+#[test]
+fn disproof_tree() {
+  env_logger::try_init().ok();
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+  let mut field = construct_field(&mut rng, IMAGE_15.image);
+  let mut df_pn = DfPn::default();
+  let (proof, tree) = df_pn.prove(&mut field, Player::Red, 0, 8, &|| false);
+  assert_eq!(proof, Proof::Disproven);
+  assert!(tree.len() > 1);
+  assert!(check_tree(&mut field, &tree, Player::Black) >= tree.len());
+}
+
+#[test]
+fn df_pn_max_nodes() {
+  env_logger::try_init().ok();
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+  let mut field = construct_field(&mut rng, IMAGE_12.image);
+  let mut df_pn = DfPn::new(DfPnConfig {
+    rebuild_trajectories: false,
+    max_nodes: 100,
+  });
+  let (proof, tree) = df_pn.prove(&mut field, Player::Red, 3, 8, &|| false);
+  assert_eq!(proof, Proof::Unknown);
+  assert!(tree.is_empty());
+  assert_eq!(df_pn.solve(&mut field, Player::Red, 8, &|| false), None);
+}
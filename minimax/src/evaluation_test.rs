@@ -0,0 +1,83 @@
+use crate::evaluation::{Evaluation, EvaluationWeights, MaterialEvaluation, PositionalEvaluation};
+use oppai_field::construct_field::construct_field;
+use oppai_field::player::Player;
+use oppai_test_images::*;
+use rand::SeedableRng;
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+const SEED: u64 = 7;
+
+#[test]
+fn evaluation_weights_parse() {
+  let weights = "
+    # Custom weights.
+    threats 7
+    territory -2
+  "
+  .parse::<EvaluationWeights>()
+  .unwrap();
+  assert_eq!(
+    weights,
+    EvaluationWeights {
+      threats: 7,
+      territory: -2,
+      ..Default::default()
+    }
+  );
+  assert!("threats".parse::<EvaluationWeights>().is_err());
+  assert!("threats 1 2".parse::<EvaluationWeights>().is_err());
+  assert!("threats x".parse::<EvaluationWeights>().is_err());
+  assert!("mobility 1".parse::<EvaluationWeights>().is_err());
+}
+
+#[test]
+fn evaluation_to_score() {
+  let evaluation = PositionalEvaluation::default();
+  let margin = evaluation.margin();
+  for score in -3..=3 {
+    for positional in [-margin, 0, margin] {
+      assert_eq!(
+        evaluation.to_score(score * PositionalEvaluation::SCALE + positional),
+        score
+      );
+    }
+  }
+  assert_eq!(MaterialEvaluation.to_score(-5), -5);
+}
+
+#[test]
+fn positional_evaluation_is_symmetric() {
+  let evaluation = PositionalEvaluation::default();
+  for image in [IMAGE_1, IMAGE_2, IMAGE_5, IMAGE_14, IMAGE_15] {
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+    let mut field = construct_field(&mut rng, image.image);
+    let hash = field.hash();
+    let red = evaluation.evaluate(&mut field, Player::Red);
+    let black = evaluation.evaluate(&mut field, Player::Black);
+    assert_eq!(red, -black);
+    assert_eq!(evaluation.to_score(red), field.score(Player::Red));
+    assert_eq!(field.hash(), hash);
+  }
+}
+
+#[test]
+fn positional_evaluation_prefers_threats() {
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+  let mut field = construct_field(
+    &mut rng,
+    "
+    ......
+    ..a...
+    .aAa..
+    ......
+    ......
+    ",
+  );
+  let evaluation = PositionalEvaluation::new(EvaluationWeights {
+    threats: 1,
+    connectivity: 0,
+    grounding: 0,
+    territory: 0,
+  });
+  assert_eq!(evaluation.evaluate(&mut field, Player::Red), 1);
+}
@@ -1,9 +1,11 @@
-use oppai_field::field::Pos;
+use oppai_field::field::{Field, Pos};
 use std::{
   convert::From,
   default::Default,
-  iter,
-  sync::atomic::{AtomicUsize, Ordering},
+  fmt,
+  io::{self, Read, Write},
+  iter, mem,
+  sync::atomic::{AtomicI32, AtomicU8, AtomicUsize, Ordering},
 };
 
 #[cfg(target_pointer_width = "32")]
@@ -127,6 +129,9 @@ impl HashData {
 struct HashEntry {
   hash: AtomicUsize,
   data: AtomicUsize,
+  /// Generation of the search that stored the entry. It isn't verified with
+  /// the hash since it affects only the replacement.
+  generation: AtomicU8,
 }
 
 impl Clone for HashEntry {
@@ -134,6 +139,7 @@ impl Clone for HashEntry {
     Self {
       hash: AtomicUsize::new(self.hash.load(Ordering::SeqCst)),
       data: AtomicUsize::new(self.data.load(Ordering::SeqCst)),
+      generation: AtomicU8::new(self.generation.load(Ordering::SeqCst)),
     }
   }
 }
@@ -143,6 +149,7 @@ impl Default for HashEntry {
     HashEntry {
       hash: AtomicUsize::new(0),
       data: AtomicUsize::new(0),
+      generation: AtomicU8::new(0),
     }
   }
 }
@@ -157,31 +164,177 @@ impl HashEntry {
       HashData::default()
     }
   }
+
+  fn data(&self) -> HashData {
+    HashData {
+      data: self.data.load(Ordering::Relaxed),
+    }
+  }
+
+  fn generation(&self) -> u8 {
+    self.generation.load(Ordering::Relaxed)
+  }
+
+  fn store(&self, hash: u64, hash_data: HashData, generation: u8) {
+    self.hash.store(hash as usize ^ hash_data.data, Ordering::Relaxed);
+    self.data.store(hash_data.data, Ordering::Relaxed);
+    self.generation.store(generation, Ordering::Relaxed);
+  }
+}
+
+/// Entries with the same index. The first one is replaced only by deeper
+/// entries or entries of a newer search, the second one is always replaced.
+#[derive(Debug, Clone, Default)]
+struct HashBucket {
+  depth_preferred: HashEntry,
+  always_replace: HashEntry,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct HashTableStats {
+  /// Number of lookups.
+  pub probes: usize,
+  /// Number of lookups that found an entry.
+  pub hits: usize,
+  /// Number of stored entries.
+  pub stores: usize,
+  /// Permille of sampled entries stored by the current search.
+  pub fill: usize,
+}
+
+impl HashTableStats {
+  pub fn hit_rate(&self) -> f64 {
+    if self.probes == 0 {
+      0.0
+    } else {
+      self.hits as f64 / self.probes as f64
+    }
+  }
+}
+
+impl fmt::Display for HashTableStats {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{} probes, {:.1}% hits, {} stores, {}\u{2030} filled",
+      self.probes,
+      self.hit_rate() * 100.0,
+      self.stores,
+      self.fill
+    )
+  }
+}
+
+/// Number of buckets to check to estimate the fill of the table.
+const FILL_SAMPLE: usize = 1000;
+
+const FILE_MAGIC: [u8; 8] = *b"OPPAITT\0";
+const FILE_VERSION: u32 = 2;
+
+/// Identifies positions whose hashes are stored in the table. Hashes are
+/// meaningful only for fields of the same size with the same Zobrist hashes,
+/// and estimations only for evaluations of the same scale.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct HashTableKey {
+  pub width: u32,
+  pub height: u32,
+  /// Fingerprint of the Zobrist hashes of the field.
+  pub zobrist: u64,
+  /// Scale of the evaluation of stored estimations.
+  pub scale: i32,
+}
+
+impl HashTableKey {
+  pub fn new(field: &Field, scale: i32) -> Self {
+    let zobrist = field.zobrist();
+    Self {
+      width: field.width(),
+      height: field.height(),
+      zobrist: (0..field.length()).fold(0, |acc, pos| acc.rotate_left(7) ^ zobrist.get_hash(pos)),
+      scale,
+    }
+  }
+}
+
+fn write_u32<W: Write>(writer: &mut W, value: u32) -> io::Result<()> {
+  writer.write_all(&value.to_le_bytes())
+}
+
+fn write_u64<W: Write>(writer: &mut W, value: u64) -> io::Result<()> {
+  writer.write_all(&value.to_le_bytes())
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+  let mut buf = [0; 4];
+  reader.read_exact(&mut buf)?;
+  Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+  let mut buf = [0; 8];
+  reader.read_exact(&mut buf)?;
+  Ok(u64::from_le_bytes(buf))
 }
 
-#[derive(Debug, Clone)]
+fn invalid_data(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[derive(Debug)]
 pub struct HashTable {
-  entries: Vec<HashEntry>,
+  buckets: Vec<HashBucket>,
+  /// Scale of the evaluation of stored estimations, or 0 if the table isn't
+  /// bound to an evaluation yet.
+  scale: AtomicI32,
+  generation: AtomicU8,
+  probes: AtomicUsize,
+  hits: AtomicUsize,
+  stores: AtomicUsize,
+}
+
+impl Clone for HashTable {
+  fn clone(&self) -> Self {
+    Self {
+      buckets: self.buckets.clone(),
+      scale: AtomicI32::new(self.scale.load(Ordering::SeqCst)),
+      generation: AtomicU8::new(self.generation.load(Ordering::SeqCst)),
+      probes: AtomicUsize::new(self.probes.load(Ordering::SeqCst)),
+      hits: AtomicUsize::new(self.hits.load(Ordering::SeqCst)),
+      stores: AtomicUsize::new(self.stores.load(Ordering::SeqCst)),
+    }
+  }
 }
 
 impl HashTable {
+  /// Memory of one bucket in bytes.
+  pub const BUCKET_SIZE: usize = mem::size_of::<HashBucket>();
+
   #[inline]
   fn index(length: usize, hash: u64) -> usize {
     (hash % (length as u64)) as usize
   }
 
-  pub fn new(length: usize) -> HashTable {
+  /// Creates a table that takes at most `memory` bytes, but has at least one
+  /// bucket.
+  pub fn new(memory: usize) -> HashTable {
+    let length = (memory / HashTable::BUCKET_SIZE).max(1);
     HashTable {
-      entries: iter::repeat_with(HashEntry::default).take(length).collect(),
+      buckets: iter::repeat_with(HashBucket::default).take(length).collect(),
+      scale: AtomicI32::new(0),
+      generation: AtomicU8::new(0),
+      probes: AtomicUsize::new(0),
+      hits: AtomicUsize::new(0),
+      stores: AtomicUsize::new(0),
     }
   }
 
+  /// Number of entries the table can contain.
   pub fn len(&self) -> usize {
-    self.entries.len()
+    self.buckets.len() * 2
   }
 
   pub fn is_empty(&self) -> bool {
-    self.entries.is_empty()
+    self.buckets.is_empty()
   }
 
   fn choose_best(data1: HashData, data2: HashData) -> HashData {
@@ -200,30 +353,181 @@ impl HashTable {
     }
   }
 
+  fn generation(&self) -> u8 {
+    self.generation.load(Ordering::Relaxed)
+  }
+
+  pub fn scale(&self) -> i32 {
+    self.scale.load(Ordering::Relaxed)
+  }
+
+  /// Binds the table to estimations of the given scale unless it's bound
+  /// already. Returns `false` if it's bound to another scale, since its
+  /// estimations would be misinterpreted.
+  pub fn bind(&self, scale: i32) -> bool {
+    match self
+      .scale
+      .compare_exchange(0, scale, Ordering::Relaxed, Ordering::Relaxed)
+    {
+      Ok(_) => true,
+      Err(cur_scale) => cur_scale == scale,
+    }
+  }
+
+  /// Starts a new search. Entries of previous searches are still available,
+  /// but they are replaced first. Statistics are reset.
+  pub fn next_generation(&self) {
+    self.generation.fetch_add(1, Ordering::Relaxed);
+    self.probes.store(0, Ordering::Relaxed);
+    self.hits.store(0, Ordering::Relaxed);
+    self.stores.store(0, Ordering::Relaxed);
+  }
+
   pub fn put(&self, hash: u64, hash_data: HashData) {
-    let idx = HashTable::index(self.len(), hash);
-    let cur_data = self.entries[idx].verified(hash);
-    let new_data = if cur_data.hash_type() == HashType::Empty {
-      hash_data
+    let bucket = &self.buckets[HashTable::index(self.buckets.len(), hash)];
+    let generation = self.generation();
+    for entry in [&bucket.depth_preferred, &bucket.always_replace] {
+      let cur_data = entry.verified(hash);
+      if cur_data.hash_type() != HashType::Empty {
+        let new_data = if entry.generation() == generation {
+          HashTable::choose_best(cur_data, hash_data)
+        } else {
+          hash_data
+        };
+        if cur_data != new_data || entry.generation() != generation {
+          entry.store(hash, new_data, generation);
+          self.stores.fetch_add(1, Ordering::Relaxed);
+        }
+        return;
+      }
+    }
+    let depth_preferred = &bucket.depth_preferred;
+    let cur_data = depth_preferred.data();
+    if cur_data.hash_type() == HashType::Empty
+      || depth_preferred.generation() != generation
+      || hash_data.depth() >= cur_data.depth()
+    {
+      if cur_data.hash_type() != HashType::Empty && depth_preferred.generation() == generation {
+        let xored_hash = depth_preferred.hash.load(Ordering::Relaxed);
+        bucket
+          .always_replace
+          .store(xored_hash as u64 ^ cur_data.data as u64, cur_data, generation);
+      }
+      depth_preferred.store(hash, hash_data, generation);
     } else {
-      HashTable::choose_best(cur_data, hash_data)
-    };
-    if cur_data != new_data {
-      let xored_hash = hash as usize ^ new_data.data;
-      let entry = &self.entries[idx];
-      entry.hash.store(xored_hash, Ordering::Relaxed);
-      entry.data.store(new_data.data, Ordering::Relaxed);
+      bucket.always_replace.store(hash, hash_data, generation);
     }
+    self.stores.fetch_add(1, Ordering::Relaxed);
   }
 
   pub fn get(&self, hash: u64) -> HashData {
-    let idx = HashTable::index(self.len(), hash);
-    self.entries[idx].verified(hash)
+    let bucket = &self.buckets[HashTable::index(self.buckets.len(), hash)];
+    self.probes.fetch_add(1, Ordering::Relaxed);
+    let mut hash_data = bucket.depth_preferred.verified(hash);
+    if hash_data.hash_type() == HashType::Empty {
+      hash_data = bucket.always_replace.verified(hash);
+    }
+    if hash_data.hash_type() != HashType::Empty {
+      self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+    hash_data
+  }
+
+  pub fn stats(&self) -> HashTableStats {
+    let generation = self.generation();
+    let sample = &self.buckets[..self.buckets.len().min(FILL_SAMPLE)];
+    let filled = sample
+      .iter()
+      .flat_map(|bucket| [&bucket.depth_preferred, &bucket.always_replace])
+      .filter(|entry| entry.data().hash_type() != HashType::Empty && entry.generation() == generation)
+      .count();
+    HashTableStats {
+      probes: self.probes.load(Ordering::Relaxed),
+      hits: self.hits.load(Ordering::Relaxed),
+      stores: self.stores.load(Ordering::Relaxed),
+      fill: filled * 1000 / (sample.len() * 2),
+    }
+  }
+
+  /// Writes entries of the table. The header contains the version of the
+  /// format, the key and the size of a machine word since the packing of
+  /// entries depends on it.
+  pub fn save<W: Write>(&self, key: HashTableKey, mut writer: W) -> io::Result<()> {
+    writer.write_all(&FILE_MAGIC)?;
+    write_u32(&mut writer, FILE_VERSION)?;
+    write_u32(&mut writer, usize::BITS)?;
+    write_u32(&mut writer, key.width)?;
+    write_u32(&mut writer, key.height)?;
+    write_u64(&mut writer, key.zobrist)?;
+    write_u32(&mut writer, key.scale as u32)?;
+    let entries = self
+      .buckets
+      .iter()
+      .flat_map(|bucket| [&bucket.depth_preferred, &bucket.always_replace])
+      .filter(|entry| entry.data().hash_type() != HashType::Empty);
+    write_u64(&mut writer, entries.clone().count() as u64)?;
+    for entry in entries {
+      let data = entry.data.load(Ordering::Relaxed);
+      let hash = entry.hash.load(Ordering::Relaxed) ^ data;
+      write_u64(&mut writer, hash as u64)?;
+      write_u64(&mut writer, data as u64)?;
+    }
+    writer.flush()
+  }
+
+  /// Puts entries written by `save` with the same key to the table, which gets
+  /// bound to the scale of the key. They are considered to be stored by the
+  /// current search, so they are not replaced before the next one. Returns the
+  /// number of read entries.
+  pub fn load<R: Read>(&self, key: HashTableKey, mut reader: R) -> io::Result<usize> {
+    let mut magic = [0; FILE_MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+    if magic != FILE_MAGIC {
+      return Err(invalid_data("Not a hash table file"));
+    }
+    if read_u32(&mut reader)? != FILE_VERSION {
+      return Err(invalid_data("Unsupported hash table file version"));
+    }
+    if read_u32(&mut reader)? != usize::BITS {
+      return Err(invalid_data(
+        "Hash table file is saved on a machine with another word size",
+      ));
+    }
+    let file_key = HashTableKey {
+      width: read_u32(&mut reader)?,
+      height: read_u32(&mut reader)?,
+      zobrist: read_u64(&mut reader)?,
+      scale: read_u32(&mut reader)? as i32,
+    };
+    if file_key.scale != key.scale {
+      return Err(invalid_data("Hash table file is saved for another evaluation"));
+    }
+    if file_key != key {
+      return Err(invalid_data("Hash table file is saved for another field"));
+    }
+    if !self.bind(key.scale) {
+      return Err(invalid_data("Hash table is used by another evaluation"));
+    }
+    let count = read_u64(&mut reader)? as usize;
+    for _ in 0..count {
+      let hash = read_u64(&mut reader)?;
+      let data = HashData {
+        data: read_u64(&mut reader)? as usize,
+      };
+      self.put(hash, data);
+    }
+    Ok(count)
   }
 
-  pub fn clear(&mut self) {
-    for entry in self.entries.iter_mut() {
-      *entry = HashEntry::default();
+  pub fn clear(&self) {
+    for bucket in self.buckets.iter() {
+      for entry in [&bucket.depth_preferred, &bucket.always_replace] {
+        entry.store(0, HashData::default(), 0);
+      }
     }
+    self.generation.store(0, Ordering::Relaxed);
+    self.probes.store(0, Ordering::Relaxed);
+    self.hits.store(0, Ordering::Relaxed);
+    self.stores.store(0, Ordering::Relaxed);
   }
 }
@@ -1,8 +1,9 @@
-use crate::hash_table::{HashData, HashTable, HashType};
+use crate::hash_table::{HashData, HashTable, HashTableKey, HashType};
+use std::io::ErrorKind;
 
 #[test]
 fn hash_table_put_get_one_entry() {
-  let hash_table = HashTable::new(100);
+  let hash_table = HashTable::new(100 * HashTable::BUCKET_SIZE);
   let hash = 1_234_567_890u64;
   let data = HashData::new(3, HashType::Exact, 17, 1234);
   hash_table.put(hash, data);
@@ -11,20 +12,65 @@ fn hash_table_put_get_one_entry() {
 
 #[test]
 fn hash_table_collision() {
-  let hash_table = HashTable::new(100);
+  let hash_table = HashTable::new(100 * HashTable::BUCKET_SIZE);
   let hash1 = 123u64;
   let hash2 = 723u64;
   let data1 = HashData::new(3, HashType::Exact, 17, 1234);
   let data2 = HashData::new(7, HashType::Alpha, 23, -4321);
   hash_table.put(hash1, data1);
   hash_table.put(hash2, data2);
+  assert_eq!(hash_table.get(hash1), data1);
+  assert_eq!(hash_table.get(hash2), data2);
+}
+
+#[test]
+fn hash_table_depth_preferred() {
+  let hash_table = HashTable::new(100 * HashTable::BUCKET_SIZE);
+  let hash1 = 123u64;
+  let hash2 = 723u64;
+  let hash3 = 1323u64;
+  let data1 = HashData::new(7, HashType::Exact, 17, 1234);
+  let data2 = HashData::new(3, HashType::Alpha, 23, -4321);
+  let data3 = HashData::new(5, HashType::Beta, 29, 42);
+  hash_table.put(hash1, data1);
+  hash_table.put(hash2, data2);
+  hash_table.put(hash3, data3);
+  assert_eq!(hash_table.get(hash1), data1);
+  assert_eq!(hash_table.get(hash2).hash_type(), HashType::Empty);
+  assert_eq!(hash_table.get(hash3), data3);
+}
+
+#[test]
+fn hash_table_old_generation_replace() {
+  let hash_table = HashTable::new(100 * HashTable::BUCKET_SIZE);
+  let hash1 = 123u64;
+  let hash2 = 723u64;
+  let data1 = HashData::new(7, HashType::Exact, 17, 1234);
+  let data2 = HashData::new(3, HashType::Alpha, 23, -4321);
+  hash_table.put(hash1, data1);
+  hash_table.next_generation();
+  hash_table.put(hash2, data2);
   assert_eq!(hash_table.get(hash1).hash_type(), HashType::Empty);
   assert_eq!(hash_table.get(hash2), data2);
 }
 
+#[test]
+fn hash_table_stats() {
+  let hash_table = HashTable::new(100 * HashTable::BUCKET_SIZE);
+  let hash = 1_234_567_890u64;
+  hash_table.put(hash, HashData::new(3, HashType::Exact, 17, 1234));
+  hash_table.get(hash);
+  hash_table.get(hash + 1);
+  let stats = hash_table.stats();
+  assert_eq!((stats.probes, stats.hits, stats.stores, stats.fill), (2, 1, 1, 5));
+  hash_table.next_generation();
+  let stats = hash_table.stats();
+  assert_eq!((stats.probes, stats.hits, stats.stores, stats.fill), (0, 0, 0, 0));
+}
+
 #[test]
 fn hash_table_priority_replace() {
-  let hash_table = HashTable::new(100);
+  let hash_table = HashTable::new(100 * HashTable::BUCKET_SIZE);
   let hash = 1_234_567_890u64;
   let data1 = HashData::new(3, HashType::Alpha, 17, 1234);
   let data2 = HashData::new(3, HashType::Exact, 19, 1237);
@@ -35,7 +81,7 @@ fn hash_table_priority_replace() {
 
 #[test]
 fn hash_table_priority_remain() {
-  let hash_table = HashTable::new(100);
+  let hash_table = HashTable::new(100 * HashTable::BUCKET_SIZE);
   let hash = 1_234_567_890u64;
   let data1 = HashData::new(3, HashType::Exact, 17, 1234);
   let data2 = HashData::new(3, HashType::Alpha, 19, 1233);
@@ -43,3 +89,80 @@ fn hash_table_priority_remain() {
   hash_table.put(hash, data2);
   assert_eq!(hash_table.get(hash), data1);
 }
+
+const KEY: HashTableKey = HashTableKey {
+  width: 10,
+  height: 12,
+  zobrist: 0x0123_4567_89ab_cdef,
+  scale: 1,
+};
+
+#[test]
+fn hash_table_save_load() {
+  let hash_table = HashTable::new(100 * HashTable::BUCKET_SIZE);
+  let data1 = HashData::new(7, HashType::Exact, 17, 1234);
+  let data2 = HashData::new(3, HashType::Alpha, 23, -4321);
+  hash_table.put(123, data1);
+  hash_table.put(1_234_567_890, data2);
+  let mut buffer = Vec::new();
+  hash_table.save(KEY, &mut buffer).unwrap();
+  let loaded = HashTable::new(50 * HashTable::BUCKET_SIZE);
+  assert_eq!(loaded.load(KEY, buffer.as_slice()).unwrap(), 2);
+  assert_eq!(loaded.get(123), data1);
+  assert_eq!(loaded.get(1_234_567_890), data2);
+}
+
+#[test]
+fn hash_table_load_another_key() {
+  let hash_table = HashTable::new(100 * HashTable::BUCKET_SIZE);
+  hash_table.put(123, HashData::new(7, HashType::Exact, 17, 1234));
+  let mut buffer = Vec::new();
+  hash_table.save(KEY, &mut buffer).unwrap();
+  let key = HashTableKey { width: 11, ..KEY };
+  let loaded = HashTable::new(100 * HashTable::BUCKET_SIZE);
+  assert_eq!(
+    loaded.load(key, buffer.as_slice()).unwrap_err().kind(),
+    ErrorKind::InvalidData
+  );
+  assert_eq!(loaded.get(123).hash_type(), HashType::Empty);
+}
+
+#[test]
+fn hash_table_load_another_scale() {
+  let hash_table = HashTable::new(100 * HashTable::BUCKET_SIZE);
+  hash_table.put(123, HashData::new(7, HashType::Exact, 17, 1234));
+  let mut buffer = Vec::new();
+  hash_table.save(KEY, &mut buffer).unwrap();
+  let key = HashTableKey { scale: 32, ..KEY };
+  let loaded = HashTable::new(100 * HashTable::BUCKET_SIZE);
+  assert_eq!(
+    loaded.load(key, buffer.as_slice()).unwrap_err().kind(),
+    ErrorKind::InvalidData
+  );
+  assert_eq!(loaded.get(123).hash_type(), HashType::Empty);
+}
+
+#[test]
+fn hash_table_load_bound_to_another_scale() {
+  let hash_table = HashTable::new(100 * HashTable::BUCKET_SIZE);
+  let mut buffer = Vec::new();
+  hash_table.save(KEY, &mut buffer).unwrap();
+  let loaded = HashTable::new(100 * HashTable::BUCKET_SIZE);
+  assert!(loaded.bind(32));
+  assert_eq!(
+    loaded.load(KEY, buffer.as_slice()).unwrap_err().kind(),
+    ErrorKind::InvalidData
+  );
+}
+
+#[test]
+fn hash_table_load_another_version() {
+  let hash_table = HashTable::new(100 * HashTable::BUCKET_SIZE);
+  let mut buffer = Vec::new();
+  hash_table.save(KEY, &mut buffer).unwrap();
+  buffer[8] += 1;
+  assert_eq!(
+    hash_table.load(KEY, buffer.as_slice()).unwrap_err().kind(),
+    ErrorKind::InvalidData
+  );
+}
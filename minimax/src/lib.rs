@@ -3,10 +3,19 @@
 #[macro_use]
 extern crate log;
 
+pub mod df_pn;
+#[cfg(test)]
+mod df_pn_test;
+pub mod evaluation;
+#[cfg(test)]
+mod evaluation_test;
 pub mod hash_table;
 #[cfg(test)]
 mod hash_table_test;
 pub mod minimax;
 #[cfg(test)]
 mod minimax_test;
+pub mod move_ordering;
+#[cfg(test)]
+mod move_ordering_test;
 pub mod trajectories_pruning;
@@ -1,15 +1,19 @@
-use crate::hash_table::{HashData, HashTable, HashType};
+use crate::evaluation::{Evaluation, MaterialEvaluation};
+use crate::hash_table::{HashData, HashTable, HashTableStats, HashType};
+use crate::move_ordering::MoveOrdering;
 use crate::trajectories_pruning::TrajectoriesPruning;
 #[cfg(not(target_arch = "wasm32"))]
 use crossbeam::{self, queue::SegQueue};
 use oppai_common::common;
 use oppai_field::field::{Field, NonZeroPos, Pos};
 use oppai_field::player::Player;
-use std::iter;
+use oppai_ladders::ladders::losing_ladder_moves;
 #[cfg(not(target_arch = "wasm32"))]
-use std::sync::atomic::AtomicIsize;
+use std::sync::atomic::{AtomicBool, AtomicIsize};
+use std::sync::atomic::{AtomicUsize, Ordering};
 #[cfg(not(target_arch = "wasm32"))]
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::{cmp::Reverse, iter, sync::Arc};
 use strum::{EnumString, VariantNames};
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, EnumString, VariantNames)]
@@ -22,8 +26,24 @@ pub enum MinimaxType {
 pub struct MinimaxConfig {
   pub threads_count: usize,
   pub minimax_type: MinimaxType,
-  pub hash_table_size: usize,
+  /// Memory of the hash table in megabytes.
+  pub hash_table_memory: usize,
   pub rebuild_trajectories: bool,
+  /// Makes the search result independent of the threads scheduling at the cost
+  /// of searching all root moves with the initial alpha.
+  pub deterministic: bool,
+  /// Number of best root moves to evaluate with exact estimations.
+  pub multi_pv: usize,
+  /// Solve independent groups of trajectories separately and combine their
+  /// results as a sum of games.
+  pub split_trajectories: bool,
+  /// Order moves by killer, history and counter-move heuristics.
+  pub move_ordering: bool,
+  /// Maximum number of capturing and escaping moves searched beyond the depth
+  /// limit. Zero disables the quiescence search.
+  pub quiescence_depth: u32,
+  /// Exclude root moves that start or extend a ladder losing for the player.
+  pub forbid_losing_ladders: bool,
 }
 
 impl Default for MinimaxConfig {
@@ -34,22 +54,127 @@ impl Default for MinimaxConfig {
       #[cfg(target_arch = "wasm32")]
       threads_count: 1,
       minimax_type: MinimaxType::NegaScout,
-      hash_table_size: 10000,
+      hash_table_memory: 16,
       rebuild_trajectories: false,
+      deterministic: false,
+      multi_pv: 1,
+      split_trajectories: false,
+      move_ordering: true,
+      quiescence_depth: 0,
+      forbid_losing_ladders: false,
     }
   }
 }
 
-#[derive(Clone)]
-pub struct Minimax {
+pub struct Minimax<E = MaterialEvaluation> {
   config: MinimaxConfig,
-  hash_table: HashTable,
+  /// The hash table can be shared with other instances.
+  hash_table: Arc<HashTable>,
+  move_ordering: MoveOrdering,
+  /// Number of positions visited by the alpha-beta search.
+  nodes: AtomicUsize,
+  evaluation: E,
+}
+
+impl<E: Clone> Clone for Minimax<E> {
+  fn clone(&self) -> Self {
+    Self {
+      config: self.config.clone(),
+      hash_table: Arc::new(self.hash_table.as_ref().clone()),
+      move_ordering: self.move_ordering.clone(),
+      nodes: AtomicUsize::new(self.nodes.load(Ordering::Relaxed)),
+      evaluation: self.evaluation.clone(),
+    }
+  }
 }
 
 impl Minimax {
   pub fn new(config: MinimaxConfig) -> Minimax {
-    let hash_table = HashTable::new(config.hash_table_size);
-    Minimax { config, hash_table }
+    Self::with_evaluation(config, MaterialEvaluation)
+  }
+}
+
+impl<E: Evaluation> Minimax<E> {
+  pub fn with_evaluation(config: MinimaxConfig, evaluation: E) -> Minimax<E> {
+    let hash_table = Arc::new(HashTable::new(config.hash_table_memory * 1024 * 1024));
+    Self::with_hash_table(config, evaluation, hash_table).expect("A new hash table is bound to any evaluation")
+  }
+
+  /// Creates an instance that uses the given hash table instead of its own,
+  /// so that several instances can reuse results of each other. The table
+  /// size from the config is ignored. Generations and statistics of the table
+  /// are shared too, so a search started by one instance ages entries of the
+  /// others. Returns `None` if the table stores estimations of an evaluation
+  /// with another scale.
+  pub fn with_hash_table(config: MinimaxConfig, evaluation: E, hash_table: Arc<HashTable>) -> Option<Minimax<E>> {
+    if !hash_table.bind(evaluation.scale()) {
+      return None;
+    }
+    Some(Minimax {
+      config,
+      hash_table,
+      move_ordering: MoveOrdering::new(),
+      nodes: AtomicUsize::new(0),
+      evaluation,
+    })
+  }
+
+  fn move_ordering(&self) -> Option<&MoveOrdering> {
+    Some(&self.move_ordering).filter(|_| self.config.move_ordering)
+  }
+
+  /// Prepares heuristics and statistics for a new search.
+  fn start_search(&self) {
+    self.hash_table.next_generation();
+    self.move_ordering.age();
+    self.nodes.store(0, Ordering::Relaxed);
+  }
+
+  /// Logs statistics of the finished search.
+  fn finish_search(&self) {
+    info!(
+      "Visited {} nodes, hash table: {}.",
+      self.nodes(),
+      self.hash_table_stats()
+    );
+  }
+
+  /// Statistics of the hash table since the start of the last search.
+  pub fn hash_table_stats(&self) -> HashTableStats {
+    self.hash_table.stats()
+  }
+
+  /// Number of positions visited by the last search.
+  pub fn nodes(&self) -> usize {
+    self.nodes.load(Ordering::Relaxed)
+  }
+
+  /// Root moves that start or extend a ladder losing for the player.
+  fn forbidden_moves<SS: Fn() -> bool>(&self, field: &mut Field, player: Player, should_stop: &SS) -> Vec<Pos> {
+    if self.config.forbid_losing_ladders {
+      losing_ladder_moves(field, player, should_stop)
+    } else {
+      Vec::new()
+    }
+  }
+
+  /// Removes forbidden moves from the root moves unless there are no other
+  /// moves.
+  fn forbid(trajectories_pruning: &mut TrajectoriesPruning, forbidden_moves: &[Pos]) {
+    let moves = trajectories_pruning.moves_mut();
+    if moves.iter().any(|pos| !forbidden_moves.contains(pos)) {
+      moves.retain(|pos| !forbidden_moves.contains(pos));
+    }
+  }
+
+  /// Bounds of the estimation given bounds of the score from trajectories.
+  fn bounds(&self, field: &mut Field, player: Player, trajectories_pruning: &TrajectoriesPruning) -> (i32, i32) {
+    let score = field.score(player);
+    let scale = self.evaluation.scale();
+    let margin = self.evaluation.margin();
+    let alpha = trajectories_pruning.alpha().unwrap_or(score) * scale - margin;
+    let beta = trajectories_pruning.beta().unwrap_or(score) * scale + margin;
+    (alpha, beta)
   }
 
   #[inline]
@@ -63,6 +188,79 @@ impl Minimax {
     hash_table.put(hash, new_hash_value);
   }
 
+  /// Searches only capturing and escaping moves, so that the estimation isn't
+  /// taken in the middle of a fight. The player may stop capturing at any
+  /// moment, but if the enemy threatens to capture something, stopping means
+  /// giving the move to the enemy.
+  fn quiescence<SS: Fn() -> bool>(
+    field: &mut Field,
+    player: Player,
+    depth: u32,
+    alpha: i32,
+    beta: i32,
+    empty_board: &mut [u32],
+    nodes: &AtomicUsize,
+    evaluation: &E,
+    should_stop: &SS,
+  ) -> i32 {
+    if depth == 0 || should_stop() {
+      return evaluation.evaluate(field, player);
+    }
+    let captures = TrajectoriesPruning::captures(field, player, empty_board, should_stop);
+    let threatened = captures.is_threatened();
+    if !threatened && !captures.can_capture() {
+      return evaluation.evaluate(field, player);
+    }
+    let trajectories_pruning = TrajectoriesPruning::quiescence(field, player, empty_board, should_stop);
+    let enemy = player.next();
+    let stand_pat = if threatened {
+      nodes.fetch_add(1, Ordering::Relaxed);
+      -Self::quiescence(
+        field,
+        enemy,
+        depth - 1,
+        -beta,
+        -alpha,
+        empty_board,
+        nodes,
+        evaluation,
+        should_stop,
+      )
+    } else {
+      evaluation.evaluate(field, player)
+    };
+    if stand_pat >= beta {
+      return stand_pat;
+    }
+    let mut cur_alpha = alpha.max(stand_pat);
+    for &pos in trajectories_pruning.moves() {
+      nodes.fetch_add(1, Ordering::Relaxed);
+      field.put_point(pos, player);
+      let cur_estimation = -Self::quiescence(
+        field,
+        enemy,
+        depth - 1,
+        -beta,
+        -cur_alpha,
+        empty_board,
+        nodes,
+        evaluation,
+        should_stop,
+      );
+      field.undo();
+      if should_stop() {
+        return cur_alpha;
+      }
+      if cur_estimation > cur_alpha {
+        cur_alpha = cur_estimation;
+        if cur_alpha >= beta {
+          break;
+        }
+      }
+    }
+    cur_alpha
+  }
+
   fn alpha_beta<SS: Fn() -> bool>(
     field: &mut Field,
     depth: u32,
@@ -73,23 +271,42 @@ impl Minimax {
     beta: i32,
     empty_board: &mut Vec<u32>,
     hash_table: &HashTable,
+    move_ordering: Option<&MoveOrdering>,
+    nodes: &AtomicUsize,
+    quiescence_depth: u32,
+    evaluation: &E,
     should_stop: &SS,
   ) -> i32 {
     if should_stop() {
       return alpha;
     }
+    nodes.fetch_add(1, Ordering::Relaxed);
     let enemy = player.next();
     if let Some(last_pos) = last_pos {
       if common::is_last_move_stupid(field, last_pos.get(), enemy) {
-        return i32::max_value();
+        return i32::MAX;
       }
     }
     if depth == 0 {
-      return field.score(player);
+      return if quiescence_depth > 0 {
+        Self::quiescence(
+          field,
+          player,
+          quiescence_depth,
+          alpha,
+          beta,
+          empty_board,
+          nodes,
+          evaluation,
+          should_stop,
+        )
+      } else {
+        evaluation.evaluate(field, player)
+      };
     }
     let moves = trajectories_pruning.moves();
     if moves.is_empty() {
-      return field.score(player);
+      return evaluation.evaluate(field, player);
     }
     let mut cur_alpha = alpha;
     let hash_value = hash_table.get(field.colored_hash(player));
@@ -117,7 +334,7 @@ impl Minimax {
     };
     if last_pos.is_some() && beta - alpha > 1 {
       let enemy_trajectories_pruning = trajectories_pruning.dec_and_swap(depth - 1, empty_board);
-      let cur_estimation = -Minimax::alpha_beta(
+      let cur_estimation = -Self::alpha_beta(
         field,
         depth - 1,
         None,
@@ -127,6 +344,10 @@ impl Minimax {
         -beta + 1,
         empty_board,
         hash_table,
+        move_ordering,
+        nodes,
+        quiescence_depth,
+        evaluation,
         should_stop,
       );
       if cur_estimation >= beta {
@@ -138,11 +359,11 @@ impl Minimax {
       field.put_point(hash_pos, player);
       if common::is_penult_move_stupid(field) {
         field.undo();
-        return i32::max_value();
+        return i32::MAX;
       }
       let next_trajectories_pruning =
         trajectories_pruning.next(field, enemy, depth - 1, empty_board, hash_pos, should_stop);
-      let cur_estimation = -Minimax::alpha_beta(
+      let cur_estimation = -Self::alpha_beta(
         field,
         depth - 1,
         NonZeroPos::new(hash_pos),
@@ -152,6 +373,10 @@ impl Minimax {
         -cur_alpha,
         empty_board,
         hash_table,
+        move_ordering,
+        nodes,
+        quiescence_depth,
+        evaluation,
         should_stop,
       );
       field.undo();
@@ -162,7 +387,7 @@ impl Minimax {
         return cur_alpha;
       }
       if cur_estimation > cur_alpha {
-        Minimax::put_new_hash_value(
+        Self::put_new_hash_value(
           hash_table,
           field.colored_hash(player),
           hash_pos,
@@ -172,19 +397,31 @@ impl Minimax {
         );
         cur_alpha = cur_estimation;
         if cur_alpha >= beta {
+          if let Some(move_ordering) = move_ordering {
+            move_ordering.cutoff(player, depth, last_pos.map(NonZeroPos::get), hash_pos);
+          }
           return cur_alpha;
         }
       }
     }
+    let ordered_moves;
+    let moves = if let Some(move_ordering) = move_ordering {
+      let mut moves = moves.clone();
+      move_ordering.sort(player, depth, last_pos.map(NonZeroPos::get), &mut moves);
+      ordered_moves = moves;
+      &ordered_moves
+    } else {
+      moves
+    };
     // For all moves instead the one from the hash table.
     for &pos in moves.iter().filter(|&&pos| Some(pos) != hash_pos_option) {
       field.put_point(pos, player);
       if common::is_penult_move_stupid(field) {
         field.undo();
-        return i32::max_value();
+        return i32::MAX;
       }
       let next_trajectories_pruning = trajectories_pruning.next(field, enemy, depth - 1, empty_board, pos, should_stop);
-      let mut cur_estimation = -Minimax::alpha_beta(
+      let mut cur_estimation = -Self::alpha_beta(
         field,
         depth - 1,
         NonZeroPos::new(pos),
@@ -194,10 +431,14 @@ impl Minimax {
         -cur_alpha,
         empty_board,
         hash_table,
+        move_ordering,
+        nodes,
+        quiescence_depth,
+        evaluation,
         should_stop,
       );
       if cur_estimation > cur_alpha && cur_estimation < beta {
-        cur_estimation = -Minimax::alpha_beta(
+        cur_estimation = -Self::alpha_beta(
           field,
           depth - 1,
           NonZeroPos::new(pos),
@@ -207,6 +448,10 @@ impl Minimax {
           -cur_estimation,
           empty_board,
           hash_table,
+          move_ordering,
+          nodes,
+          quiescence_depth,
+          evaluation,
           should_stop,
         );
       }
@@ -218,9 +463,12 @@ impl Minimax {
         return cur_alpha;
       }
       if cur_estimation > cur_alpha {
-        Minimax::put_new_hash_value(hash_table, field.colored_hash(player), pos, depth, cur_estimation, beta);
+        Self::put_new_hash_value(hash_table, field.colored_hash(player), pos, depth, cur_estimation, beta);
         cur_alpha = cur_estimation;
         if cur_alpha >= beta {
+          if let Some(move_ordering) = move_ordering {
+            move_ordering.cutoff(player, depth, last_pos.map(NonZeroPos::get), pos);
+          }
           break;
         }
       }
@@ -248,7 +496,7 @@ impl Minimax {
       depth, player, beta
     );
     if depth == 0 || should_stop() {
-      return field.score(player);
+      return self.evaluation.evaluate(field, player);
     }
     let moves = trajectories_pruning.moves();
     debug!(
@@ -259,20 +507,21 @@ impl Minimax {
         .collect::<Vec<(u32, u32)>>()
     );
     if moves.is_empty() || should_stop() {
-      return field.score(player);
+      return self.evaluation.evaluate(field, player);
     }
     #[cfg(not(target_arch = "wasm32"))]
     {
-      let queue = SegQueue::new();
-      if let Some(best_pos) = *best_move {
-        queue.push(best_pos.get());
-        for &pos in moves.iter().filter(|&&pos| pos != best_pos.get()) {
-          queue.push(pos);
-        }
+      let deterministic = self.config.deterministic;
+      let order = if let Some(best_pos) = *best_move {
+        iter::once(best_pos.get())
+          .chain(moves.iter().filter(|&&pos| pos != best_pos.get()).copied())
+          .collect::<Vec<_>>()
       } else {
-        for &pos in moves.iter() {
-          queue.push(pos);
-        }
+        moves.clone()
+      };
+      let queue = SegQueue::new();
+      for &pos in order.iter() {
+        queue.push(pos);
       }
       let atomic_alpha = AtomicIsize::new(alpha as isize);
       let best_moves = SegQueue::new();
@@ -282,7 +531,7 @@ impl Minimax {
         for _ in 0..self.config.threads_count {
           scope.spawn(|_| {
             let mut local_field = field.clone();
-            let mut local_empty_board = iter::repeat(0u32).take(field.length()).collect::<Vec<_>>();
+            let mut local_empty_board = iter::repeat_n(0u32, field.length()).collect::<Vec<_>>();
             let mut local_best_move = 0;
             let mut local_alpha = alpha;
             let enemy = player.next();
@@ -302,12 +551,19 @@ impl Minimax {
               if should_stop() {
                 break;
               }
-              let cur_alpha = atomic_alpha.load(Ordering::Relaxed) as i32;
+              // In the deterministic mode alpha isn't shared between threads, so
+              // every root move gets the same estimation regardless of the order
+              // in which they are searched.
+              let cur_alpha = if deterministic {
+                alpha
+              } else {
+                atomic_alpha.load(Ordering::Relaxed) as i32
+              };
               if cur_alpha >= beta {
                 skipped_moves.push(pos);
                 break;
               }
-              let mut cur_estimation = -Minimax::alpha_beta(
+              let mut cur_estimation = -Self::alpha_beta(
                 &mut local_field,
                 depth - 1,
                 NonZeroPos::new(pos),
@@ -317,13 +573,17 @@ impl Minimax {
                 -cur_alpha,
                 &mut local_empty_board,
                 &self.hash_table,
+                self.move_ordering(),
+                &self.nodes,
+                self.config.quiescence_depth,
+                &self.evaluation,
                 should_stop,
               );
               if should_stop() {
                 break;
               }
               if cur_estimation > cur_alpha && cur_estimation < beta {
-                cur_estimation = -Minimax::alpha_beta(
+                cur_estimation = -Self::alpha_beta(
                   &mut local_field,
                   depth - 1,
                   NonZeroPos::new(pos),
@@ -333,6 +593,10 @@ impl Minimax {
                   -cur_estimation,
                   &mut local_empty_board,
                   &self.hash_table,
+                  self.move_ordering(),
+                  &self.nodes,
+                  self.config.quiescence_depth,
+                  &self.evaluation,
                   should_stop,
                 );
               }
@@ -341,6 +605,10 @@ impl Minimax {
               if should_stop() {
                 break;
               }
+              // Estimations above beta depend on the hash table content.
+              if deterministic {
+                cur_estimation = cur_estimation.min(beta);
+              }
               debug!(
                 "Estimation for move ({}, {}) is {}, alpha is {}, beta is {}.",
                 field.to_x(pos),
@@ -350,7 +618,9 @@ impl Minimax {
                 beta
               );
               local_field.undo();
-              if cur_estimation > cur_alpha {
+              if deterministic && cur_estimation > cur_alpha {
+                best_moves.push((pos, cur_estimation));
+              } else if cur_estimation > cur_alpha {
                 local_alpha = cur_estimation;
                 local_best_move = pos;
               }
@@ -377,10 +647,14 @@ impl Minimax {
       .expect("Minimax alpha_beta_parallel panic");
       let mut result = 0;
       let best_alpha = atomic_alpha.load(Ordering::SeqCst) as i32;
+      let mut best_moves = iter::from_fn(|| best_moves.pop()).collect::<Vec<_>>();
+      if deterministic {
+        best_moves.sort_by_key(|&(pos, _)| order.iter().position(|&order_pos| order_pos == pos));
+      }
       if best_alpha > alpha {
         let moves = trajectories_pruning.moves_mut();
         moves.clear();
-        while let Some((pos, pos_alpha)) = best_moves.pop() {
+        for (pos, pos_alpha) in best_moves {
           if pos_alpha == best_alpha || pos_alpha >= beta {
             moves.push(pos);
           }
@@ -410,7 +684,7 @@ impl Minimax {
     #[cfg(target_arch = "wasm32")]
     {
       let mut best_alpha = alpha;
-      let mut empty_board = iter::repeat(0u32).take(field.length()).collect::<Vec<_>>();
+      let mut empty_board = iter::repeat_n(0u32, field.length()).collect::<Vec<_>>();
       let enemy = player.next();
       let first_pos = best_move.map_or(0, |pos| pos.get());
       for pos in NonZeroPos::new(first_pos)
@@ -427,7 +701,7 @@ impl Minimax {
         if should_stop() {
           break;
         }
-        let mut cur_estimation = -Minimax::alpha_beta(
+        let mut cur_estimation = -Self::alpha_beta(
           field,
           depth - 1,
           NonZeroPos::new(pos),
@@ -437,13 +711,17 @@ impl Minimax {
           -best_alpha,
           &mut empty_board,
           &self.hash_table,
+          self.move_ordering(),
+          &self.nodes,
+          self.config.quiescence_depth,
+          &self.evaluation,
           should_stop,
         );
         if should_stop() {
           break;
         }
         if cur_estimation > best_alpha && cur_estimation < beta {
-          cur_estimation = -Minimax::alpha_beta(
+          cur_estimation = -Self::alpha_beta(
             field,
             depth - 1,
             NonZeroPos::new(pos),
@@ -453,6 +731,10 @@ impl Minimax {
             -cur_estimation,
             &mut empty_board,
             &self.hash_table,
+            self.move_ordering(),
+            &self.nodes,
+            self.config.quiescence_depth,
+            &self.evaluation,
             should_stop,
           );
         }
@@ -492,8 +774,7 @@ impl Minimax {
     best_move: &mut Option<NonZeroPos>,
     should_stop: &SS,
   ) -> i32 {
-    let mut alpha = trajectories_pruning.alpha().unwrap_or_else(|| field.score(player));
-    let mut beta = trajectories_pruning.beta().unwrap_or_else(|| field.score(player));
+    let (mut alpha, mut beta) = self.bounds(field, player, trajectories_pruning);
     while alpha != beta {
       if let [single_move] = *trajectories_pruning.moves().as_slice() {
         *best_move = NonZeroPos::new(single_move);
@@ -537,8 +818,7 @@ impl Minimax {
     best_move: &mut Option<NonZeroPos>,
     should_stop: &SS,
   ) -> i32 {
-    let alpha = trajectories_pruning.alpha().unwrap_or_else(|| field.score(player));
-    let beta = trajectories_pruning.beta().unwrap_or_else(|| field.score(player));
+    let (alpha, beta) = self.bounds(field, player, trajectories_pruning);
     self.alpha_beta_parallel(
       field,
       player,
@@ -562,7 +842,23 @@ impl Minimax {
     if depth == 0 {
       return (None, field.score(player));
     }
-    let mut empty_board = iter::repeat(0u32).take(field.length()).collect::<Vec<_>>();
+    self.start_search();
+    let forbidden_moves = self.forbidden_moves(field, player, should_stop);
+    let result = self.minimax_depth(field, player, depth, &forbidden_moves, should_stop);
+    self.finish_search();
+    result
+  }
+
+  /// Searches to the given depth as a part of the started search.
+  fn minimax_depth<SS: Fn() -> bool + Sync>(
+    &self,
+    field: &mut Field,
+    player: Player,
+    depth: u32,
+    forbidden_moves: &[Pos],
+    should_stop: &SS,
+  ) -> (Option<NonZeroPos>, i32) {
+    let mut empty_board = iter::repeat_n(0u32, field.length()).collect::<Vec<_>>();
     let mut trajectories_pruning = TrajectoriesPruning::new(
       self.config.rebuild_trajectories,
       field,
@@ -571,11 +867,25 @@ impl Minimax {
       &mut empty_board,
       should_stop,
     );
+    Self::forbid(&mut trajectories_pruning, forbidden_moves);
+    if self.config.split_trajectories {
+      if let Some(result) = self.minimax_groups(
+        field,
+        player,
+        depth,
+        &trajectories_pruning,
+        forbidden_moves,
+        &mut empty_board,
+        should_stop,
+      ) {
+        return result;
+      }
+    }
     let mut best_move = None;
     info!("Calculating of our estimation. Player is {}", player);
     let minimax_function = match self.config.minimax_type {
-      MinimaxType::NegaScout => Minimax::nega_scout,
-      MinimaxType::Mtdf => Minimax::mtdf,
+      MinimaxType::NegaScout => Self::nega_scout,
+      MinimaxType::Mtdf => Self::mtdf,
     };
     let estimation = minimax_function(
       self,
@@ -596,7 +906,7 @@ impl Minimax {
     );
     // Check if we could lose something if we don't make the current best move.
     // If we couldn't that means that the current best move is just a random move.
-    if -self.alpha_beta_parallel(
+    let enemy_estimation = -self.alpha_beta_parallel(
       field,
       enemy,
       depth - 1,
@@ -605,35 +915,141 @@ impl Minimax {
       &mut enemy_trajectories_pruning,
       &mut enemy_best_move,
       should_stop,
-    ) < estimation
-    {
+    );
+    if enemy_estimation < estimation {
       info!(
         "Estimation is greater than enemy estimation. So the best move is {:?}, estimation is {}.",
         best_move.map(|pos| (field.to_x(pos.get()), field.to_y(pos.get()))),
         estimation
       );
-      (best_move, estimation)
+      (best_move, self.evaluation.to_score(estimation))
     } else {
       info!(
         "Estimation is less than or equal enemy estimation. So all moves have the same estimation {}.",
         estimation
       );
-      (None, estimation)
+      (None, self.evaluation.to_score(estimation))
     }
   }
 
+  /// Solves independent groups of trajectories separately. Every group is
+  /// estimated with our move first and with the enemy move first, and then
+  /// players alternately take the group where the move changes its result the
+  /// most. Returns `None` if there are less than two groups.
+  fn minimax_groups<SS: Fn() -> bool + Sync>(
+    &self,
+    field: &mut Field,
+    player: Player,
+    depth: u32,
+    trajectories_pruning: &TrajectoriesPruning,
+    forbidden_moves: &[Pos],
+    empty_board: &mut [u32],
+    should_stop: &SS,
+  ) -> Option<(Option<NonZeroPos>, i32)> {
+    let groups = trajectories_pruning.split(field, empty_board);
+    if groups.len() < 2 {
+      return None;
+    }
+    info!("Solving {} independent groups of trajectories.", groups.len());
+    let minimax_function = match self.config.minimax_type {
+      MinimaxType::NegaScout => Self::nega_scout,
+      MinimaxType::Mtdf => Self::mtdf,
+    };
+    let enemy = player.next();
+    let score = field.score(player);
+    let mut results = Vec::with_capacity(groups.len());
+    for mut group in groups {
+      Self::forbid(&mut group, forbidden_moves);
+      let mut best_move = None;
+      let estimation = minimax_function(self, field, player, &mut group, depth, &mut best_move, should_stop);
+      let mut enemy_group = group.dec_and_swap(depth - 1, empty_board);
+      let (alpha, beta) = self.bounds(field, enemy, &enemy_group);
+      let enemy_estimation = -self.alpha_beta_parallel(
+        field,
+        enemy,
+        depth - 1,
+        alpha,
+        beta,
+        &mut enemy_group,
+        &mut None,
+        should_stop,
+      );
+      debug!(
+        "Group with moves {:?} has estimation {} and enemy estimation {}.",
+        group
+          .moves()
+          .iter()
+          .map(|&pos| (field.to_x(pos), field.to_y(pos)))
+          .collect::<Vec<_>>(),
+        estimation,
+        enemy_estimation
+      );
+      results.push((best_move, estimation, enemy_estimation));
+    }
+    results.sort_by_key(|&(_, estimation, enemy_estimation)| Reverse(estimation - enemy_estimation));
+    let estimation = score
+      + results
+        .iter()
+        .enumerate()
+        .map(|(i, &(_, estimation, enemy_estimation))| {
+          let estimation = if i % 2 == 0 { estimation } else { enemy_estimation };
+          self.evaluation.to_score(estimation) - score
+        })
+        .sum::<i32>();
+    let (best_move, best_estimation, enemy_estimation) = results[0];
+    // If the move doesn't change the result of any group then it's just a
+    // random move.
+    let best_move = best_move.filter(|_| best_estimation > enemy_estimation);
+    info!(
+      "Best move of groups is {:?}, estimation is {}.",
+      best_move.map(|pos| (field.to_x(pos.get()), field.to_y(pos.get()))),
+      estimation
+    );
+    Some((best_move, estimation))
+  }
+
+  /// Iterative deepening for split trajectories. Trajectories are rebuilt on
+  /// every iteration since groups may change with depth, but all iterations
+  /// are one search for the hash table, move ordering and statistics.
+  fn split_minimax_with_time<SS: Fn() -> bool + Sync>(
+    &self,
+    field: &mut Field,
+    player: Player,
+    should_stop: &SS,
+  ) -> (Option<NonZeroPos>, i32, u32) {
+    self.start_search();
+    let forbidden_moves = self.forbidden_moves(field, player, should_stop);
+    let mut depth = 1;
+    let mut result = (None, field.score(player));
+    while !should_stop() {
+      let cur_result = self.minimax_depth(field, player, depth, &forbidden_moves, should_stop);
+      if should_stop() {
+        break;
+      }
+      result = cur_result;
+      depth += 1;
+    }
+    self.finish_search();
+    (result.0, result.1, depth - 1)
+  }
+
   pub fn minimax_with_time<SS: Fn() -> bool + Sync>(
     &self,
     field: &mut Field,
     player: Player,
     should_stop: &SS,
   ) -> (Option<NonZeroPos>, i32, u32) {
+    if self.config.split_trajectories {
+      return self.split_minimax_with_time(field, player, should_stop);
+    }
+    self.start_search();
     let enemy = player.next();
     let mut depth = 1;
     let mut best_move = None;
     let mut cur_best_move = None;
     let mut enemy_best_move = None;
-    let mut empty_board = iter::repeat(0u32).take(field.length()).collect::<Vec<_>>();
+    let mut empty_board = iter::repeat_n(0u32, field.length()).collect::<Vec<_>>();
+    let forbidden_moves = self.forbidden_moves(field, player, should_stop);
     let mut trajectories_pruning = TrajectoriesPruning::new(
       self.config.rebuild_trajectories,
       field,
@@ -642,11 +1058,12 @@ impl Minimax {
       &mut empty_board,
       should_stop,
     );
+    Self::forbid(&mut trajectories_pruning, &forbidden_moves);
     let minimax_function = match self.config.minimax_type {
-      MinimaxType::NegaScout => Minimax::nega_scout,
-      MinimaxType::Mtdf => Minimax::mtdf,
+      MinimaxType::NegaScout => Self::nega_scout,
+      MinimaxType::Mtdf => Self::mtdf,
     };
-    let mut estimation = field.score(player);
+    let mut estimation = self.evaluation.evaluate(field, player);
     while !should_stop() {
       estimation = minimax_function(
         self,
@@ -698,11 +1115,234 @@ impl Minimax {
       }
       depth += 1;
       trajectories_pruning = trajectories_pruning.inc(field, player, depth, &mut empty_board, should_stop);
+      Self::forbid(&mut trajectories_pruning, &forbidden_moves);
+    }
+    self.finish_search();
+    (best_move, self.evaluation.to_score(estimation), depth - 1)
+  }
+
+  /// Alpha for the next root move of the multi-PV search. A move should be
+  /// better than skipping the move and not worse than the last of `count`
+  /// best moves found so far.
+  fn multi_pv_alpha(best_moves: &[(usize, Pos, i32)], count: usize, pass_estimation: i32) -> i32 {
+    if best_moves.len() < count {
+      pass_estimation
+    } else {
+      best_moves[count - 1].2 - 1
+    }
+  }
+
+  /// Adds the root move to the best moves keeping them ordered by estimation
+  /// and then by the order of moves, so that the result doesn't depend on the
+  /// order in which the moves were searched.
+  fn multi_pv_add(best_moves: &mut Vec<(usize, Pos, i32)>, count: usize, index: usize, pos: Pos, estimation: i32) {
+    best_moves.push((index, pos, estimation));
+    best_moves.sort_by_key(|&(index, _, estimation)| (-estimation, index));
+    best_moves.truncate(count);
+  }
+
+  /// Evaluates up to `multi_pv` best moves with exact estimations, sorted from
+  /// the best one. Moves that are not better than skipping the move are
+  /// omitted.
+  pub fn multi_pv<SS: Fn() -> bool + Sync>(
+    &self,
+    field: &mut Field,
+    player: Player,
+    depth: u32,
+    should_stop: &SS,
+  ) -> Vec<(Pos, i32)> {
+    info!(
+      "Starting multi-PV minimax with depth {}, player {} and {} moves.",
+      depth, player, self.config.multi_pv
+    );
+    let count = self.config.multi_pv;
+    if depth == 0 || count == 0 {
+      return Vec::new();
+    }
+    self.start_search();
+    let mut empty_board = iter::repeat_n(0u32, field.length()).collect::<Vec<_>>();
+    let mut trajectories_pruning = TrajectoriesPruning::new(
+      self.config.rebuild_trajectories,
+      field,
+      player,
+      depth,
+      &mut empty_board,
+      should_stop,
+    );
+    let forbidden_moves = self.forbidden_moves(field, player, should_stop);
+    Self::forbid(&mut trajectories_pruning, &forbidden_moves);
+    // Score can't exceed the number of points.
+    let infinity = (field.length() as i32 + 1) * self.evaluation.scale();
+    let enemy = player.next();
+    let enemy_trajectories_pruning = trajectories_pruning.dec_and_swap(depth - 1, &mut empty_board);
+    let pass_estimation = -Self::alpha_beta(
+      field,
+      depth - 1,
+      None,
+      enemy,
+      &enemy_trajectories_pruning,
+      -infinity,
+      infinity,
+      &mut empty_board,
+      &self.hash_table,
+      self.move_ordering(),
+      &self.nodes,
+      self.config.quiescence_depth,
+      &self.evaluation,
+      should_stop,
+    );
+    info!("Estimation without a move is {}.", pass_estimation);
+    let moves = trajectories_pruning.moves();
+    #[cfg(not(target_arch = "wasm32"))]
+    let best_moves = {
+      let queue = SegQueue::new();
+      for (index, &pos) in moves.iter().enumerate() {
+        queue.push((index, pos));
+      }
+      let best_moves = Mutex::new(Vec::with_capacity(count + 1));
+      crossbeam::scope(|scope| {
+        for _ in 0..self.config.threads_count {
+          scope.spawn(|_| {
+            let mut local_field = field.clone();
+            let mut local_empty_board = iter::repeat_n(0u32, field.length()).collect::<Vec<_>>();
+            while let Some((index, pos)) = queue.pop() {
+              if should_stop() {
+                break;
+              }
+              let alpha = Self::multi_pv_alpha(&best_moves.lock().unwrap(), count, pass_estimation);
+              local_field.put_point(pos, player);
+              let next_trajectories_pruning = trajectories_pruning.next(
+                &mut local_field,
+                enemy,
+                depth - 1,
+                &mut local_empty_board,
+                pos,
+                should_stop,
+              );
+              let estimation = -Self::alpha_beta(
+                &mut local_field,
+                depth - 1,
+                NonZeroPos::new(pos),
+                enemy,
+                &next_trajectories_pruning,
+                -infinity,
+                -alpha,
+                &mut local_empty_board,
+                &self.hash_table,
+                self.move_ordering(),
+                &self.nodes,
+                self.config.quiescence_depth,
+                &self.evaluation,
+                should_stop,
+              );
+              local_field.undo();
+              if should_stop() {
+                break;
+              }
+              if estimation > alpha {
+                Self::multi_pv_add(&mut best_moves.lock().unwrap(), count, index, pos, estimation);
+              }
+            }
+          });
+        }
+      })
+      .expect("Minimax multi_pv panic");
+      best_moves.into_inner().unwrap()
+    };
+    #[cfg(target_arch = "wasm32")]
+    let best_moves = {
+      let mut best_moves = Vec::with_capacity(count + 1);
+      for (index, &pos) in moves.iter().enumerate() {
+        if should_stop() {
+          break;
+        }
+        let alpha = Self::multi_pv_alpha(&best_moves, count, pass_estimation);
+        field.put_point(pos, player);
+        let next_trajectories_pruning =
+          trajectories_pruning.next(field, enemy, depth - 1, &mut empty_board, pos, should_stop);
+        let estimation = -Self::alpha_beta(
+          field,
+          depth - 1,
+          NonZeroPos::new(pos),
+          enemy,
+          &next_trajectories_pruning,
+          -infinity,
+          -alpha,
+          &mut empty_board,
+          &self.hash_table,
+          self.move_ordering(),
+          &self.nodes,
+          self.config.quiescence_depth,
+          &self.evaluation,
+          should_stop,
+        );
+        field.undo();
+        if should_stop() {
+          break;
+        }
+        if estimation > alpha {
+          Self::multi_pv_add(&mut best_moves, count, index, pos, estimation);
+        }
+      }
+      best_moves
+    };
+    self.finish_search();
+    for &(_, pos, estimation) in best_moves.iter() {
+      info!(
+        "Estimation for move ({}, {}) is {}.",
+        field.to_x(pos),
+        field.to_y(pos),
+        estimation
+      );
+    }
+    best_moves
+      .into_iter()
+      .map(|(_, pos, estimation)| (pos, self.evaluation.to_score(estimation)))
+      .collect()
+  }
+
+  /// Multi-PV search with iterative deepening. Returns the result of the
+  /// deepest completed search and its depth.
+  pub fn multi_pv_with_time<SS: Fn() -> bool + Sync>(
+    &self,
+    field: &mut Field,
+    player: Player,
+    should_stop: &SS,
+  ) -> (Vec<(Pos, i32)>, u32) {
+    let mut depth = 1;
+    let mut best_moves = Vec::new();
+    while !should_stop() {
+      let cur_best_moves = self.multi_pv(field, player, depth, should_stop);
+      if should_stop() {
+        break;
+      }
+      best_moves = cur_best_moves;
+      depth += 1;
+    }
+    (best_moves, depth - 1)
+  }
+
+  pub fn config(&self) -> &MinimaxConfig {
+    &self.config
+  }
+
+  pub fn hash_table(&self) -> &Arc<HashTable> {
+    &self.hash_table
+  }
+
+  /// Makes the search use the given hash table. Returns `false` and keeps the
+  /// current table if the given one stores estimations of an evaluation with
+  /// another scale.
+  pub fn set_hash_table(&mut self, hash_table: Arc<HashTable>) -> bool {
+    if !hash_table.bind(self.evaluation.scale()) {
+      return false;
     }
-    (best_move, estimation, depth - 1)
+    self.hash_table = hash_table;
+    true
   }
 
   pub fn clear(&mut self) {
     self.hash_table.clear();
+    self.move_ordering.clear();
   }
 }
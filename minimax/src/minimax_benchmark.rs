@@ -14,15 +14,40 @@ const SEED: u64 = 7;
 const MINIMAX_CONFIG_NEGASCOUT: MinimaxConfig = MinimaxConfig {
   threads_count: 1,
   minimax_type: MinimaxType::NegaScout,
-  hash_table_size: 1_000,
+  hash_table_memory: 1,
   rebuild_trajectories: false,
+  deterministic: false,
+  multi_pv: 1,
+  split_trajectories: false,
+  move_ordering: true,
+  quiescence_depth: 0,
+  forbid_losing_ladders: false,
 };
 
 const MINIMAX_CONFIG_MTDF: MinimaxConfig = MinimaxConfig {
   threads_count: 1,
   minimax_type: MinimaxType::Mtdf,
-  hash_table_size: 1_000,
+  hash_table_memory: 1,
   rebuild_trajectories: false,
+  deterministic: false,
+  multi_pv: 1,
+  split_trajectories: false,
+  move_ordering: true,
+  quiescence_depth: 0,
+  forbid_losing_ladders: false,
+};
+
+const MINIMAX_CONFIG_NO_MOVE_ORDERING: MinimaxConfig = MinimaxConfig {
+  threads_count: 1,
+  minimax_type: MinimaxType::NegaScout,
+  hash_table_memory: 1,
+  rebuild_trajectories: false,
+  deterministic: false,
+  multi_pv: 1,
+  split_trajectories: false,
+  move_ordering: false,
+  quiescence_depth: 0,
+  forbid_losing_ladders: false,
 };
 
 macro_rules! minimax_bench {
@@ -90,4 +115,24 @@ minimax_benches!(
   }
 );
 
-criterion_main!(negascout, mtdf);
+minimax_benches!(
+  no_move_ordering => {
+    no_move_ordering_1, MINIMAX_CONFIG_NO_MOVE_ORDERING, IMAGE_1, 8;
+    no_move_ordering_2, MINIMAX_CONFIG_NO_MOVE_ORDERING, IMAGE_2, 8;
+    no_move_ordering_3, MINIMAX_CONFIG_NO_MOVE_ORDERING, IMAGE_3, 8;
+    no_move_ordering_4, MINIMAX_CONFIG_NO_MOVE_ORDERING, IMAGE_4, 8;
+    no_move_ordering_5, MINIMAX_CONFIG_NO_MOVE_ORDERING, IMAGE_5, 8;
+    no_move_ordering_6, MINIMAX_CONFIG_NO_MOVE_ORDERING, IMAGE_6, 8;
+    no_move_ordering_7, MINIMAX_CONFIG_NO_MOVE_ORDERING, IMAGE_7, 10;
+    no_move_ordering_8, MINIMAX_CONFIG_NO_MOVE_ORDERING, IMAGE_8, 8;
+    no_move_ordering_9, MINIMAX_CONFIG_NO_MOVE_ORDERING, IMAGE_9, 10;
+    no_move_ordering_10, MINIMAX_CONFIG_NO_MOVE_ORDERING, IMAGE_10, 8;
+    no_move_ordering_11, MINIMAX_CONFIG_NO_MOVE_ORDERING, IMAGE_11, 12;
+    no_move_ordering_12, MINIMAX_CONFIG_NO_MOVE_ORDERING, IMAGE_12, 8;
+    no_move_ordering_13, MINIMAX_CONFIG_NO_MOVE_ORDERING, IMAGE_13, 8;
+    no_move_ordering_14, MINIMAX_CONFIG_NO_MOVE_ORDERING, IMAGE_14, 8;
+    no_move_ordering_15, MINIMAX_CONFIG_NO_MOVE_ORDERING, IMAGE_15, 8;
+  }
+);
+
+criterion_main!(negascout, mtdf, no_move_ordering);
@@ -1,3 +1,5 @@
+use crate::evaluation::{EvaluationWeights, MaterialEvaluation, PositionalEvaluation};
+use crate::hash_table::{HashTable, HashTableKey};
 use crate::minimax::{Minimax, MinimaxConfig, MinimaxType};
 use oppai_field::construct_field::construct_field;
 use oppai_field::field::NonZeroPos;
@@ -5,32 +7,48 @@ use oppai_field::player::Player;
 use oppai_test_images::*;
 use rand::SeedableRng;
 use rand_xoshiro::Xoshiro256PlusPlus;
+use std::sync::Arc;
 
 const SEED: u64 = 7;
 
 const MINIMAX_CONFIG_NEGASCOUT: MinimaxConfig = MinimaxConfig {
   threads_count: 1,
   minimax_type: MinimaxType::NegaScout,
-  hash_table_size: 10_000,
+  hash_table_memory: 1,
   rebuild_trajectories: false,
+  deterministic: false,
+  multi_pv: 1,
+  split_trajectories: false,
+  move_ordering: true,
+  quiescence_depth: 0,
+  forbid_losing_ladders: false,
 };
 
 const MINIMAX_CONFIG_MTDF: MinimaxConfig = MinimaxConfig {
   threads_count: 1,
   minimax_type: MinimaxType::Mtdf,
-  hash_table_size: 10_000,
+  hash_table_memory: 1,
   rebuild_trajectories: false,
+  deterministic: false,
+  multi_pv: 1,
+  split_trajectories: false,
+  move_ordering: true,
+  quiescence_depth: 0,
+  forbid_losing_ladders: false,
 };
 
 macro_rules! minimax_test {
   ($(#[$($attr:meta),+])* $name:ident, $config:ident, $image:ident, $depth:expr) => {
+    minimax_test!($(#[$($attr),+])* $name, $config, MaterialEvaluation, $image, $depth);
+  };
+  ($(#[$($attr:meta),+])* $name:ident, $config:ident, $evaluation:expr, $image:ident, $depth:expr) => {
     #[test]
     $(#[$($attr),+])*
     fn $name() {
       env_logger::try_init().ok();
       let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
       let mut field = construct_field(&mut rng, $image.image);
-      let minimax = Minimax::new($config);
+      let minimax = Minimax::with_evaluation($config, $evaluation);
       let (pos, _) = minimax.minimax(&mut field, Player::Red, $depth, &|| false);
       assert_eq!(pos, NonZeroPos::new(field.to_pos($image.solution.0, $image.solution.1)));
     }
@@ -104,3 +122,314 @@ minimax_test!(mtdf_12, MINIMAX_CONFIG_MTDF, IMAGE_12, 8);
 minimax_test!(mtdf_13, MINIMAX_CONFIG_MTDF, IMAGE_13, 8);
 minimax_test!(mtdf_14, MINIMAX_CONFIG_MTDF, IMAGE_14, 8);
 minimax_test!(mtdf_15, MINIMAX_CONFIG_MTDF, IMAGE_15, 8);
+
+const MINIMAX_CONFIG_SPLIT: MinimaxConfig = MinimaxConfig {
+  split_trajectories: true,
+  ..MINIMAX_CONFIG_NEGASCOUT
+};
+
+minimax_test!(split_1, MINIMAX_CONFIG_SPLIT, IMAGE_1, 8);
+minimax_test!(split_2, MINIMAX_CONFIG_SPLIT, IMAGE_2, 8);
+minimax_test!(split_3, MINIMAX_CONFIG_SPLIT, IMAGE_3, 8);
+minimax_test!(split_4, MINIMAX_CONFIG_SPLIT, IMAGE_4, 8);
+minimax_test!(split_5, MINIMAX_CONFIG_SPLIT, IMAGE_5, 8);
+minimax_test!(split_6, MINIMAX_CONFIG_SPLIT, IMAGE_6, 8);
+minimax_test!(split_8, MINIMAX_CONFIG_SPLIT, IMAGE_8, 8);
+minimax_test!(split_10, MINIMAX_CONFIG_SPLIT, IMAGE_10, 8);
+minimax_test!(split_12, MINIMAX_CONFIG_SPLIT, IMAGE_12, 8);
+minimax_test!(split_13, MINIMAX_CONFIG_SPLIT, IMAGE_13, 8);
+minimax_test!(split_14, MINIMAX_CONFIG_SPLIT, IMAGE_14, 8);
+minimax_test!(split_15, MINIMAX_CONFIG_SPLIT, IMAGE_15, 8);
+
+const MINIMAX_CONFIG_QUIESCENCE: MinimaxConfig = MinimaxConfig {
+  quiescence_depth: 4,
+  ..MINIMAX_CONFIG_NEGASCOUT
+};
+
+minimax_test!(quiescence_1, MINIMAX_CONFIG_QUIESCENCE, IMAGE_1, 8);
+minimax_test!(quiescence_2, MINIMAX_CONFIG_QUIESCENCE, IMAGE_2, 8);
+minimax_test!(quiescence_3, MINIMAX_CONFIG_QUIESCENCE, IMAGE_3, 8);
+minimax_test!(quiescence_4, MINIMAX_CONFIG_QUIESCENCE, IMAGE_4, 8);
+minimax_test!(quiescence_5, MINIMAX_CONFIG_QUIESCENCE, IMAGE_5, 8);
+minimax_test!(quiescence_6, MINIMAX_CONFIG_QUIESCENCE, IMAGE_6, 8);
+minimax_test!(quiescence_8, MINIMAX_CONFIG_QUIESCENCE, IMAGE_8, 8);
+minimax_test!(quiescence_10, MINIMAX_CONFIG_QUIESCENCE, IMAGE_10, 8);
+minimax_test!(quiescence_12, MINIMAX_CONFIG_QUIESCENCE, IMAGE_12, 8);
+minimax_test!(quiescence_13, MINIMAX_CONFIG_QUIESCENCE, IMAGE_13, 8);
+minimax_test!(quiescence_14, MINIMAX_CONFIG_QUIESCENCE, IMAGE_14, 8);
+minimax_test!(quiescence_15, MINIMAX_CONFIG_QUIESCENCE, IMAGE_15, 8);
+
+const POSITIONAL_EVALUATION: PositionalEvaluation = PositionalEvaluation::new(EvaluationWeights::DEFAULT);
+
+minimax_test!(
+  positional_1,
+  MINIMAX_CONFIG_NEGASCOUT,
+  POSITIONAL_EVALUATION,
+  IMAGE_1,
+  8
+);
+minimax_test!(
+  positional_2,
+  MINIMAX_CONFIG_NEGASCOUT,
+  POSITIONAL_EVALUATION,
+  IMAGE_2,
+  8
+);
+minimax_test!(
+  positional_3,
+  MINIMAX_CONFIG_NEGASCOUT,
+  POSITIONAL_EVALUATION,
+  IMAGE_3,
+  8
+);
+minimax_test!(
+  positional_4,
+  MINIMAX_CONFIG_NEGASCOUT,
+  POSITIONAL_EVALUATION,
+  IMAGE_4,
+  8
+);
+minimax_test!(
+  positional_5,
+  MINIMAX_CONFIG_NEGASCOUT,
+  POSITIONAL_EVALUATION,
+  IMAGE_5,
+  8
+);
+minimax_test!(
+  positional_6,
+  MINIMAX_CONFIG_NEGASCOUT,
+  POSITIONAL_EVALUATION,
+  IMAGE_6,
+  8
+);
+minimax_test!(
+  positional_8,
+  MINIMAX_CONFIG_NEGASCOUT,
+  POSITIONAL_EVALUATION,
+  IMAGE_8,
+  8
+);
+minimax_test!(
+  positional_10,
+  MINIMAX_CONFIG_NEGASCOUT,
+  POSITIONAL_EVALUATION,
+  IMAGE_10,
+  8
+);
+minimax_test!(
+  positional_12,
+  MINIMAX_CONFIG_NEGASCOUT,
+  POSITIONAL_EVALUATION,
+  IMAGE_12,
+  8
+);
+minimax_test!(
+  positional_13,
+  MINIMAX_CONFIG_NEGASCOUT,
+  POSITIONAL_EVALUATION,
+  IMAGE_13,
+  8
+);
+minimax_test!(
+  positional_14,
+  MINIMAX_CONFIG_NEGASCOUT,
+  POSITIONAL_EVALUATION,
+  IMAGE_14,
+  8
+);
+// The solution of the 15th image has the same score as two other moves, so
+// the positional evaluation is free to choose another one.
+
+#[test]
+fn minimax_deterministic() {
+  env_logger::try_init().ok();
+  let config = MinimaxConfig {
+    threads_count: 4,
+    deterministic: true,
+    ..MINIMAX_CONFIG_NEGASCOUT
+  };
+  let minimax = || {
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+    let mut field = construct_field(&mut rng, IMAGE_2.image);
+    let minimax = Minimax::new(config.clone());
+    let result = minimax.minimax(&mut field, Player::Red, 8, &|| false);
+    (result, field.to_pos(IMAGE_2.solution.0, IMAGE_2.solution.1))
+  };
+  let ((pos, estimation), solution) = minimax();
+  assert_eq!(pos, NonZeroPos::new(solution));
+  assert_eq!(minimax(), ((pos, estimation), solution));
+}
+
+#[test]
+fn minimax_deterministic_threads() {
+  env_logger::try_init().ok();
+  let minimax = |image: &TestImage, threads_count| {
+    let config = MinimaxConfig {
+      threads_count,
+      deterministic: true,
+      ..MINIMAX_CONFIG_NEGASCOUT
+    };
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+    let mut field = construct_field(&mut rng, image.image);
+    Minimax::new(config).minimax(&mut field, Player::Red, 8, &|| false)
+  };
+  for image in [&IMAGE_1, &IMAGE_12, &IMAGE_15] {
+    assert_eq!(minimax(image, 1), minimax(image, 4));
+  }
+}
+
+#[test]
+fn multi_pv() {
+  env_logger::try_init().ok();
+  let config = MinimaxConfig {
+    multi_pv: 3,
+    ..MINIMAX_CONFIG_NEGASCOUT
+  };
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+  let mut field = construct_field(&mut rng, IMAGE_1.image);
+  let minimax = Minimax::new(config);
+  let moves = minimax.multi_pv(&mut field, Player::Red, 8, &|| false);
+  let (_, estimation) = minimax.minimax(&mut field, Player::Red, 8, &|| false);
+  assert!(!moves.is_empty() && moves.len() <= 3);
+  assert_eq!(
+    moves[0],
+    (field.to_pos(IMAGE_1.solution.0, IMAGE_1.solution.1), estimation)
+  );
+  assert!(moves.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+}
+
+#[test]
+fn split_estimation() {
+  env_logger::try_init().ok();
+  for image in [IMAGE_3, IMAGE_4, IMAGE_5, IMAGE_6] {
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+    let mut field = construct_field(&mut rng, image.image);
+    let (_, estimation) = Minimax::new(MINIMAX_CONFIG_NEGASCOUT).minimax(&mut field, Player::Red, 8, &|| false);
+    let (_, split_estimation) = Minimax::new(MINIMAX_CONFIG_SPLIT).minimax(&mut field, Player::Red, 8, &|| false);
+    assert_eq!(split_estimation, estimation);
+  }
+}
+
+#[test]
+fn move_ordering() {
+  env_logger::try_init().ok();
+  for image in [IMAGE_1, IMAGE_2, IMAGE_3, IMAGE_4, IMAGE_5, IMAGE_6] {
+    let minimax = |move_ordering| {
+      let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+      let mut field = construct_field(&mut rng, image.image);
+      let config = MinimaxConfig {
+        move_ordering,
+        ..MINIMAX_CONFIG_NEGASCOUT
+      };
+      Minimax::new(config).minimax(&mut field, Player::Red, 10, &|| false)
+    };
+    assert_eq!(minimax(true), minimax(false));
+  }
+}
+
+#[test]
+fn quiescence() {
+  env_logger::try_init().ok();
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+  let mut field = construct_field(&mut rng, IMAGE_2.image);
+  let (_, estimation) = Minimax::new(MINIMAX_CONFIG_NEGASCOUT).minimax(&mut field, Player::Red, 10, &|| false);
+  // Without the quiescence search the capture is found only at depth 7.
+  let (_, shallow_estimation) = Minimax::new(MINIMAX_CONFIG_NEGASCOUT).minimax(&mut field, Player::Red, 5, &|| false);
+  assert!(shallow_estimation < estimation);
+  let config = MinimaxConfig {
+    quiescence_depth: 4,
+    ..MINIMAX_CONFIG_NEGASCOUT
+  };
+  let (_, quiescence_estimation) = Minimax::new(config).minimax(&mut field, Player::Red, 5, &|| false);
+  assert_eq!(quiescence_estimation, estimation);
+}
+
+#[test]
+fn shared_hash_table() {
+  env_logger::try_init().ok();
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+  let mut field = construct_field(&mut rng, IMAGE_12.image);
+  let minimax = Minimax::new(MINIMAX_CONFIG_NEGASCOUT);
+  let result = minimax.minimax(&mut field, Player::Red, 8, &|| false);
+  let nodes = minimax.nodes();
+  let shared = Minimax::with_hash_table(
+    MINIMAX_CONFIG_NEGASCOUT,
+    MaterialEvaluation,
+    minimax.hash_table().clone(),
+  )
+  .unwrap();
+  assert_eq!(shared.minimax(&mut field, Player::Red, 8, &|| false), result);
+  assert!(shared.nodes() < nodes);
+}
+
+#[test]
+fn saved_hash_table() {
+  env_logger::try_init().ok();
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+  let mut field = construct_field(&mut rng, IMAGE_12.image);
+  let minimax = Minimax::new(MINIMAX_CONFIG_NEGASCOUT);
+  let result = minimax.minimax(&mut field, Player::Red, 8, &|| false);
+  let nodes = minimax.nodes();
+  let mut buffer = Vec::new();
+  minimax
+    .hash_table()
+    .save(HashTableKey::new(&field, 1), &mut buffer)
+    .unwrap();
+  let hash_table = HashTable::new(1024 * 1024);
+  hash_table
+    .load(HashTableKey::new(&field, 1), buffer.as_slice())
+    .unwrap();
+  let loaded = Minimax::with_hash_table(MINIMAX_CONFIG_NEGASCOUT, MaterialEvaluation, Arc::new(hash_table)).unwrap();
+  assert_eq!(loaded.minimax(&mut field, Player::Red, 8, &|| false), result);
+  assert!(loaded.nodes() < nodes);
+}
+
+#[test]
+fn hash_table_another_scale() {
+  let minimax = Minimax::new(MINIMAX_CONFIG_NEGASCOUT);
+  let hash_table = minimax.hash_table().clone();
+  let mut positional = Minimax::with_evaluation(
+    MINIMAX_CONFIG_NEGASCOUT,
+    PositionalEvaluation::new(EvaluationWeights::DEFAULT),
+  );
+  assert!(!positional.set_hash_table(hash_table.clone()));
+  assert!(!Arc::ptr_eq(positional.hash_table(), &hash_table));
+  assert!(Minimax::with_hash_table(
+    MINIMAX_CONFIG_NEGASCOUT,
+    PositionalEvaluation::new(EvaluationWeights::DEFAULT),
+    hash_table,
+  )
+  .is_none());
+}
+
+#[test]
+fn forbid_losing_ladders() {
+  env_logger::try_init().ok();
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+  // The escape at (4, 4) loses a bigger group in a ladder too long for the
+  // search.
+  let mut field = construct_field(
+    &mut rng,
+    "
+    .........
+    ......a..
+    .........
+    ...a.....
+    ..aA.....
+    .aAAa....
+    ..aa.....
+    .........
+    ",
+  );
+  let escape = NonZeroPos::new(field.to_pos(4, 4));
+  let (pos, _) = Minimax::new(MINIMAX_CONFIG_NEGASCOUT).minimax(&mut field, Player::Black, 8, &|| false);
+  assert_eq!(pos, escape);
+  let config = MinimaxConfig {
+    forbid_losing_ladders: true,
+    ..MINIMAX_CONFIG_NEGASCOUT
+  };
+  let (pos, estimation) = Minimax::new(config).minimax(&mut field, Player::Black, 8, &|| false);
+  assert_ne!(pos, escape);
+  assert_eq!(estimation, -3);
+}
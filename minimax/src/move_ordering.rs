@@ -0,0 +1,135 @@
+use oppai_field::field::Pos;
+use oppai_field::player::Player;
+use std::{
+  iter,
+  sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Number of positions in the history and counter-move tables for each player.
+/// Positions of bigger fields share entries, which only affects the moves order.
+const POSITIONS_COUNT: usize = 1 << 13;
+
+/// Number of depths that have their own killer moves.
+const DEPTHS_COUNT: usize = 64;
+
+/// Number of killer moves for each depth.
+const KILLERS_COUNT: usize = 2;
+
+fn clone_atomics(atomics: &[AtomicUsize]) -> Vec<AtomicUsize> {
+  atomics
+    .iter()
+    .map(|atomic| AtomicUsize::new(atomic.load(Ordering::Relaxed)))
+    .collect()
+}
+
+fn new_atomics(length: usize) -> Vec<AtomicUsize> {
+  iter::repeat_with(|| AtomicUsize::new(0)).take(length).collect()
+}
+
+/// Killer moves per depth, history of cutoffs and counter moves to the last
+/// move. Tables are shared between threads without locks, so concurrent
+/// updates may be lost, which only affects the moves order.
+#[derive(Debug)]
+pub struct MoveOrdering {
+  killers: Vec<AtomicUsize>,
+  history: Vec<AtomicUsize>,
+  counter_moves: Vec<AtomicUsize>,
+}
+
+impl Clone for MoveOrdering {
+  fn clone(&self) -> Self {
+    Self {
+      killers: clone_atomics(&self.killers),
+      history: clone_atomics(&self.history),
+      counter_moves: clone_atomics(&self.counter_moves),
+    }
+  }
+}
+
+impl Default for MoveOrdering {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl MoveOrdering {
+  pub fn new() -> Self {
+    Self {
+      killers: new_atomics(DEPTHS_COUNT * KILLERS_COUNT),
+      history: new_atomics(2 * POSITIONS_COUNT),
+      counter_moves: new_atomics(2 * POSITIONS_COUNT),
+    }
+  }
+
+  #[inline]
+  fn player_index(player: Player, pos: Pos) -> usize {
+    usize::from(player == Player::Black) * POSITIONS_COUNT + pos % POSITIONS_COUNT
+  }
+
+  #[inline]
+  fn killers(&self, depth: u32) -> &[AtomicUsize] {
+    let index = (depth as usize).min(DEPTHS_COUNT - 1) * KILLERS_COUNT;
+    &self.killers[index..index + KILLERS_COUNT]
+  }
+
+  /// Priority of the move: killer moves go first, then the counter move to the
+  /// last move, then other moves by their history. Lower is better.
+  fn priority(&self, player: Player, depth: u32, last_pos: Option<Pos>, pos: Pos) -> (usize, usize) {
+    if let Some(i) = self
+      .killers(depth)
+      .iter()
+      .position(|killer| killer.load(Ordering::Relaxed) == pos)
+    {
+      return (i, 0);
+    }
+    if last_pos
+      .is_some_and(|last_pos| self.counter_moves[Self::player_index(player, last_pos)].load(Ordering::Relaxed) == pos)
+    {
+      return (KILLERS_COUNT, 0);
+    }
+    let history = self.history[Self::player_index(player, pos)].load(Ordering::Relaxed);
+    (KILLERS_COUNT + 1, usize::MAX - history)
+  }
+
+  /// Sorts moves by their priority. The first move from trajectories is the
+  /// best guess more often than killer moves, so it keeps its place. The sort
+  /// is stable, so moves with equal priority keep the order of trajectories.
+  pub fn sort(&self, player: Player, depth: u32, last_pos: Option<Pos>, moves: &mut [Pos]) {
+    if let Some((_, rest)) = moves.split_first_mut() {
+      rest.sort_by_cached_key(|&pos| self.priority(player, depth, last_pos, pos));
+    }
+  }
+
+  /// Remembers the move that caused a beta cutoff.
+  pub fn cutoff(&self, player: Player, depth: u32, last_pos: Option<Pos>, pos: Pos) {
+    let killers = self.killers(depth);
+    if killers[0].load(Ordering::Relaxed) != pos {
+      for i in (1..KILLERS_COUNT).rev() {
+        killers[i].store(killers[i - 1].load(Ordering::Relaxed), Ordering::Relaxed);
+      }
+      killers[0].store(pos, Ordering::Relaxed);
+    }
+    let depth = depth as usize;
+    self.history[Self::player_index(player, pos)].fetch_add(depth * depth, Ordering::Relaxed);
+    if let Some(last_pos) = last_pos {
+      self.counter_moves[Self::player_index(player, last_pos)].store(pos, Ordering::Relaxed);
+    }
+  }
+
+  /// Halves the history and forgets killer moves, so that the next search
+  /// relies more on its own cutoffs.
+  pub fn age(&self) {
+    for killer in &self.killers {
+      killer.store(0, Ordering::Relaxed);
+    }
+    for history in &self.history {
+      history.store(history.load(Ordering::Relaxed) / 2, Ordering::Relaxed);
+    }
+  }
+
+  pub fn clear(&self) {
+    for atomic in self.killers.iter().chain(&self.history).chain(&self.counter_moves) {
+      atomic.store(0, Ordering::Relaxed);
+    }
+  }
+}
@@ -0,0 +1,49 @@
+use crate::move_ordering::MoveOrdering;
+use oppai_field::player::Player;
+
+#[test]
+fn move_ordering_keeps_first_move() {
+  let move_ordering = MoveOrdering::new();
+  move_ordering.cutoff(Player::Red, 3, None, 31);
+  let mut moves = vec![17, 23, 31];
+  move_ordering.sort(Player::Red, 3, None, &mut moves);
+  assert_eq!(moves, vec![17, 31, 23]);
+}
+
+#[test]
+fn move_ordering_killers() {
+  let move_ordering = MoveOrdering::new();
+  move_ordering.cutoff(Player::Red, 3, None, 31);
+  move_ordering.cutoff(Player::Red, 3, None, 29);
+  let mut moves = vec![17, 23, 29, 31, 37];
+  move_ordering.sort(Player::Red, 3, None, &mut moves);
+  assert_eq!(moves, vec![17, 29, 31, 23, 37]);
+  // Killer moves are stored per depth.
+  let mut moves = vec![17, 23, 37];
+  move_ordering.sort(Player::Red, 2, None, &mut moves);
+  assert_eq!(moves, vec![17, 23, 37]);
+}
+
+#[test]
+fn move_ordering_counter_move() {
+  let move_ordering = MoveOrdering::new();
+  move_ordering.cutoff(Player::Black, 5, Some(41), 37);
+  let mut moves = vec![17, 23, 37];
+  move_ordering.sort(Player::Black, 4, Some(41), &mut moves);
+  assert_eq!(moves, vec![17, 37, 23]);
+  let mut moves = vec![17, 23, 37];
+  move_ordering.sort(Player::Red, 4, Some(41), &mut moves);
+  assert_eq!(moves, vec![17, 23, 37]);
+}
+
+#[test]
+fn move_ordering_history() {
+  let move_ordering = MoveOrdering::new();
+  move_ordering.cutoff(Player::Red, 1, None, 23);
+  move_ordering.cutoff(Player::Red, 2, None, 37);
+  move_ordering.cutoff(Player::Red, 2, None, 31);
+  move_ordering.age();
+  let mut moves = vec![17, 23, 31, 37];
+  move_ordering.sort(Player::Red, 2, None, &mut moves);
+  assert_eq!(moves, vec![17, 31, 37, 23]);
+}
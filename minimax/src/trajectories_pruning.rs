@@ -102,7 +102,7 @@ impl TrajectoriesPruning {
     if depth == 0 {
       return TrajectoriesPruning::empty(rebuild_trajectories);
     }
-    let mut cur_trajectories = build_trajectories(field, player, (depth + 1) / 2, empty_board, should_stop);
+    let mut cur_trajectories = build_trajectories(field, player, depth.div_ceil(2), empty_board, should_stop);
     if should_stop() {
       return TrajectoriesPruning::empty(rebuild_trajectories);
     }
@@ -119,6 +119,37 @@ impl TrajectoriesPruning {
     }
   }
 
+  /// Trajectories of captures with one move of both the player and the enemy.
+  pub fn captures<SS: Fn() -> bool>(
+    field: &mut Field,
+    player: Player,
+    empty_board: &mut [u32],
+    should_stop: &SS,
+  ) -> TrajectoriesPruning {
+    TrajectoriesPruning::new(false, field, player, 2, empty_board, should_stop)
+  }
+
+  /// Trajectories for the quiescence search: captures and threats of the
+  /// player and captures of the enemy.
+  pub fn quiescence<SS: Fn() -> bool>(
+    field: &mut Field,
+    player: Player,
+    empty_board: &mut [u32],
+    should_stop: &SS,
+  ) -> TrajectoriesPruning {
+    TrajectoriesPruning::new(false, field, player, 3, empty_board, should_stop)
+  }
+
+  /// Checks if the player can capture something with one move.
+  pub fn can_capture(&self) -> bool {
+    self.cur_trajectories.iter().any(|trajectory| trajectory.len() == 1)
+  }
+
+  /// Checks if the enemy can capture something with one move.
+  pub fn is_threatened(&self) -> bool {
+    self.enemy_trajectories.iter().any(|trajectory| trajectory.len() == 1)
+  }
+
   fn last_pos_trajectory(field: &Field, player: Player, depth: u32, last_pos: Pos) -> Option<Trajectory> {
     let mut points = Vec::with_capacity(4);
     let mut hash = 0;
@@ -138,7 +169,7 @@ impl TrajectoriesPruning {
         return None;
       }
     }
-    if points.len() as u32 <= (depth + 1) / 2 {
+    if points.len() as u32 <= depth.div_ceil(2) {
       Some(Trajectory::new(points, hash, field.score(player) + 1))
     } else {
       None
@@ -158,7 +189,7 @@ impl TrajectoriesPruning {
       return TrajectoriesPruning::empty(self.rebuild_trajectories);
     }
     let mut cur_trajectories = if self.rebuild_trajectories {
-      build_trajectories(field, player, (depth + 1) / 2, empty_board, should_stop)
+      build_trajectories(field, player, depth.div_ceil(2), empty_board, should_stop)
     } else {
       self
         .enemy_trajectories
@@ -261,14 +292,14 @@ impl TrajectoriesPruning {
     empty_board: &mut [u32],
     should_stop: &SS,
   ) -> TrajectoriesPruning {
-    let (mut cur_trajectories, mut enemy_trajectories) = if depth % 2 == 0 {
+    let (mut cur_trajectories, mut enemy_trajectories) = if depth.is_multiple_of(2) {
       let enemy_trajectories = build_trajectories(field, player.next(), depth / 2, empty_board, should_stop);
       if should_stop() {
         return TrajectoriesPruning::empty(self.rebuild_trajectories);
       }
       (self.cur_trajectories.clone(), enemy_trajectories)
     } else {
-      let cur_trajectories = build_trajectories(field, player, (depth + 1) / 2, empty_board, should_stop);
+      let cur_trajectories = build_trajectories(field, player, depth.div_ceil(2), empty_board, should_stop);
       if should_stop() {
         return TrajectoriesPruning::empty(self.rebuild_trajectories);
       }
@@ -286,6 +317,76 @@ impl TrajectoriesPruning {
     }
   }
 
+  fn find(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+      parents[i] = parents[parents[i]];
+      i = parents[i];
+    }
+    i
+  }
+
+  fn union(parents: &mut [usize], i: usize, j: usize) {
+    let i = TrajectoriesPruning::find(parents, i);
+    let j = TrajectoriesPruning::find(parents, j);
+    parents[i.max(j)] = i.min(j);
+  }
+
+  /// Splits trajectories into groups that neither intersect nor touch each
+  /// other, so that moves of one group don't affect trajectories of another
+  /// one.
+  pub fn split(&self, field: &Field, empty_board: &mut [u32]) -> Vec<TrajectoriesPruning> {
+    let cur_count = self.cur_trajectories.len();
+    let trajectories = || self.cur_trajectories.iter().chain(self.enemy_trajectories.iter());
+    let mut parents = (0..cur_count + self.enemy_trajectories.len()).collect::<Vec<_>>();
+    for (i, trajectory) in trajectories().enumerate() {
+      for &pos in trajectory.points() {
+        if empty_board[pos] == 0 {
+          empty_board[pos] = i as u32 + 1;
+        } else {
+          TrajectoriesPruning::union(&mut parents, i, empty_board[pos] as usize - 1);
+        }
+      }
+    }
+    for (i, trajectory) in trajectories().enumerate() {
+      for &pos in trajectory.points() {
+        for near_pos in field.directions_diag(pos) {
+          if empty_board[near_pos] != 0 {
+            TrajectoriesPruning::union(&mut parents, i, empty_board[near_pos] as usize - 1);
+          }
+        }
+      }
+    }
+    let mut roots = Vec::new();
+    let mut groups = Vec::<(Vec<Trajectory>, Vec<Trajectory>)>::new();
+    for (i, trajectory) in trajectories().enumerate() {
+      let root = TrajectoriesPruning::find(&mut parents, i);
+      let index = roots.iter().position(|&r| r == root).unwrap_or_else(|| {
+        roots.push(root);
+        groups.push((Vec::new(), Vec::new()));
+        groups.len() - 1
+      });
+      if i < cur_count {
+        groups[index].0.push(trajectory.clone());
+      } else {
+        groups[index].1.push(trajectory.clone());
+      }
+    }
+    TrajectoriesPruning::deproject(&self.cur_trajectories, empty_board);
+    TrajectoriesPruning::deproject(&self.enemy_trajectories, empty_board);
+    groups
+      .into_iter()
+      .map(|(mut cur_trajectories, mut enemy_trajectories)| {
+        let moves = TrajectoriesPruning::calculate_moves(&mut cur_trajectories, &mut enemy_trajectories, empty_board);
+        TrajectoriesPruning {
+          rebuild_trajectories: self.rebuild_trajectories,
+          cur_trajectories,
+          enemy_trajectories,
+          moves,
+        }
+      })
+      .collect()
+  }
+
   fn trajectories_score(trajectories: &[Trajectory]) -> Option<i32> {
     trajectories.iter().map(Trajectory::score).max()
   }
@@ -9,6 +9,7 @@ use std::{
   cmp,
   fs::File,
   io::{BufRead, BufReader},
+  ops::Range,
   str::FromStr,
 };
 
@@ -27,7 +28,7 @@ pub struct Patterns {
 impl Default for Patterns {
   fn default() -> Self {
     Self {
-      min_size: u32::max_value(),
+      min_size: u32::MAX,
       dfa: Dfa::default(),
     }
   }
@@ -231,6 +232,41 @@ impl Patterns {
   }
 
   pub fn find(&self, field: &Field, player: Player, first_match: bool) -> Vec<Pos> {
+    let matched = self.find_in(
+      field,
+      player,
+      first_match,
+      0..field.width() as i32,
+      0..field.height() as i32,
+    );
+    info!(
+      "Found {}moves: {:?}.",
+      if first_match { "first matched " } else { "" },
+      matched
+        .iter()
+        .map(|&pos| (field.to_x(pos), field.to_y(pos)))
+        .collect::<Vec<(u32, u32)>>()
+    );
+    matched
+  }
+
+  /// Finds patterns with centers not farther than `radius` from `pos`. It's
+  /// much cheaper than the search on the whole field and is intended for local
+  /// responses.
+  pub fn find_near(&self, field: &Field, player: Player, pos: Pos, radius: u32, first_match: bool) -> Vec<Pos> {
+    let x = field.to_x(pos) as i32;
+    let y = field.to_y(pos) as i32;
+    let radius = radius as i32;
+    self.find_in(
+      field,
+      player,
+      first_match,
+      x - radius..x + radius + 1,
+      y - radius..y + radius + 1,
+    )
+  }
+
+  fn find_in(&self, field: &Field, player: Player, first_match: bool, xs: Range<i32>, ys: Range<i32>) -> Vec<Pos> {
     if self.dfa.is_empty() || field.width() < self.min_size - 2 || field.height() < self.min_size - 2 {
       return Vec::new();
     }
@@ -238,8 +274,8 @@ impl Patterns {
     let left_border = (self.min_size as i32 - 1) / 2 - 1;
     let right_border = self.min_size as i32 / 2 - 1;
     let inv_color = player == Player::Black;
-    for y in left_border..field.height() as i32 - right_border {
-      for x in left_border..field.width() as i32 - right_border {
+    for y in ys.start.max(left_border)..ys.end.min(field.height() as i32 - right_border) {
+      for x in xs.start.max(left_border)..xs.end.min(field.width() as i32 - right_border) {
         let moves = self.dfa.run(
           &mut Spiral::default().map(|(shift_x, shift_y)| {
             let cur_x = x + shift_x;
@@ -260,24 +296,10 @@ impl Patterns {
           matched.push(field.to_pos(move_x, move_y));
         }
         if first_match && !matched.is_empty() {
-          info!(
-            "Found first matched moves: {:?}.",
-            matched
-              .iter()
-              .map(|&pos| (field.to_x(pos), field.to_y(pos)))
-              .collect::<Vec<(u32, u32)>>()
-          );
           return matched;
         }
       }
     }
-    info!(
-      "Found moves: {:?}.",
-      matched
-        .iter()
-        .map(|&pos| (field.to_x(pos), field.to_y(pos)))
-        .collect::<Vec<(u32, u32)>>()
-    );
     matched
   }
 }
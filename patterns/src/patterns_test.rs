@@ -470,3 +470,31 @@ fn multiple_patterns() {
     ]
   );
 }
+
+#[test]
+fn pattern_find_near() {
+  let p = construct_patterns(&["
+    .....
+    +XO..
+    .OX..
+    .O.X.
+    .....
+    "]);
+  let field = construct_field(
+    &mut Xoshiro256PlusPlus::seed_from_u64(SEED),
+    "
+    .........
+    .aA......
+    .Aa......
+    .A.a.....
+    .........
+    ",
+  );
+  assert_eq!(
+    p.find_near(&field, Player::Red, field.to_pos(2, 2), 1, false),
+    vec![field.to_pos(0, 1)]
+  );
+  assert!(p
+    .find_near(&field, Player::Red, field.to_pos(7, 2), 1, false)
+    .is_empty());
+}
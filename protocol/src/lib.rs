@@ -24,6 +24,16 @@ pub enum Request {
   PutPoint { coords: Coords, player: Player },
   Undo,
   Analyze { player: Player, constraint: Constraint },
+  Decision,
+  DrawOffer,
+  InspectUct { depth: u32, format: TreeFormat },
+  Prove { player: Player, target: i32, depth: u32 },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum TreeFormat {
+  Json,
+  Sgf,
 }
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
@@ -32,13 +42,85 @@ pub struct Move {
   pub weight: f64,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Decision {
+  Play,
+  Resign,
+  OfferDraw,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Proof {
+  Win,
+  Loss,
+  Draw,
+}
+
+/// UCT node statistics for the player who made its move. The root has no
+/// coordinates.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct UctNode {
+  pub coords: Option<Coords>,
+  pub visits: usize,
+  pub wins: usize,
+  pub draws: usize,
+  pub winrate: f64,
+  pub ucb: f64,
+  pub prior: f64,
+  pub score: f64,
+  pub proof: Option<Proof>,
+  pub children: Vec<UctNode>,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct UctTree {
+  pub player: Player,
+  pub komi: i32,
+  pub root: UctNode,
+}
+
+/// Node of a df-pn solution tree. A node without coordinates is a pass.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct ProofNode {
+  pub coords: Option<Coords>,
+  pub player: Player,
+  pub children: Vec<ProofNode>,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(tag = "format", content = "value")]
+pub enum Tree {
+  Json(UctTree),
+  Sgf(String),
+}
+
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 #[serde(tag = "command")]
 pub enum Response {
   Init,
-  PutPoint { put: bool },
-  Undo { undone: bool },
-  Analyze { moves: Vec<Move> },
+  PutPoint {
+    put: bool,
+  },
+  Undo {
+    undone: bool,
+  },
+  Analyze {
+    moves: Vec<Move>,
+  },
+  Decision {
+    decision: Decision,
+  },
+  DrawOffer {
+    accept: bool,
+  },
+  InspectUct {
+    tree: Option<Tree>,
+  },
+  /// `proven` is `None` if the proof ran out of nodes.
+  Prove {
+    proven: Option<bool>,
+    moves: Vec<ProofNode>,
+  },
 }
 
 #[cfg(test)]
@@ -96,6 +178,41 @@ mod tests {
     r#"{"command":"Analyze","player":"Red","constraint":{"type":"Complexity","value":1.0}}"#
   );
 
+  from_to_json_test!(
+    decision_request,
+    Request,
+    Request::Decision,
+    r#"{"command":"Decision"}"#
+  );
+
+  from_to_json_test!(
+    draw_offer_request,
+    Request,
+    Request::DrawOffer,
+    r#"{"command":"DrawOffer"}"#
+  );
+
+  from_to_json_test!(
+    inspect_uct_request,
+    Request,
+    Request::InspectUct {
+      depth: 2,
+      format: TreeFormat::Sgf
+    },
+    r#"{"command":"InspectUct","depth":2,"format":"Sgf"}"#
+  );
+
+  from_to_json_test!(
+    prove_request,
+    Request,
+    Request::Prove {
+      player: Player::Black,
+      target: 1,
+      depth: 8
+    },
+    r#"{"command":"Prove","player":"Black","target":1,"depth":8}"#
+  );
+
   from_to_json_test!(init_response, Response, Response::Init, r#"{"command":"Init"}"#);
 
   from_to_json_test!(
@@ -123,4 +240,82 @@ mod tests {
     },
     r#"{"command":"Analyze","moves":[{"coords":{"x":1,"y":2},"weight":1.0}]}"#
   );
+
+  from_to_json_test!(
+    decision_response,
+    Response,
+    Response::Decision {
+      decision: Decision::Resign
+    },
+    r#"{"command":"Decision","decision":"Resign"}"#
+  );
+
+  from_to_json_test!(
+    draw_offer_response,
+    Response,
+    Response::DrawOffer { accept: true },
+    r#"{"command":"DrawOffer","accept":true}"#
+  );
+
+  from_to_json_test!(
+    inspect_uct_json_response,
+    Response,
+    Response::InspectUct {
+      tree: Some(Tree::Json(UctTree {
+        player: Player::Red,
+        komi: 0,
+        root: UctNode {
+          coords: None,
+          visits: 2,
+          wins: 1,
+          draws: 0,
+          winrate: 0.5,
+          ucb: 0.0,
+          prior: 0.0,
+          score: 0.0,
+          proof: None,
+          children: vec![UctNode {
+            coords: Some(Coords { x: 1, y: 2 }),
+            visits: 1,
+            wins: 1,
+            draws: 0,
+            winrate: 1.0,
+            ucb: 1.5,
+            prior: 0.0,
+            score: 1.0,
+            proof: Some(Proof::Win),
+            children: Vec::new(),
+          }],
+        },
+      }))
+    },
+    r#"{"command":"InspectUct","tree":{"format":"Json","value":{"player":"Red","komi":0,"root":{"coords":null,"visits":2,"wins":1,"draws":0,"winrate":0.5,"ucb":0.0,"prior":0.0,"score":0.0,"proof":null,"children":[{"coords":{"x":1,"y":2},"visits":1,"wins":1,"draws":0,"winrate":1.0,"ucb":1.5,"prior":0.0,"score":1.0,"proof":"Win","children":[]}]}}}}"#
+  );
+
+  from_to_json_test!(
+    inspect_uct_sgf_response,
+    Response,
+    Response::InspectUct {
+      tree: Some(Tree::Sgf("(;GM[40])".into()))
+    },
+    r#"{"command":"InspectUct","tree":{"format":"Sgf","value":"(;GM[40])"}}"#
+  );
+
+  from_to_json_test!(
+    prove_response,
+    Response,
+    Response::Prove {
+      proven: Some(true),
+      moves: vec![ProofNode {
+        coords: Some(Coords { x: 1, y: 2 }),
+        player: Player::Red,
+        children: vec![ProofNode {
+          coords: None,
+          player: Player::Black,
+          children: Vec::new(),
+        }],
+      }]
+    },
+    r#"{"command":"Prove","proven":true,"moves":[{"coords":{"x":1,"y":2},"player":"Red","children":[{"coords":null,"player":"Black","children":[]}]}]}"#
+  );
 }
@@ -1082,6 +1082,7 @@ impl<R: Rng> Session<R> {
     Ok(())
   }
 
+  #[allow(clippy::result_large_err)]
   async fn accept_connection(mut self, state: Arc<State>, stream: TcpStream) -> Result<()> {
     let ws_stream = tokio_tungstenite::accept_hdr_async(stream, |request: &Request, response| {
       let mut jar = CookieJar::new();
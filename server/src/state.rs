@@ -137,6 +137,7 @@ impl State {
     connection.try_send(response).map_err(From::from)
   }
 
+  #[allow(dead_code)]
   pub async fn send_to_player(&self, player_id: PlayerId, response: Response) {
     if let Some(connections) = self.players.pin_owned().get(&player_id) {
       for &connection_id in connections {
@@ -3,7 +3,12 @@ mod test;
 #[cfg(feature = "zero")]
 pub mod visits;
 
-use oppai_field::{any_field::AnyField, extended_field::ExtendedField, player::Player};
+use oppai_field::{
+  any_field::AnyField,
+  extended_field::ExtendedField,
+  field::{Field, Pos},
+  player::Player,
+};
 use rand::Rng;
 use sgf_parse::{serialize, unknown_game::Prop, GameTree, SgfNode};
 use std::{fmt::Display, iter};
@@ -109,15 +114,11 @@ pub fn from_sgf<F: AnyField, R: Rng>(node: &SgfNode<Prop>, rng: &mut R) -> Optio
   'outer: for node in node.main_variation() {
     for prop in node.properties() {
       match prop {
-        Prop::B(s) => {
-          if !handle(Player::Black, s) {
-            break 'outer;
-          }
+        Prop::B(s) if !handle(Player::Black, s) => {
+          break 'outer;
         }
-        Prop::W(s) => {
-          if !handle(Player::Red, s) {
-            break 'outer;
-          }
+        Prop::W(s) if !handle(Player::Red, s) => {
+          break 'outer;
         }
         Prop::AB(set) => {
           for s in set {
@@ -217,3 +218,55 @@ pub fn to_sgf(field: &ExtendedField) -> Option<SgfNode<Prop>> {
 pub fn to_sgf_str(field: &ExtendedField) -> Option<String> {
   to_sgf(field).map(|node| serialize(iter::once(&GameTree::Unknown(node))))
 }
+
+/// Sequence of moves following the game with a comment on every move, e.g. a
+/// branch of a search tree.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Variation {
+  pub pos: Pos,
+  pub player: Player,
+  pub comment: String,
+  pub children: Vec<Variation>,
+}
+
+fn variation_to_sgf(field: &Field, variation: &Variation) -> SgfNode<Prop> {
+  let m = format!(
+    "{}",
+    Move::Move(
+      field.to_x(variation.pos) as u8,
+      field.to_y(variation.pos) as u8,
+      Vec::new()
+    )
+  );
+  let mut properties = vec![match variation.player {
+    Player::Red => Prop::W(m),
+    Player::Black => Prop::B(m),
+  }];
+  if !variation.comment.is_empty() {
+    properties.push(Prop::C(variation.comment.as_str().into()));
+  }
+  let children = variation
+    .children
+    .iter()
+    .map(|child| variation_to_sgf(field, child))
+    .collect();
+  SgfNode::new(properties, children, false)
+}
+
+/// Converts the game to SGF with variations after its last move.
+pub fn to_sgf_with_variations(field: &ExtendedField, variations: &[Variation]) -> Option<SgfNode<Prop>> {
+  let mut root = to_sgf(field)?;
+  let mut last = &mut root;
+  while !last.children.is_empty() {
+    last = &mut last.children[0];
+  }
+  last.children = variations
+    .iter()
+    .map(|variation| variation_to_sgf(field.field(), variation))
+    .collect();
+  Some(root)
+}
+
+pub fn to_sgf_with_variations_str(field: &ExtendedField, variations: &[Variation]) -> Option<String> {
+  to_sgf_with_variations(field, variations).map(|node| serialize(iter::once(&GameTree::Unknown(node))))
+}
@@ -1,5 +1,7 @@
-use crate::{from_sgf_str, to_sgf_str};
-use oppai_field::{any_field::AnyField, construct_field::construct_field, field::Field, player::Player};
+use crate::{from_sgf_str, to_sgf_str, to_sgf_with_variations_str, Variation};
+use oppai_field::{
+  any_field::AnyField, construct_field::construct_field, extended_field::ExtendedField, field::Field, player::Player,
+};
 use rand::SeedableRng;
 use rand_xoshiro::Xoshiro256PlusPlus;
 
@@ -103,3 +105,39 @@ fn zagram352562() {
   assert_eq!(field.captured_count(Player::Red), 60);
   assert_eq!(field.captured_count(Player::Black), 3);
 }
+
+#[test]
+fn variations() {
+  env_logger::try_init().ok();
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
+  let field: ExtendedField = construct_field(
+    &mut rng,
+    "
+    ...
+    .a.
+    ...
+    ",
+  )
+  .into();
+  let variation = |x, y, player, comment: &str, children| Variation {
+    pos: field.field().to_pos(x, y),
+    player,
+    comment: comment.into(),
+    children,
+  };
+  let variations = vec![
+    variation(
+      0,
+      0,
+      Player::Black,
+      "first",
+      vec![variation(2, 2, Player::Red, "", Vec::new())],
+    ),
+    variation(1, 0, Player::Black, "second", Vec::new()),
+  ];
+  let sgf = to_sgf_with_variations_str(&field, &variations).unwrap();
+  assert_eq!(
+    sgf,
+    "(;GM[40]SZ[3:3]RU[russian];W[bb](;B[aa]C[first];W[cc])(;B[ba]C[second]))"
+  );
+}
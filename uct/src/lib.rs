@@ -3,6 +3,8 @@
 #[macro_use]
 extern crate log;
 
+pub mod playout;
+pub mod prior;
 pub mod uct;
 #[cfg(test)]
 mod uct_test;
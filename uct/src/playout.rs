@@ -0,0 +1,174 @@
+use oppai_common::trajectory::build_trajectories;
+use oppai_field::field::{Field, Pos};
+use oppai_field::player::Player;
+use oppai_patterns::patterns::Patterns;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::sync::Arc;
+
+/// Probability to look for captures and escapes during guided playouts, either
+/// near the last move or among single move trajectories of the playout start.
+const CAPTURE_PROBABILITY: f64 = 0.9;
+
+/// Probability to respond to the last move with a pattern during guided
+/// playouts.
+const PATTERN_PROBABILITY: f64 = 0.5;
+
+/// Radius around the last move to look for patterns in.
+const PATTERN_RADIUS: u32 = 2;
+
+/// Probability to play near the last move during guided playouts.
+const LOCAL_PROBABILITY: f64 = 0.1;
+
+/// Plays random games from the UCT leaves.
+pub trait PlayoutPolicy: Send + Sync {
+  /// Plays the game till the end starting with a move of `player` and returns
+  /// its final score for `player`. Only `possible_moves` should be played, and
+  /// their order can be changed.
+  fn play<R: Rng>(&self, field: &mut Field, player: Player, possible_moves: &mut [Pos], rng: &mut R) -> i32;
+}
+
+/// Whether the point can be put during a playout. Moves inside empty bases are
+/// never played since they are suicidal.
+pub fn is_playout_move_allowed(field: &Field, pos: Pos) -> bool {
+  let cell = field.cell(pos);
+  cell.is_putting_allowed() && !cell.is_empty_base()
+}
+
+/// Moves are played in random order.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct UniformPolicy;
+
+impl PlayoutPolicy for UniformPolicy {
+  fn play<R: Rng>(&self, field: &mut Field, player: Player, possible_moves: &mut [Pos], rng: &mut R) -> i32 {
+    possible_moves.shuffle(rng);
+    let mut cur_player = player;
+    for &pos in possible_moves.iter() {
+      if is_playout_move_allowed(field, pos) {
+        field.put_point(pos, cur_player);
+        cur_player = cur_player.next();
+      }
+    }
+    field.score(player)
+  }
+}
+
+/// Each move responds to the previous one with a capture, an escape, a pattern,
+/// a still valid capture or escape from trajectories of the starting position
+/// or a near move when possible. Otherwise moves are played in random order as
+/// in uniform playouts. All of them are taken from possible moves.
+#[derive(Clone, Default)]
+pub struct GuidedPolicy {
+  patterns: Arc<Patterns>,
+}
+
+impl GuidedPolicy {
+  pub fn new(patterns: Arc<Patterns>) -> Self {
+    GuidedPolicy { patterns }
+  }
+
+  fn is_capturing_move(field: &mut Field, pos: Pos, player: Player) -> bool {
+    // Capturing is possible only if the point connects at least two groups.
+    if field.number_near_groups(pos, player) < 2 || !field.put_point(pos, player) {
+      return false;
+    }
+    let capturing = field.get_delta_score(player) > 0;
+    field.undo();
+    capturing
+  }
+
+  /// Capturing points of single move trajectories of both players, the most
+  /// valuable first. Playing such a point either captures or escapes.
+  fn urgent_moves(field: &mut Field, possible: &[bool]) -> Vec<(Pos, Player)> {
+    let mut empty_board = vec![0; field.length()];
+    let mut urgent_moves = Vec::new();
+    for player in [Player::Red, Player::Black] {
+      let score = field.score(player);
+      urgent_moves.extend(
+        build_trajectories(field, player, 1, &mut empty_board, &|| false)
+          .into_iter()
+          .map(|trajectory| (trajectory.points()[0], player, trajectory.score() - score))
+          .filter(|&(pos, _, _)| possible[pos]),
+      );
+    }
+    urgent_moves.sort_by_key(|&(_, _, gain)| -gain);
+    urgent_moves.into_iter().map(|(pos, player, _)| (pos, player)).collect()
+  }
+
+  /// Chooses a response to the last move among points marked in `possible`.
+  fn guided_move<R: Rng>(
+    &self,
+    field: &mut Field,
+    player: Player,
+    last_pos: Pos,
+    possible: &[bool],
+    urgent_moves: &mut Vec<(Pos, Player)>,
+    rng: &mut R,
+  ) -> Option<Pos> {
+    let is_allowed = |field: &Field, pos: Pos| possible[pos] && is_playout_move_allowed(field, pos);
+    let neighbours = field.directions_diag(last_pos);
+    if rng.gen_bool(CAPTURE_PROBABILITY) {
+      let enemy = player.next();
+      for pos in neighbours {
+        if is_allowed(field, pos)
+          && (GuidedPolicy::is_capturing_move(field, pos, player) || GuidedPolicy::is_capturing_move(field, pos, enemy))
+        {
+          return Some(pos);
+        }
+      }
+    }
+    if rng.gen_bool(PATTERN_PROBABILITY) {
+      let moves = self.patterns.find_near(field, player, last_pos, PATTERN_RADIUS, false);
+      let pos = moves
+        .into_iter()
+        .filter(|&pos| is_allowed(field, pos))
+        .collect::<Vec<_>>()
+        .choose(rng)
+        .copied();
+      if pos.is_some() {
+        return pos;
+      }
+    }
+    if rng.gen_bool(CAPTURE_PROBABILITY) {
+      // Trajectories that are no longer capturing are dropped for the rest of
+      // the playout.
+      urgent_moves.retain(|&(pos, owner)| is_allowed(field, pos) && GuidedPolicy::is_capturing_move(field, pos, owner));
+      if let Some(&(pos, _)) = urgent_moves.first() {
+        return Some(pos);
+      }
+    }
+    if rng.gen_bool(LOCAL_PROBABILITY) {
+      return neighbours
+        .iter()
+        .copied()
+        .filter(|&pos| is_allowed(field, pos))
+        .collect::<Vec<_>>()
+        .choose(rng)
+        .copied();
+    }
+    None
+  }
+}
+
+impl PlayoutPolicy for GuidedPolicy {
+  fn play<R: Rng>(&self, field: &mut Field, player: Player, possible_moves: &mut [Pos], rng: &mut R) -> i32 {
+    possible_moves.shuffle(rng);
+    let mut possible = vec![false; field.length()];
+    for &pos in possible_moves.iter() {
+      possible[pos] = true;
+    }
+    let mut moves = possible_moves.iter().copied();
+    let mut cur_player = player;
+    let mut last_pos = field.moves().last().copied();
+    let mut urgent_moves = GuidedPolicy::urgent_moves(field, &possible);
+    while let Some(pos) = last_pos
+      .and_then(|last_pos| self.guided_move(field, cur_player, last_pos, &possible, &mut urgent_moves, rng))
+      .or_else(|| moves.find(|&pos| is_playout_move_allowed(field, pos)))
+    {
+      field.put_point(pos, cur_player);
+      cur_player = cur_player.next();
+      last_pos = Some(pos);
+    }
+    field.score(player)
+  }
+}
@@ -0,0 +1,55 @@
+use oppai_field::field::{Field, Pos};
+use oppai_field::player::Player;
+use oppai_patterns::patterns::Patterns;
+use std::sync::Arc;
+
+/// Radius around the last move to look for patterns in.
+const PATTERN_RADIUS: u32 = 2;
+
+/// Weight of moves matched by patterns compared to other moves.
+const PATTERN_WEIGHT: f64 = 10.0;
+
+/// Estimates how promising moves are before they are searched.
+pub trait PriorPolicy: Send + Sync {
+  /// Returns non-negative weights of `moves` for `player`, or `None` if the
+  /// policy can't tell them apart. Weights are normalized by the caller.
+  fn priors(&self, field: &Field, player: Player, moves: &[Pos]) -> Option<Vec<f64>>;
+}
+
+/// All moves are equal, so children are unlocked in random order.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct NoPrior;
+
+impl PriorPolicy for NoPrior {
+  fn priors(&self, _: &Field, _: Player, _: &[Pos]) -> Option<Vec<f64>> {
+    None
+  }
+}
+
+/// Prefers moves matched by patterns near the last move.
+#[derive(Clone, Default)]
+pub struct PatternPrior {
+  patterns: Arc<Patterns>,
+}
+
+impl PatternPrior {
+  pub fn new(patterns: Arc<Patterns>) -> Self {
+    PatternPrior { patterns }
+  }
+}
+
+impl PriorPolicy for PatternPrior {
+  fn priors(&self, field: &Field, player: Player, moves: &[Pos]) -> Option<Vec<f64>> {
+    let &last_pos = field.moves().last()?;
+    let matched = self.patterns.find_near(field, player, last_pos, PATTERN_RADIUS, false);
+    if matched.is_empty() {
+      return None;
+    }
+    Some(
+      moves
+        .iter()
+        .map(|pos| if matched.contains(pos) { PATTERN_WEIGHT } else { 1.0 })
+        .collect(),
+    )
+  }
+}
@@ -1,13 +1,22 @@
-use crate::wave_pruning::WavePruning;
+use crate::playout::{PlayoutPolicy, UniformPolicy};
+use crate::prior::{NoPrior, PriorPolicy};
+use crate::wave_pruning::{Region, WavePruning};
 use oppai_common::common;
 use oppai_field::field::{Field, Pos};
 use oppai_field::player::Player;
+use oppai_ladders::ladders::losing_ladder_moves;
 use rand::distributions::{Distribution, Standard};
 use rand::seq::SliceRandom;
 use rand::{Rng, SeedableRng};
 use std::{
-  ptr,
-  sync::atomic::{AtomicIsize, AtomicPtr, AtomicUsize, Ordering},
+  cmp,
+  collections::VecDeque,
+  f64::consts::FRAC_2_PI,
+  iter, mem, ptr,
+  sync::{
+    atomic::{AtomicBool, AtomicI64, AtomicIsize, AtomicU32, AtomicU64, AtomicU8, AtomicUsize, Ordering},
+    OnceLock,
+  },
 };
 use strum::{EnumString, VariantNames};
 
@@ -16,6 +25,8 @@ pub enum UcbType {
   Winrate,
   Ucb1,
   Ucb1Tuned,
+  /// UCB1-Tuned over the winrate blended with all-moves-as-first statistics.
+  Rave,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, EnumString, VariantNames)]
@@ -29,6 +40,11 @@ pub enum UctKomiType {
 pub struct UctConfig {
   pub threads_count: usize,
   pub radius: u32,
+  /// Maximum radius added around moves with free points nearby. It's
+  /// proportional to the share of free points.
+  pub empty_radius: u32,
+  /// Radius added around the last moves.
+  pub recent_radius: u32,
   pub ucb_type: UcbType,
   pub draw_weight: f64,
   pub uctk: f64,
@@ -39,6 +55,64 @@ pub struct UctConfig {
   pub green: f64,
   pub komi_min_iterations: usize,
   pub fpu: f64,
+  /// Number of visits at which UCT and AMAF winrates have equal weights with
+  /// the RAVE formula.
+  pub rave_equivalence: f64,
+  /// Weight of the expected score utility added to the winrate. Zero makes
+  /// the search rely on the winrate only.
+  pub score_weight: f64,
+  /// Score difference at which the score utility reaches half of its weight.
+  pub score_scale: f64,
+  /// Weight of the PUCT term that favours children with high priors.
+  pub prior_weight: f64,
+  /// Children are unlocked in the order of their priors, so that a node with
+  /// `n` visits has `widening_base * n ^ widening_exponent` of them available.
+  /// Zero makes all children available at once.
+  pub widening_base: f64,
+  pub widening_exponent: f64,
+  /// Maximum memory of the tree in megabytes. When it's reached the tree is
+  /// compacted pruning the least visited subtrees, either between moves or
+  /// during the search. Compaction might temporarily take half of this limit
+  /// more.
+  pub memory: usize,
+  /// Makes the analysis depend only on the rng and the iterations count
+  /// regardless of the threads count and scheduling.
+  pub deterministic: bool,
+  /// Number of losses a node temporarily gets while a thread descends
+  /// through it, so that other threads prefer different paths. It's not used
+  /// with a single thread or in deterministic mode.
+  pub virtual_loss: usize,
+  /// Exclude root moves that start or extend a ladder losing for the player.
+  pub forbid_losing_ladders: bool,
+}
+
+/// Radius of wave pruning around the move. It's larger when there are few
+/// points nearby, so that the search sees distant moves on an empty field
+/// while crowded fights stay focused.
+fn wave_radius(config: &UctConfig, field: &Field, pos: Pos) -> u32 {
+  if config.empty_radius == 0 {
+    return config.radius;
+  }
+  let r = (config.radius + config.empty_radius) as i32;
+  let x = field.to_x(pos) as i32;
+  let y = field.to_y(pos) as i32;
+  let mut total = 0;
+  let mut free = 0;
+  for dx in -r..=r {
+    let r_y = r - dx.abs();
+    for dy in -r_y..=r_y {
+      if (0..field.width() as i32).contains(&(x + dx)) && (0..field.height() as i32).contains(&(y + dy)) {
+        total += 1;
+        if field
+          .cell(field.to_pos((x + dx) as u32, (y + dy) as u32))
+          .is_putting_allowed()
+        {
+          free += 1;
+        }
+      }
+    }
+  }
+  config.radius + (config.empty_radius * free + total / 2) / total
 }
 
 impl Default for UctConfig {
@@ -49,6 +123,8 @@ impl Default for UctConfig {
       #[cfg(target_arch = "wasm32")]
       threads_count: 1,
       radius: 3,
+      empty_radius: 0,
+      recent_radius: 0,
       ucb_type: UcbType::Ucb1Tuned,
       draw_weight: 0.4,
       uctk: 1.0,
@@ -59,23 +135,122 @@ impl Default for UctConfig {
       green: 0.5,
       komi_min_iterations: 3000,
       fpu: 1.1,
+      rave_equivalence: 300.0,
+      score_weight: 0.0,
+      score_scale: 10.0,
+      prior_weight: 1.0,
+      widening_base: 0.0,
+      widening_exponent: 0.5,
+      memory: 1024,
+      deterministic: false,
+      virtual_loss: 3,
+      forbid_losing_ladders: false,
     }
   }
 }
 
+/// Proven result of the game for the player who made the move of the node.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Proof {
+  Win = 1,
+  Loss = 2,
+  Draw = 3,
+}
+
+impl Proof {
+  fn from_score(score: i32) -> Proof {
+    use std::cmp::Ordering;
+    match score.cmp(&0) {
+      Ordering::Greater => Proof::Win,
+      Ordering::Less => Proof::Loss,
+      Ordering::Equal => Proof::Draw,
+    }
+  }
+}
+
+/// Statistics of a UCT node for inspection. Wins, draws and the proof are
+/// given for the player who made the move of the node.
+#[derive(Clone, PartialEq, Debug)]
+pub struct UctNodeInfo {
+  pub pos: Pos,
+  pub visits: usize,
+  pub wins: usize,
+  pub draws: usize,
+  pub winrate: f64,
+  /// Value the node would get during selection, without progressive widening.
+  pub ucb: f64,
+  pub prior: f64,
+  /// Mean score of random games.
+  pub score: f64,
+  /// All-moves-as-first visits, which are counted only with RAVE.
+  pub amaf_visits: usize,
+  pub proof: Option<Proof>,
+  pub children: Vec<UctNodeInfo>,
+}
+
+/// Snapshot of the UCT tree.
+#[derive(Clone, PartialEq, Debug)]
+pub struct UctTreeInfo {
+  /// Player to move at the root.
+  pub player: Player,
+  pub moves_count: usize,
+  pub komi: isize,
+  pub root: UctNodeInfo,
+}
+
+/// Index of a node in the arena.
+type NodeIndex = u32;
+
+/// Index of a missing node.
+const NO_NODE: NodeIndex = NodeIndex::MAX;
+
+/// Number of nodes allocated at once.
+const SEGMENT_SIZE: usize = 1 << 16;
+
+/// Number of simulations planned at once in deterministic mode. Smaller
+/// rounds are closer to sequential UCT, larger ones leave more root children
+/// to play in parallel.
+const DETERMINISTIC_ROUND_SIZE: usize = 64;
+
 struct UctNode {
   wins: AtomicUsize,
   draws: AtomicUsize,
   visits: AtomicUsize,
-  pos: Pos,
-  child: AtomicPtr<UctNode>,
-  sibling: Option<Box<UctNode>>,
+  /// Part of visits added by `add_virtual_loss`.
+  virtual_visits: AtomicUsize,
+  amaf_wins: AtomicUsize,
+  amaf_draws: AtomicUsize,
+  amaf_visits: AtomicUsize,
+  proof: AtomicU8,
+  /// Some moves are missing from the children because the arena was full when
+  /// they were added.
+  partial: AtomicBool,
+  score_sum: AtomicI64,
+  score_square_sum: AtomicU64,
+  prior: AtomicU64,
+  pos: AtomicUsize,
+  child: AtomicU32,
+  sibling: AtomicU32,
 }
 
-impl Drop for UctNode {
-  fn drop(&mut self) {
-    unsafe {
-      self.clear_child();
+impl Default for UctNode {
+  fn default() -> Self {
+    UctNode {
+      wins: AtomicUsize::new(0),
+      draws: AtomicUsize::new(0),
+      visits: AtomicUsize::new(0),
+      virtual_visits: AtomicUsize::new(0),
+      amaf_wins: AtomicUsize::new(0),
+      amaf_draws: AtomicUsize::new(0),
+      amaf_visits: AtomicUsize::new(0),
+      proof: AtomicU8::new(0),
+      partial: AtomicBool::new(false),
+      score_sum: AtomicI64::new(0),
+      score_square_sum: AtomicU64::new(0),
+      prior: AtomicU64::new(0),
+      pos: AtomicUsize::new(0),
+      child: AtomicU32::new(NO_NODE),
+      sibling: AtomicU32::new(NO_NODE),
     }
   }
 }
@@ -86,141 +261,339 @@ impl Clone for UctNode {
       wins: AtomicUsize::new(self.wins.load(Ordering::SeqCst)),
       draws: AtomicUsize::new(self.draws.load(Ordering::SeqCst)),
       visits: AtomicUsize::new(self.visits.load(Ordering::SeqCst)),
-      pos: self.pos,
-      child: self.get_child_ref().cloned().map_or(AtomicPtr::default(), |child| {
-        AtomicPtr::new(Box::into_raw(Box::new(child)))
-      }),
-      sibling: self.sibling.clone(),
+      virtual_visits: AtomicUsize::new(self.virtual_visits.load(Ordering::SeqCst)),
+      amaf_wins: AtomicUsize::new(self.amaf_wins.load(Ordering::SeqCst)),
+      amaf_draws: AtomicUsize::new(self.amaf_draws.load(Ordering::SeqCst)),
+      amaf_visits: AtomicUsize::new(self.amaf_visits.load(Ordering::SeqCst)),
+      proof: AtomicU8::new(self.proof.load(Ordering::SeqCst)),
+      partial: AtomicBool::new(self.partial.load(Ordering::SeqCst)),
+      score_sum: AtomicI64::new(self.score_sum.load(Ordering::SeqCst)),
+      score_square_sum: AtomicU64::new(self.score_square_sum.load(Ordering::SeqCst)),
+      prior: AtomicU64::new(self.prior.load(Ordering::SeqCst)),
+      pos: AtomicUsize::new(self.pos.load(Ordering::SeqCst)),
+      child: AtomicU32::new(self.child.load(Ordering::SeqCst)),
+      sibling: AtomicU32::new(self.sibling.load(Ordering::SeqCst)),
     }
   }
 }
 
 impl UctNode {
-  pub fn new(pos: Pos) -> UctNode {
-    UctNode {
-      wins: AtomicUsize::new(0),
-      draws: AtomicUsize::new(0),
-      visits: AtomicUsize::new(0),
-      pos,
-      child: AtomicPtr::new(ptr::null_mut()),
-      sibling: None,
-    }
+  /// Turns the node into a new leaf since nodes of a cleared arena are reused.
+  pub fn init(&self, pos: Pos) {
+    self.clear_stats();
+    self.virtual_visits.store(0, Ordering::Relaxed);
+    self.clear_proof();
+    self.set_partial(false);
+    self.prior.store(0, Ordering::Relaxed);
+    self.pos.store(pos, Ordering::Relaxed);
+    self.child.store(NO_NODE, Ordering::Relaxed);
+    self.sibling.store(NO_NODE, Ordering::Relaxed);
+  }
+
+  /// Copies statistics and the proof of a node from another arena.
+  pub fn copy_stats(&self, node: &UctNode) {
+    let copy = |to: &AtomicUsize, from: &AtomicUsize| to.store(from.load(Ordering::Relaxed), Ordering::Relaxed);
+    copy(&self.wins, &node.wins);
+    copy(&self.draws, &node.draws);
+    copy(&self.visits, &node.visits);
+    copy(&self.virtual_visits, &node.virtual_visits);
+    copy(&self.amaf_wins, &node.amaf_wins);
+    copy(&self.amaf_draws, &node.amaf_draws);
+    copy(&self.amaf_visits, &node.amaf_visits);
+    self.proof.store(node.proof.load(Ordering::Relaxed), Ordering::Relaxed);
+    self.set_partial(node.is_partial());
+    self.score_sum.store(node.get_score_sum(), Ordering::Relaxed);
+    self
+      .score_square_sum
+      .store(node.score_square_sum.load(Ordering::Relaxed), Ordering::Relaxed);
+    self.prior.store(node.prior.load(Ordering::Relaxed), Ordering::Relaxed);
   }
 
   pub fn get_pos(&self) -> Pos {
-    self.pos
+    self.pos.load(Ordering::Relaxed)
   }
 
-  pub fn get_sibling(&mut self) -> Option<Box<UctNode>> {
-    self.sibling.take()
+  pub fn get_visits(&self) -> usize {
+    self.visits.load(Ordering::Relaxed)
   }
 
-  pub fn get_sibling_ref(&self) -> Option<&UctNode> {
-    self.sibling.as_deref()
+  /// Visits without virtual losses of other threads. It can be less than the
+  /// actual number while a virtual loss is being added or reverted.
+  pub fn get_real_visits(&self) -> usize {
+    let virtual_visits = self.virtual_visits.load(Ordering::Relaxed);
+    self.get_visits().saturating_sub(virtual_visits)
   }
 
-  pub fn get_sibling_mut(&mut self) -> Option<&mut UctNode> {
-    self.sibling.as_deref_mut()
+  pub fn get_wins(&self) -> usize {
+    self.wins.load(Ordering::Relaxed)
   }
 
-  pub fn clear_sibling(&mut self) {
-    self.sibling = None;
+  pub fn get_draws(&self) -> usize {
+    self.draws.load(Ordering::Relaxed)
   }
 
-  pub fn set_sibling(&mut self, sibling: Box<UctNode>) {
-    self.sibling = Some(sibling);
+  pub fn add_win(&self) {
+    self.visits.fetch_add(1, Ordering::Relaxed);
+    self.wins.fetch_add(1, Ordering::Relaxed);
   }
 
-  pub fn set_sibling_option(&mut self, sibling: Option<Box<UctNode>>) {
-    self.sibling = sibling;
+  pub fn add_draw(&self) {
+    self.visits.fetch_add(1, Ordering::Relaxed);
+    self.draws.fetch_add(1, Ordering::Relaxed);
   }
 
-  pub fn get_child(&self) -> Option<Box<UctNode>> {
-    let ptr = self.child.swap(ptr::null_mut(), Ordering::Relaxed);
-    if ptr.is_null() {
-      None
-    } else {
-      Some(unsafe { Box::from_raw(ptr) })
-    }
+  pub fn add_loose(&self) {
+    self.visits.fetch_add(1, Ordering::Relaxed);
   }
 
-  pub fn get_child_ref(&self) -> Option<&UctNode> {
-    let ptr = self.child.load(Ordering::Relaxed);
-    if ptr.is_null() {
-      None
-    } else {
-      Some(unsafe { &*ptr })
-    }
+  /// Adds visits without wins unless the node is lost.
+  pub fn add_virtual_loss(&self, count: usize) {
+    self.virtual_visits.fetch_add(count, Ordering::Relaxed);
+    self
+      .visits
+      .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |visits| {
+        (visits != usize::MAX).then(|| visits + count)
+      })
+      .ok();
   }
 
-  pub fn get_child_mut(&mut self) -> Option<&mut UctNode> {
-    let ptr = self.child.load(Ordering::Relaxed);
-    if ptr.is_null() {
-      None
-    } else {
-      Some(unsafe { &mut *ptr })
-    }
+  /// Removes visits added by `add_virtual_loss` unless the node is lost.
+  pub fn revert_virtual_loss(&self, count: usize) {
+    self
+      .visits
+      .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |visits| {
+        (visits != usize::MAX).then(|| visits - count)
+      })
+      .ok();
+    self.virtual_visits.fetch_sub(count, Ordering::Relaxed);
   }
 
-  unsafe fn clear_child(&self) {
-    let ptr = self.child.swap(ptr::null_mut(), Ordering::Relaxed);
-    if !ptr.is_null() {
-      drop::<Box<UctNode>>(Box::from_raw(ptr));
-    }
+  pub fn get_amaf_visits(&self) -> usize {
+    self.amaf_visits.load(Ordering::Relaxed)
   }
 
-  pub fn set_child(&self, child: Box<UctNode>) {
-    let child_ptr = Box::into_raw(child);
-    let result = self
-      .child
-      .compare_exchange(ptr::null_mut(), child_ptr, Ordering::Relaxed, Ordering::Relaxed);
-    if result.is_err() {
-      drop::<Box<UctNode>>(unsafe { Box::from_raw(child_ptr) });
-    }
+  pub fn get_amaf_wins(&self) -> usize {
+    self.amaf_wins.load(Ordering::Relaxed)
   }
 
-  pub fn get_visits(&self) -> usize {
-    self.visits.load(Ordering::Relaxed)
+  pub fn get_amaf_draws(&self) -> usize {
+    self.amaf_draws.load(Ordering::Relaxed)
   }
 
-  pub fn get_wins(&self) -> usize {
-    self.wins.load(Ordering::Relaxed)
+  pub fn add_amaf_win(&self) {
+    self.amaf_visits.fetch_add(1, Ordering::Relaxed);
+    self.amaf_wins.fetch_add(1, Ordering::Relaxed);
   }
 
-  pub fn get_draws(&self) -> usize {
-    self.draws.load(Ordering::Relaxed)
+  pub fn add_amaf_draw(&self) {
+    self.amaf_visits.fetch_add(1, Ordering::Relaxed);
+    self.amaf_draws.fetch_add(1, Ordering::Relaxed);
   }
 
-  pub fn add_win(&self) {
-    self.visits.fetch_add(1, Ordering::Relaxed);
-    self.wins.fetch_add(1, Ordering::Relaxed);
+  pub fn add_amaf_loose(&self) {
+    self.amaf_visits.fetch_add(1, Ordering::Relaxed);
   }
 
-  pub fn add_draw(&self) {
-    self.visits.fetch_add(1, Ordering::Relaxed);
-    self.draws.fetch_add(1, Ordering::Relaxed);
+  /// Accumulates the final score of a random game for the player who made the
+  /// move of the node.
+  pub fn add_score(&self, score: i32) {
+    self.score_sum.fetch_add(score as i64, Ordering::Relaxed);
+    self
+      .score_square_sum
+      .fetch_add((score as i64 * score as i64) as u64, Ordering::Relaxed);
   }
 
-  pub fn add_loose(&self) {
-    self.visits.fetch_add(1, Ordering::Relaxed);
+  pub fn get_score_sum(&self) -> i64 {
+    self.score_sum.load(Ordering::Relaxed)
+  }
+
+  pub fn get_mean_score(&self) -> f64 {
+    self.get_score_sum() as f64 / self.get_visits() as f64
+  }
+
+  pub fn get_score_variance(&self) -> f64 {
+    let mean = self.get_mean_score();
+    self.score_square_sum.load(Ordering::Relaxed) as f64 / self.get_visits() as f64 - mean * mean
+  }
+
+  /// Prior probability of the move of the node.
+  pub fn get_prior(&self) -> f64 {
+    f64::from_bits(self.prior.load(Ordering::Relaxed))
+  }
+
+  pub fn set_prior(&self, prior: f64) {
+    self.prior.store(prior.to_bits(), Ordering::Relaxed);
+  }
+
+  pub fn get_proof(&self) -> Option<Proof> {
+    match self.proof.load(Ordering::Relaxed) {
+      1 => Some(Proof::Win),
+      2 => Some(Proof::Loss),
+      3 => Some(Proof::Draw),
+      _ => None,
+    }
+  }
+
+  pub fn set_proof(&self, proof: Proof) {
+    self.proof.store(proof as u8, Ordering::Relaxed);
+  }
+
+  pub fn clear_proof(&self) {
+    self.proof.store(0, Ordering::Relaxed);
+  }
+
+  pub fn is_partial(&self) -> bool {
+    self.partial.load(Ordering::Relaxed)
+  }
+
+  pub fn set_partial(&self, partial: bool) {
+    self.partial.store(partial, Ordering::Relaxed);
   }
 
   pub fn lose_node(&self) {
     self.wins.store(0, Ordering::Relaxed);
     self.draws.store(0, Ordering::Relaxed);
-    self.visits.store(usize::max_value(), Ordering::Relaxed);
+    self.visits.store(usize::MAX, Ordering::Relaxed);
   }
 
   pub fn clear_stats(&self) {
     self.wins.store(0, Ordering::Relaxed);
     self.draws.store(0, Ordering::Relaxed);
     self.visits.store(0, Ordering::Relaxed);
+    self.amaf_wins.store(0, Ordering::Relaxed);
+    self.amaf_draws.store(0, Ordering::Relaxed);
+    self.amaf_visits.store(0, Ordering::Relaxed);
+    self.score_sum.store(0, Ordering::Relaxed);
+    self.score_square_sum.store(0, Ordering::Relaxed);
+  }
+}
+
+/// Storage of UCT nodes. Nodes are allocated in large segments, never freed
+/// one by one and linked by indices, so the number of nodes can be limited.
+struct UctArena {
+  segments: Box<[OnceLock<Box<[UctNode]>>]>,
+  capacity: usize,
+  len: AtomicUsize,
+}
+
+impl Clone for UctArena {
+  fn clone(&self) -> Self {
+    Self {
+      segments: self.segments.clone(),
+      capacity: self.capacity,
+      len: AtomicUsize::new(self.len.load(Ordering::SeqCst)),
+    }
+  }
+}
+
+impl UctArena {
+  pub fn new(capacity: usize) -> Self {
+    let capacity = capacity.clamp(1, NO_NODE as usize);
+    UctArena {
+      segments: (0..capacity.div_ceil(SEGMENT_SIZE)).map(|_| OnceLock::new()).collect(),
+      capacity,
+      len: AtomicUsize::new(0),
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.len.load(Ordering::Relaxed)
+  }
+
+  pub fn capacity(&self) -> usize {
+    self.capacity
+  }
+
+  /// Memory allocated for nodes in bytes.
+  pub fn memory(&self) -> usize {
+    self
+      .segments
+      .iter()
+      .filter_map(OnceLock::get)
+      .map(|segment| segment.len())
+      .sum::<usize>()
+      * mem::size_of::<UctNode>()
+  }
+
+  /// Forgets all nodes keeping allocated segments for reuse.
+  pub fn clear(&mut self) {
+    *self.len.get_mut() = 0;
+  }
+
+  pub fn get(&self, index: NodeIndex) -> &UctNode {
+    let index = index as usize;
+    &self.segments[index / SEGMENT_SIZE]
+      .get()
+      .expect("UCT node is not allocated")[index % SEGMENT_SIZE]
+  }
+
+  fn get_link(&self, index: NodeIndex) -> Option<&UctNode> {
+    (index != NO_NODE).then(|| self.get(index))
+  }
+
+  pub fn child(&self, node: &UctNode) -> Option<&UctNode> {
+    self.get_link(node.child.load(Ordering::Acquire))
+  }
+
+  pub fn sibling(&self, node: &UctNode) -> Option<&UctNode> {
+    self.get_link(node.sibling.load(Ordering::Relaxed))
+  }
+
+  pub fn children<'a>(&'a self, node: &UctNode) -> impl Iterator<Item = &'a UctNode> + 'a {
+    iter::successors(self.child(node), move |&child| self.sibling(child))
+  }
+
+  pub fn find_child(&self, node: &UctNode, pos: Pos) -> Option<NodeIndex> {
+    let mut index = node.child.load(Ordering::Acquire);
+    while index != NO_NODE && self.get(index).get_pos() != pos {
+      index = self.get(index).sibling.load(Ordering::Relaxed);
+    }
+    (index != NO_NODE).then_some(index)
+  }
+
+  /// Allocates new leaves for `moves` linked as siblings and returns the index
+  /// of the first one, or `None` if there is no space left.
+  pub fn alloc(&self, moves: &[Pos]) -> Option<NodeIndex> {
+    let count = moves.len();
+    if count == 0 {
+      return None;
+    }
+    let start = self
+      .len
+      .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |len| {
+        (len + count <= self.capacity).then_some(len + count)
+      })
+      .ok()?;
+    for segment in start / SEGMENT_SIZE..=(start + count - 1) / SEGMENT_SIZE {
+      self.segments[segment].get_or_init(|| {
+        let size = SEGMENT_SIZE.min(self.capacity - segment * SEGMENT_SIZE);
+        (0..size).map(|_| UctNode::default()).collect()
+      });
+    }
+    for (i, &pos) in moves.iter().enumerate() {
+      let node = self.get((start + i) as NodeIndex);
+      node.init(pos);
+      if i + 1 < count {
+        node.sibling.store((start + i + 1) as NodeIndex, Ordering::Relaxed);
+      }
+    }
+    Some(start as NodeIndex)
+  }
+
+  /// Attaches allocated children to the node unless another thread has already
+  /// done it. In the latter case they stay unused until the tree is compacted.
+  pub fn set_child(&self, node: &UctNode, child: NodeIndex) {
+    node
+      .child
+      .compare_exchange(NO_NODE, child, Ordering::Release, Ordering::Relaxed)
+      .ok();
   }
 }
 
-pub struct UctRoot {
+pub struct UctRoot<P = UniformPolicy, Q = NoPrior> {
   config: UctConfig,
-  node: Option<Box<UctNode>>,
+  arena: UctArena,
+  node: Option<NodeIndex>,
   player: Player,
   moves_count: usize,
   hash: u64,
@@ -229,13 +602,18 @@ pub struct UctRoot {
   komi_visits: AtomicUsize,
   komi_wins: AtomicUsize,
   komi_draws: AtomicUsize,
+  policy: P,
+  prior: Q,
+  /// Root moves that start or extend a ladder losing for the player.
+  forbidden_moves: Vec<Pos>,
 }
 
-impl Clone for UctRoot {
+impl<P: Clone, Q: Clone> Clone for UctRoot<P, Q> {
   fn clone(&self) -> Self {
     Self {
       config: self.config.clone(),
-      node: self.node.clone(),
+      arena: self.arena.clone(),
+      node: self.node,
       player: self.player,
       moves_count: self.moves_count,
       hash: self.hash,
@@ -244,13 +622,17 @@ impl Clone for UctRoot {
       komi_visits: AtomicUsize::new(self.komi_visits.load(Ordering::SeqCst)),
       komi_wins: AtomicUsize::new(self.komi_wins.load(Ordering::SeqCst)),
       komi_draws: AtomicUsize::new(self.komi_draws.load(Ordering::SeqCst)),
+      policy: self.policy.clone(),
+      prior: self.prior.clone(),
+      forbidden_moves: self.forbidden_moves.clone(),
     }
   }
 }
 
-impl UctRoot {
+impl<P: PlayoutPolicy, Q: PriorPolicy> UctRoot<P, Q> {
   pub fn clear(&mut self) {
     self.node = None;
+    self.arena.clear();
     self.wave_pruning.clear();
     self.player = Player::Red;
     self.moves_count = 0;
@@ -259,40 +641,193 @@ impl UctRoot {
     self.komi_visits = AtomicUsize::new(0);
     self.komi_wins = AtomicUsize::new(0);
     self.komi_draws = AtomicUsize::new(0);
+    self.forbidden_moves.clear();
+  }
+
+  /// Number of allocated nodes. Nodes unreachable from the root are counted
+  /// until the tree is compacted.
+  pub fn nodes_count(&self) -> usize {
+    self.arena.len()
+  }
+
+  /// Memory allocated for the tree in bytes.
+  pub fn memory_usage(&self) -> usize {
+    self.arena.memory()
+  }
+
+  /// Restricts or extends the points considered by the search. The tree is
+  /// cleared since it might contain moves outside of the region.
+  pub fn set_region(&mut self, region: Region) {
+    self.clear();
+    self.wave_pruning.set_region(region);
+  }
+
+  fn root(&self) -> Option<&UctNode> {
+    self.node.map(|index| self.arena.get(index))
   }
 
   fn init(&mut self, field: &Field, player: Player) {
     debug!("Initialization.");
-    self.node = Some(Box::new(UctNode::new(0)));
+    self.arena.clear();
+    self.node = self.arena.alloc(&[0]);
     self.player = player;
     self.moves_count = field.moves_count();
     self.hash = field.hash();
     if self.config.komi_type != UctKomiType::None {
       self.komi = AtomicIsize::new(field.score(player) as isize);
     }
-    self.wave_pruning.init(field, self.config.radius);
+    let config = &self.config;
+    let last_pos = field.moves().last().copied();
+    self.wave_pruning.init(field, |pos| {
+      wave_radius(config, field, pos) + if Some(pos) == last_pos { config.recent_radius } else { 0 }
+    });
   }
 
-  fn expand_node<R: Rng>(node: &mut UctNode, moves: &mut Vec<Pos>, rng: &mut R) {
-    if node.get_child_ref().is_none() {
-      if node.get_visits() == usize::max_value() {
+  fn expand_node<R: Rng>(&self, node: &UctNode, moves: &mut Vec<Pos>, rng: &mut R) {
+    if self.arena.child(node).is_none() {
+      if node.get_visits() == usize::MAX {
         node.clear_stats();
       }
     } else {
-      let mut next = node.get_child_mut();
-      while next.as_ref().unwrap().get_sibling_ref().is_some() {
-        UctRoot::expand_node(next.as_mut().unwrap(), moves, rng);
-        next = next.unwrap().get_sibling_mut();
+      let mut last = node;
+      for child in self.arena.children(node) {
+        self.expand_node(child, moves, rng);
+        last = child;
       }
-      UctRoot::expand_node(next.as_mut().unwrap(), moves, rng);
       moves.shuffle(rng);
-      for &pos in moves.iter() {
-        next.as_mut().unwrap().set_sibling(Box::new(UctNode::new(pos)));
-        next = next.unwrap().get_sibling_mut();
+      if !moves.is_empty() {
+        match self.arena.alloc(moves) {
+          Some(index) => last.sibling.store(index, Ordering::Relaxed),
+          // There is no space for new children, so the node keeps searching the
+          // old ones.
+          None => node.set_partial(true),
+        }
       }
+      // New children might refute the proof.
+      node.clear_proof();
+      self.update_proof(node);
     }
   }
 
+  /// Proves the node if one of its children is a proven win for the player to
+  /// move or all of them are proven. Children pruned as stupid moves are
+  /// considered lost, but at least one child should be proven. Partial nodes
+  /// can't be proven by their children being lost.
+  fn update_proof(&self, node: &UctNode) {
+    let mut all_proven = true;
+    let mut any_proven = false;
+    let mut draw = false;
+    for child in self.arena.children(node) {
+      match child.get_proof() {
+        Some(Proof::Win) => {
+          node.set_proof(Proof::Loss);
+          return;
+        }
+        Some(Proof::Loss) => any_proven = true,
+        Some(Proof::Draw) => {
+          any_proven = true;
+          draw = true;
+        }
+        None => all_proven &= child.get_visits() == usize::MAX,
+      }
+    }
+    if all_proven && any_proven && !node.is_partial() {
+      node.set_proof(if draw { Proof::Draw } else { Proof::Win });
+    }
+  }
+
+  fn is_proven(&self) -> bool {
+    self.root().is_some_and(|node| node.get_proof().is_some())
+  }
+
+  /// Visits count at or below which nodes lose their children so that the tree
+  /// fits into `budget` nodes, or `None` if it fits as is.
+  fn pruning_threshold(&self, root: &UctNode, budget: usize) -> Option<usize> {
+    let mut internal = Vec::new();
+    let mut stack = vec![root];
+    let mut size = 1;
+    while let Some(node) = stack.pop() {
+      let len = stack.len();
+      stack.extend(self.arena.children(node));
+      let children_count = stack.len() - len;
+      if children_count > 0 {
+        internal.push((node.get_visits(), children_count));
+        size += children_count;
+      }
+    }
+    if size <= budget {
+      return None;
+    }
+    internal.sort_unstable_by_key(|&(visits, _)| cmp::Reverse(visits));
+    let mut size = 1;
+    for (visits, children_count) in internal {
+      size += children_count;
+      if size > budget {
+        return Some(visits);
+      }
+    }
+    None
+  }
+
+  /// Copies the tree under the root to a new arena dropping unreachable nodes.
+  /// If the tree takes more than half of the arena, children of the least
+  /// visited nodes are dropped as well, so that the search has space to grow.
+  fn compact(&mut self) {
+    let Some(root) = self.root() else {
+      return;
+    };
+    let threshold = self.pruning_threshold(root, self.arena.capacity() / 2);
+    let arena = UctArena::new(self.arena.capacity());
+    let new_root = arena.alloc(&[root.get_pos()]).unwrap();
+    arena.get(new_root).copy_stats(root);
+    let mut queue = VecDeque::from([(root, new_root)]);
+    let mut pruned = 0;
+    while let Some((node, index)) = queue.pop_front() {
+      let children = self.arena.children(node).collect::<Vec<_>>();
+      if children.is_empty() {
+        continue;
+      }
+      let new_node = arena.get(index);
+      if threshold.is_some_and(|threshold| node.get_visits() <= threshold) {
+        // The proof might depend on dropped children. All moves are considered
+        // again when the node is expanded.
+        new_node.clear_proof();
+        new_node.set_partial(false);
+        pruned += 1;
+        continue;
+      }
+      let moves = children.iter().map(|child| child.get_pos()).collect::<Vec<_>>();
+      let first = arena.alloc(&moves).unwrap();
+      arena.set_child(new_node, first);
+      for (i, child) in children.into_iter().enumerate() {
+        let new_child = first + i as NodeIndex;
+        arena.get(new_child).copy_stats(child);
+        queue.push_back((child, new_child));
+      }
+    }
+    info!(
+      "Tree is compacted from {} to {} nodes, {} subtrees are pruned.",
+      self.arena.len(),
+      arena.len(),
+      pruned
+    );
+    self.arena = arena;
+    self.node = Some(new_root);
+  }
+
+  /// Whether fewer than `reserve` nodes are free in the arena.
+  fn is_full(&self, reserve: usize) -> bool {
+    self.arena.len() + reserve > self.arena.capacity()
+  }
+
+  /// Compacts the full tree during the search. Returns `false` if no nodes are
+  /// freed, so that the search goes on without further compactions.
+  fn compact_full(&mut self) -> bool {
+    let nodes_count = self.arena.len();
+    self.compact();
+    self.arena.len() < nodes_count
+  }
+
   fn update<R: Rng>(&mut self, field: &Field, player: Player, rng: &mut R) {
     if self.node.is_some() && field.hash_at(self.moves_count) != Some(self.hash) {
       self.clear();
@@ -309,8 +844,14 @@ impl UctRoot {
           if self.player != player {
             self.clear();
             self.init(field, player);
-          } else if let Some(node) = self.node.as_mut() {
-            let mut added_moves = self.wave_pruning.update(field, last_moves_count, self.config.radius);
+          } else if self.node.is_some() {
+            if self.moves_count != last_moves_count || self.arena.len() > self.arena.capacity() / 2 {
+              self.compact();
+            }
+            let config = &self.config;
+            let mut added_moves = self.wave_pruning.update(field, last_moves_count, |pos| {
+              wave_radius(config, field, pos) + config.recent_radius
+            });
             debug!(
               "Added into consideration moves: {:?}.",
               added_moves
@@ -318,13 +859,15 @@ impl UctRoot {
                 .map(|&pos| (field.to_x(pos), field.to_y(pos)))
                 .collect::<Vec<(u32, u32)>>()
             );
-            UctRoot::expand_node(node, &mut added_moves, rng);
+            let node = self.root().unwrap();
+            self.expand_node(node, &mut added_moves, rng);
+            self.prioritize_root(field, player);
             match self.config.komi_type {
-              UctKomiType::Static => self.komi = AtomicIsize::new(field.score(self.player) as isize),
+              UctKomiType::Static => self.komi.store(field.score(self.player) as isize, Ordering::Relaxed),
               UctKomiType::Dynamic => {
-                self.komi_visits = AtomicUsize::new(node.get_visits());
-                self.komi_wins = AtomicUsize::new(node.get_wins());
-                self.komi_draws = AtomicUsize::new(node.get_draws());
+                self.komi_visits.store(node.get_visits(), Ordering::Relaxed);
+                self.komi_wins.store(node.get_wins(), Ordering::Relaxed);
+                self.komi_draws.store(node.get_draws(), Ordering::Relaxed);
               }
               UctKomiType::None => {}
             }
@@ -343,14 +886,13 @@ impl UctRoot {
           self.init(field, player);
           break;
         }
-        let mut next = self.node.as_ref().unwrap().get_child();
-        while next.is_some() && next.as_ref().unwrap().pos != next_pos {
-          next = next.unwrap().get_sibling();
-        }
-        if let Some(ref mut node) = next {
-          let pos = node.get_pos();
-          debug!("Node found for move ({}, {}).", field.to_x(pos), field.to_y(pos));
-          node.clear_sibling();
+        let next = self.root().and_then(|node| self.arena.find_child(node, next_pos));
+        if next.is_some() {
+          debug!(
+            "Node found for move ({}, {}).",
+            field.to_x(next_pos),
+            field.to_y(next_pos)
+          );
         } else {
           self.clear();
           self.init(field, player);
@@ -367,9 +909,12 @@ impl UctRoot {
     }
   }
 
-  pub fn new(config: UctConfig, length: Pos) -> UctRoot {
+  /// Creates UCT with the given playout and prior policies.
+  pub fn with_policies(config: UctConfig, length: Pos, policy: P, prior: Q) -> Self {
+    let capacity = config.memory * 1024 * 1024 / mem::size_of::<UctNode>();
     UctRoot {
       config,
+      arena: UctArena::new(capacity),
       node: None,
       player: Player::Red,
       moves_count: 0,
@@ -379,39 +924,21 @@ impl UctRoot {
       komi_visits: AtomicUsize::new(0),
       komi_wins: AtomicUsize::new(0),
       komi_draws: AtomicUsize::new(0),
+      policy,
+      prior,
+      forbidden_moves: Vec::new(),
     }
   }
 
-  fn random_result(field: &Field, player: Player, komi: i32) -> Option<Player> {
+  fn random_result(score: i32, player: Player, komi: i32) -> Option<Player> {
     use std::cmp::Ordering;
-    let red_komi = if player == Player::Red { komi } else { -komi };
-    let red_score = field.score(Player::Red);
-    match red_score.cmp(&red_komi) {
-      Ordering::Greater => Some(Player::Red),
-      Ordering::Less => Some(Player::Black),
+    match score.cmp(&komi) {
+      Ordering::Greater => Some(player),
+      Ordering::Less => Some(player.next()),
       Ordering::Equal => None,
     }
   }
 
-  fn play_random_game<R: Rng>(
-    field: &mut Field,
-    player: Player,
-    rng: &mut R,
-    possible_moves: &mut Vec<Pos>,
-    komi: i32,
-  ) -> Option<Player> {
-    possible_moves.shuffle(rng);
-    let mut cur_player = player;
-    for &pos in possible_moves.iter() {
-      let cell = field.cell(pos);
-      if cell.is_putting_allowed() && !cell.is_empty_base() {
-        field.put_point(pos, cur_player);
-        cur_player = cur_player.next();
-      }
-    }
-    UctRoot::random_result(field, player, komi)
-  }
-
   fn ucb(&self, parent_visits_ln: f64, node: &UctNode, ucb_type: UcbType) -> f64 {
     let wins = node.get_wins() as f64;
     let draws = node.get_draws() as f64;
@@ -425,52 +952,299 @@ impl UctRoot {
           + (2.0 * parent_visits_ln / visits).sqrt();
         self.config.uctk * (v.min(0.25) * parent_visits_ln / visits).sqrt()
       }
+      UcbType::Rave => {
+        let beta = (self.config.rave_equivalence / (3.0 * visits + self.config.rave_equivalence)).sqrt();
+        beta * (self.amaf_win_rate(node, win_rate) - win_rate) + self.ucb(parent_visits_ln, node, UcbType::Ucb1Tuned)
+          - win_rate
+      }
     };
     win_rate + uct
   }
 
-  fn create_children<R: Rng>(field: &Field, possible_moves: &mut Vec<Pos>, node: &UctNode, rng: &mut R) {
+  /// Utility of the expected score of the node compared to the expected score
+  /// of its parent from the same point of view.
+  fn score_utility(&self, node: &UctNode, parent_score: f64) -> f64 {
+    if self.config.score_weight == 0.0 {
+      return 0.0;
+    }
+    self.config.score_weight * FRAC_2_PI * ((node.get_mean_score() - parent_score) / self.config.score_scale).atan()
+  }
+
+  /// All-moves-as-first win rate of the node, or `win_rate` if there are no
+  /// AMAF statistics yet.
+  fn amaf_win_rate(&self, node: &UctNode, win_rate: f64) -> f64 {
+    let visits = node.get_amaf_visits();
+    if visits == 0 {
+      return win_rate;
+    }
+    (node.get_amaf_wins() as f64 + node.get_amaf_draws() as f64 * self.config.draw_weight) / visits as f64
+  }
+
+  /// Normalized priors of `moves` for `player`.
+  fn priors(&self, field: &Field, player: Player, moves: &[Pos]) -> Option<Vec<f64>> {
+    let priors = self.prior.priors(field, player, moves)?;
+    let sum = priors.iter().sum::<f64>();
+    if sum > 0.0 {
+      Some(priors.into_iter().map(|prior| prior / sum).collect())
+    } else {
+      None
+    }
+  }
+
+  /// Creates children of the node ordered by their priors. Forbidden moves
+  /// are skipped unless there are no other moves. Returns `false` if there is
+  /// no space for them in the arena.
+  fn create_children<R: Rng>(
+    &self,
+    field: &Field,
+    player: Player,
+    possible_moves: &mut [Pos],
+    forbidden_moves: &[Pos],
+    node: &UctNode,
+    rng: &mut R,
+  ) -> bool {
     possible_moves.shuffle(rng);
-    let mut children = None;
-    for &pos in possible_moves.iter() {
-      if field.cell(pos).is_putting_allowed() {
-        let mut cur_child = Box::new(UctNode::new(pos));
-        cur_child.set_sibling_option(children);
-        children = Some(cur_child);
+    let mut moves = possible_moves
+      .iter()
+      .rev()
+      .copied()
+      .filter(|&pos| field.cell(pos).is_putting_allowed())
+      .collect::<Vec<_>>();
+    if moves.is_empty() {
+      return true;
+    }
+    if moves.iter().any(|pos| !forbidden_moves.contains(pos)) {
+      moves.retain(|pos| !forbidden_moves.contains(pos));
+    }
+    let priors = self.priors(field, player, &moves).map(|priors| {
+      let mut priors = moves.iter().copied().zip(priors).collect::<Vec<_>>();
+      priors.sort_by(|(_, prior1), (_, prior2)| prior2.total_cmp(prior1));
+      moves = priors.iter().map(|&(pos, _)| pos).collect();
+      priors
+    });
+    if let Some(child) = self.arena.alloc(&moves) {
+      if let Some(priors) = priors {
+        for (i, (_, prior)) in priors.into_iter().enumerate() {
+          self.arena.get(child + i as NodeIndex).set_prior(prior);
+        }
       }
+      self.arena.set_child(node, child);
+      true
+    } else {
+      false
+    }
+  }
+
+  /// Sets priors of the root children and reorders them accordingly, since
+  /// children added between moves are appended to the end.
+  fn prioritize_root(&self, field: &Field, player: Player) {
+    let Some(root) = self.root() else {
+      return;
+    };
+    let mut indices = Vec::new();
+    let mut index = root.child.load(Ordering::Relaxed);
+    while index != NO_NODE {
+      indices.push(index);
+      index = self.arena.get(index).sibling.load(Ordering::Relaxed);
     }
-    if let Some(child) = children {
-      node.set_child(child)
+    let moves = indices
+      .iter()
+      .map(|&index| self.arena.get(index).get_pos())
+      .collect::<Vec<_>>();
+    let Some(priors) = self.priors(field, player, &moves) else {
+      return;
+    };
+    let mut children = indices.into_iter().zip(priors).collect::<Vec<_>>();
+    children.sort_by(|(_, prior1), (_, prior2)| prior2.total_cmp(prior1));
+    let mut next = NO_NODE;
+    for &(index, prior) in children.iter().rev() {
+      let child = self.arena.get(index);
+      child.set_prior(prior);
+      child.sibling.store(next, Ordering::Relaxed);
+      next = index;
     }
+    root.child.store(next, Ordering::Relaxed);
   }
 
-  fn uct_select<'a>(&self, node: &'a UctNode) -> Option<&'a UctNode> {
-    let node_visits_ln = (node.get_visits() as f64).ln();
-    let mut best_uct = 0f64;
-    let mut result = None;
-    let mut next = node.get_child_ref();
-    while let Some(next_node) = next {
-      let visits = next_node.get_visits();
-      let wins = next_node.get_wins();
-      let uct_value = if visits == usize::max_value() {
-        if wins == usize::max_value() {
-          return Some(next_node);
-        }
-        -1f64
-      } else if visits == 0 {
-        self.config.fpu
+  /// Finds root moves that start or extend a ladder losing for the player and
+  /// marks existing children with them as lost, unless all of them would be.
+  fn forbid_losing_ladders<SS: Fn() -> bool>(&mut self, field: &Field, player: Player, should_stop: &SS) {
+    self.forbidden_moves = losing_ladder_moves(&mut field.clone(), player, should_stop);
+    let Some(root) = self.root() else {
+      return;
+    };
+    let forbidden = |child: &UctNode| self.forbidden_moves.contains(&child.get_pos());
+    if self.arena.children(root).all(forbidden) {
+      return;
+    }
+    for child in self.arena.children(root).filter(|&child| forbidden(child)) {
+      child.lose_node();
+    }
+  }
+
+  /// Number of children available for selection in a node with `visits`.
+  fn widening(&self, visits: usize) -> usize {
+    if self.config.widening_base == 0.0 {
+      usize::MAX
+    } else {
+      (self.config.widening_base * (visits as f64).powf(self.config.widening_exponent))
+        .ceil()
+        .max(1.0) as usize
+    }
+  }
+
+  /// PUCT term of the node, which decreases with its visits.
+  fn prior_term(&self, node: &UctNode, parent_visits_sqrt: f64, visits: usize) -> f64 {
+    self.config.prior_weight * node.get_prior() * parent_visits_sqrt / (visits + 1) as f64
+  }
+
+  /// Virtual loss applied on descent, which is needed only when threads
+  /// share paths.
+  fn virtual_loss(&self) -> usize {
+    if self.config.threads_count > 1 && !self.config.deterministic {
+      self.config.virtual_loss
+    } else {
+      0
+    }
+  }
+
+  /// Creates children of a leaf or proves it if the game is over. Returns
+  /// `false` if the node has to stay a leaf because the arena is full.
+  fn expand_leaf<R: Rng>(
+    &self,
+    field: &mut Field,
+    player: Player,
+    node: &UctNode,
+    possible_moves: &mut [Pos],
+    rng: &mut R,
+    depth: u32,
+  ) -> bool {
+    if self.arena.child(node).is_some() || node.get_proof().is_some() {
+      true
+    } else if depth > 0 && field.is_game_over() {
+      node.set_proof(Proof::from_score(field.score(player.next())));
+      true
+    } else {
+      let forbidden_moves = if depth == 0 {
+        self.forbidden_moves.as_slice()
       } else {
-        self.ucb(node_visits_ln, next_node, self.config.ucb_type)
+        &[]
       };
+      self.create_children(field, player, possible_moves, forbidden_moves, node, rng)
+    }
+  }
+
+  /// Value of the child for selection given the parent visits and score.
+  fn selection_value(&self, node: &UctNode, parent_visits_ln: f64, parent_visits_sqrt: f64, parent_score: f64) -> f64 {
+    let visits = node.get_visits();
+    match node.get_proof() {
+      Some(Proof::Win) => 1f64,
+      Some(Proof::Loss) => -1f64,
+      Some(Proof::Draw) => self.config.draw_weight,
+      None if visits == usize::MAX => -1f64,
+      None if visits == 0 => self.config.fpu + self.prior_term(node, parent_visits_sqrt, visits),
+      None => {
+        self.ucb(parent_visits_ln, node, self.config.ucb_type)
+          + self.score_utility(node, parent_score)
+          + self.prior_term(node, parent_visits_sqrt, visits)
+      }
+    }
+  }
+
+  fn node_info(&self, node: &UctNode, ucb: f64, depth: u32) -> UctNodeInfo {
+    let visits = node.get_visits();
+    let lost = visits == usize::MAX;
+    let children = if depth > 0 {
+      let visits_ln = (visits as f64).ln();
+      let visits_sqrt = (visits as f64).sqrt();
+      let score = -node.get_mean_score();
+      self
+        .arena
+        .children(node)
+        .map(|child| {
+          let ucb = self.selection_value(child, visits_ln, visits_sqrt, score);
+          self.node_info(child, ucb, depth - 1)
+        })
+        .collect()
+    } else {
+      Vec::new()
+    };
+    UctNodeInfo {
+      pos: node.get_pos(),
+      visits: if lost { 0 } else { visits },
+      wins: node.get_wins(),
+      draws: node.get_draws(),
+      winrate: if lost || visits == 0 {
+        0.0
+      } else {
+        self.ucb(0.0, node, UcbType::Winrate)
+      },
+      ucb,
+      prior: node.get_prior(),
+      score: if lost || visits == 0 {
+        0.0
+      } else {
+        node.get_mean_score()
+      },
+      amaf_visits: node.get_amaf_visits(),
+      // Stupid moves are considered lost.
+      proof: if lost { Some(Proof::Loss) } else { node.get_proof() },
+      children,
+    }
+  }
+
+  /// Returns statistics of the tree up to `depth` moves from the root.
+  pub fn inspect(&self, depth: u32) -> Option<UctTreeInfo> {
+    let root = self.root()?;
+    Some(UctTreeInfo {
+      player: self.player,
+      moves_count: self.moves_count,
+      komi: self.komi.load(Ordering::Relaxed),
+      root: self.node_info(root, 0.0, depth),
+    })
+  }
+
+  /// Selects the best child. If `child` index is specified, that child is
+  /// selected unless it's lost. `virtual_loss` is the number of visits that the
+  /// calling thread added to the node, which don't count for its children.
+  fn uct_select(&self, node: &UctNode, child: Option<usize>, virtual_loss: usize) -> Option<&UctNode> {
+    if let Some(child) = child {
+      return self
+        .arena
+        .children(node)
+        .nth(child)
+        .filter(|next_node| next_node.get_visits() != usize::MAX);
+    }
+    let node_visits = node.get_visits().saturating_sub(virtual_loss);
+    let node_score = -node.get_mean_score();
+    let node_visits_ln = (node_visits as f64).ln();
+    let node_visits_sqrt = (node_visits as f64).sqrt();
+    let widening = self.widening(node_visits);
+    let mut available = 0;
+    let mut best_uct = 0f64;
+    let mut result = None;
+    for next_node in self.arena.children(node) {
+      if available == widening {
+        break;
+      }
+      // Stupid moves don't take places of available children.
+      if next_node.get_visits() != usize::MAX {
+        available += 1;
+      }
+      if next_node.get_proof() == Some(Proof::Win) {
+        return Some(next_node);
+      }
+      let uct_value = self.selection_value(next_node, node_visits_ln, node_visits_sqrt, node_score);
       if uct_value > best_uct {
         best_uct = uct_value;
         result = Some(next_node);
       }
-      next = next_node.get_sibling_ref();
     }
     result
   }
 
+  /// Plays a simulation through the node. `virtual_loss` is the number of
+  /// visits that this thread added to the node on descent.
   fn play_simulation_rec<R: Rng>(
     &self,
     field: &mut Field,
@@ -480,20 +1254,33 @@ impl UctRoot {
     rng: &mut R,
     komi: i32,
     depth: u32,
-  ) -> Option<Player> {
-    let random_result = if node.get_visits() < self.config.when_create_children || depth == self.config.depth {
-      UctRoot::play_random_game(field, player, rng, possible_moves, komi)
+    child: Option<usize>,
+    virtual_loss: usize,
+  ) -> (Option<Player>, i32) {
+    let (random_result, score) = if child.is_none() && node.get_real_visits() < self.config.when_create_children
+      || depth == self.config.depth
+      || !self.expand_leaf(field, player, node, possible_moves, rng, depth)
+    {
+      let score = self.policy.play(field, player, possible_moves, rng);
+      (Self::random_result(score, player, komi), score)
     } else {
-      if node.get_child_ref().is_none() {
-        UctRoot::create_children(field, possible_moves, node, rng)
-      }
-      if let Some(next) = self.uct_select(node) {
+      if let Some(next) = self.uct_select(node, child, virtual_loss) {
         let pos = next.get_pos();
         field.put_point(pos, player);
         if common::is_last_move_stupid(field, pos, player) {
           field.undo();
           next.lose_node();
-          return self.play_simulation_rec(field, player, node, possible_moves, rng, komi, depth);
+          return self.play_simulation_rec(
+            field,
+            player,
+            node,
+            possible_moves,
+            rng,
+            komi,
+            depth,
+            child,
+            virtual_loss,
+          );
         }
         if common::is_penult_move_stupid(field) {
           // Theoretically, visits in this node may be overflowed by another thread, but
@@ -501,12 +1288,38 @@ impl UctRoot {
           // marked as losing on the next visit
           // because uct_select method selects
           // child determined.
-          node.lose_node();
-          return Some(player);
+          // Deterministic simulations share the root, so it's left as is.
+          if child.is_none() {
+            node.lose_node();
+          }
+          return (Some(player), field.score(player));
+        }
+        let next_virtual_loss = self.virtual_loss();
+        if next_virtual_loss > 0 {
+          next.add_virtual_loss(next_virtual_loss);
         }
-        self.play_simulation_rec(field, player.next(), next, possible_moves, rng, -komi, depth + 1)
+        let (result, score) = self.play_simulation_rec(
+          field,
+          player.next(),
+          next,
+          possible_moves,
+          rng,
+          -komi,
+          depth + 1,
+          None,
+          next_virtual_loss,
+        );
+        if next_virtual_loss > 0 {
+          next.revert_virtual_loss(next_virtual_loss);
+        }
+        // Deterministic simulations share the root, so it's proven between rounds.
+        if next.get_proof().is_some() && child.is_none() {
+          self.update_proof(node);
+        }
+        (result, -score)
       } else {
-        UctRoot::random_result(field, player, komi)
+        let score = field.score(player);
+        (Self::random_result(score, player, komi), score)
       }
     };
     if let Some(player_random_result) = random_result {
@@ -518,7 +1331,8 @@ impl UctRoot {
     } else {
       node.add_draw();
     }
-    random_result
+    node.add_score(-score);
+    (random_result, score)
   }
 
   fn play_simulation<R: Rng>(
@@ -529,8 +1343,8 @@ impl UctRoot {
     rng: &mut R,
     ratched: &AtomicIsize,
   ) {
-    if let Some(node) = self.node.as_ref() {
-      self.play_simulation_rec(
+    if let Some(node) = self.root() {
+      let (result, _) = self.play_simulation_rec(
         field,
         player,
         node,
@@ -538,51 +1352,217 @@ impl UctRoot {
         rng,
         self.komi.load(Ordering::Relaxed) as i32,
         0,
+        None,
+        0,
       );
+      if self.config.ucb_type == UcbType::Rave {
+        self.update_amaf(field, player, node, result);
+      }
       if self.config.komi_type == UctKomiType::Dynamic {
-        let visits = node.get_visits();
-        let komi_visits = self.komi_visits.load(Ordering::Relaxed);
-        let delta_visits = visits - komi_visits;
-        if delta_visits > self.config.komi_min_iterations {
-          let wins = node.get_wins();
-          let delta_wins = wins - self.komi_wins.load(Ordering::Relaxed);
-          let draws = node.get_draws();
-          let delta_draws = draws - self.komi_draws.load(Ordering::Relaxed);
-          let win_rate =
-            1f64 - (delta_wins as f64 + delta_draws as f64 * self.config.draw_weight) / delta_visits as f64;
-          let komi = self.komi.load(Ordering::Relaxed);
-          if win_rate < self.config.red || win_rate > self.config.green && komi < ratched.load(Ordering::Relaxed) {
-            let result = self
-              .komi_visits
-              .compare_exchange(komi_visits, visits, Ordering::Relaxed, Ordering::Relaxed);
-            if result.is_ok() {
-              self.komi_wins.store(wins, Ordering::Relaxed);
-              self.komi_draws.store(draws, Ordering::Relaxed);
-              if win_rate < self.config.red {
-                if komi > 0 {
-                  ratched.store(komi - 1, Ordering::Relaxed);
+        self.update_komi(node, ratched);
+      }
+    }
+  }
+
+  /// Updates all-moves-as-first statistics along the simulated path: every
+  /// child of a node on the path gets the simulation result if its move was
+  /// played later by the same player, either in the tree or in the random game.
+  fn update_amaf(&self, field: &Field, player: Player, root: &UctNode, result: Option<Player>) {
+    let moves = &field.moves()[self.moves_count..];
+    let mut played = vec![usize::MAX; field.length()];
+    for (i, &pos) in moves.iter().enumerate() {
+      played[pos] = i;
+    }
+    let mut node = root;
+    let mut cur_player = player;
+    for (depth, &pos) in moves.iter().enumerate() {
+      for child in self.arena.children(node) {
+        let i = played[child.get_pos()];
+        if i != usize::MAX && i >= depth && (i - depth).is_multiple_of(2) {
+          match result {
+            Some(winner) if winner == cur_player => child.add_amaf_win(),
+            Some(_) => child.add_amaf_loose(),
+            None => child.add_amaf_draw(),
+          }
+        }
+      }
+      let Some(child) = self.arena.children(node).find(|child| child.get_pos() == pos) else {
+        break;
+      };
+      node = child;
+      cur_player = cur_player.next();
+    }
+  }
+
+  fn update_komi(&self, node: &UctNode, ratched: &AtomicIsize) {
+    let visits = node.get_visits();
+    let komi_visits = self.komi_visits.load(Ordering::Relaxed);
+    let delta_visits = visits - komi_visits;
+    if delta_visits > self.config.komi_min_iterations {
+      let wins = node.get_wins();
+      let delta_wins = wins - self.komi_wins.load(Ordering::Relaxed);
+      let draws = node.get_draws();
+      let delta_draws = draws - self.komi_draws.load(Ordering::Relaxed);
+      let win_rate = 1f64 - (delta_wins as f64 + delta_draws as f64 * self.config.draw_weight) / delta_visits as f64;
+      let komi = self.komi.load(Ordering::Relaxed);
+      if win_rate < self.config.red || win_rate > self.config.green && komi < ratched.load(Ordering::Relaxed) {
+        let result = self
+          .komi_visits
+          .compare_exchange(komi_visits, visits, Ordering::Relaxed, Ordering::Relaxed);
+        if result.is_ok() {
+          self.komi_wins.store(wins, Ordering::Relaxed);
+          self.komi_draws.store(draws, Ordering::Relaxed);
+          if win_rate < self.config.red {
+            if komi > 0 {
+              ratched.store(komi - 1, Ordering::Relaxed);
+            }
+            self.komi.fetch_sub(1, Ordering::Relaxed);
+            info!(
+              "Komi decreased after {} visits: {}. Winrate is {}.",
+              visits,
+              komi - 1,
+              win_rate
+            );
+          } else {
+            self.komi.fetch_add(1, Ordering::Relaxed);
+            info!(
+              "Komi increased after {} visits: {}. Winrate is {}.",
+              visits,
+              komi + 1,
+              win_rate
+            );
+          }
+        }
+      }
+    }
+  }
+
+  /// Plays simulations so that the result depends only on the rng and the
+  /// iterations count, but not on the threads count or their scheduling. Each
+  /// round root moves of a fixed number of simulations are chosen one by one
+  /// with virtual losses for the already chosen ones. Then simulations through
+  /// each chosen root child are played as a separate task with its own rng.
+  /// Tasks never share nodes below the root, and dynamic komi and proofs of
+  /// the root are updated between rounds.
+  #[cfg(not(target_arch = "wasm32"))]
+  fn play_deterministic_simulations<S, R, SS>(
+    &mut self,
+    field: &Field,
+    player: Player,
+    rng: &mut R,
+    should_stop: &SS,
+    max_iterations_count: usize,
+    ratched: &AtomicIsize,
+  ) -> usize
+  where
+    R: Rng + SeedableRng<Seed = S> + Send,
+    Standard: Distribution<S>,
+    SS: Fn() -> bool + Sync,
+  {
+    let Some(node) = self.root() else {
+      return 0;
+    };
+    if self.arena.child(node).is_none() {
+      self.create_children(
+        field,
+        player,
+        &mut self.wave_pruning.moves().clone(),
+        &self.forbidden_moves,
+        node,
+        rng,
+      );
+    }
+    // A round expands at most one leaf per simulation. Compacting before the
+    // arena can get full keeps allocations independent of the scheduling.
+    let reserve = DETERMINISTIC_ROUND_SIZE * self.wave_pruning.moves().len();
+    let mut compaction = true;
+    let mut iterations = 0;
+    while iterations < max_iterations_count && !should_stop() {
+      if compaction && self.is_full(reserve) {
+        compaction = self.compact_full();
+      }
+      let node = self.root().unwrap();
+      let round = DETERMINISTIC_ROUND_SIZE.min(max_iterations_count - iterations);
+      let mut counts = vec![0; self.arena.children(node).count()];
+      let mut planned = 0;
+      while planned < round {
+        let Some(next) = self.uct_select(node, None, 0) else {
+          break;
+        };
+        let index = self
+          .arena
+          .children(node)
+          .position(|child| ptr::eq(child, next))
+          .unwrap();
+        node.add_virtual_loss(1);
+        next.add_virtual_loss(1);
+        counts[index] += 1;
+        planned += 1;
+      }
+      node.revert_virtual_loss(planned);
+      for (next, &count) in self.arena.children(node).zip(counts.iter()) {
+        next.revert_virtual_loss(count);
+      }
+      if planned == 0 {
+        break;
+      }
+      let threads_count = self.config.threads_count.max(1);
+      let mut tasks = (0..threads_count).map(|_| Vec::new()).collect::<Vec<_>>();
+      for (i, (index, &count)) in counts.iter().enumerate().filter(|&(_, &count)| count > 0).enumerate() {
+        tasks[i % threads_count].push((index, count, R::from_seed(rng.gen())));
+      }
+      let komi = self.komi.load(Ordering::Relaxed) as i32;
+      let round_iterations = AtomicUsize::new(0);
+      let uct = &*self;
+      crossbeam::scope(|scope| {
+        for thread_tasks in tasks.into_iter().filter(|thread_tasks| !thread_tasks.is_empty()) {
+          let round_iterations = &round_iterations;
+          scope.spawn(move |_| {
+            let mut local_field = field.clone();
+            for (index, count, mut local_rng) in thread_tasks {
+              // Moves are shuffled in place, so each task starts from the same order.
+              let mut possible_moves = uct.wave_pruning.moves().clone();
+              for _ in 0..count {
+                if should_stop() {
+                  break;
                 }
-                self.komi.fetch_sub(1, Ordering::Relaxed);
-                info!(
-                  "Komi decreased after {} visits: {}. Winrate is {}.",
-                  visits,
-                  komi - 1,
-                  win_rate
-                );
-              } else {
-                self.komi.fetch_add(1, Ordering::Relaxed);
-                info!(
-                  "Komi increased after {} visits: {}. Winrate is {}.",
-                  visits,
-                  komi + 1,
-                  win_rate
+                let (result, _) = uct.play_simulation_rec(
+                  &mut local_field,
+                  player,
+                  node,
+                  &mut possible_moves,
+                  &mut local_rng,
+                  komi,
+                  0,
+                  Some(index),
+                  0,
                 );
+                if uct.config.ucb_type == UcbType::Rave {
+                  // AMAF statistics of root children are shared between tasks, but
+                  // they are only read when the next round is planned.
+                  uct.update_amaf(&local_field, player, node, result);
+                }
+                for _ in 0..local_field.moves_count() - uct.moves_count {
+                  local_field.undo();
+                }
+                round_iterations.fetch_add(1, Ordering::Relaxed);
               }
             }
-          }
+          });
         }
+      })
+      .expect("UCT play_deterministic_simulations panic");
+      iterations += round_iterations.load(Ordering::Relaxed);
+      if self.config.komi_type == UctKomiType::Dynamic {
+        self.update_komi(node, ratched);
+      }
+      self.update_proof(node);
+      if node.get_proof().is_some() {
+        break;
       }
     }
+    info!("Iterations count: {}.", iterations);
+    iterations
   }
 
   pub fn best_moves<S, R, SS>(
@@ -609,33 +1589,58 @@ impl UctRoot {
     );
     debug!("Next random u64: {}.", rng.gen::<u64>());
     self.update(field, player, rng);
+    if self.config.forbid_losing_ladders {
+      self.forbid_losing_ladders(field, player, should_stop);
+    }
     info!(
       "Komi is {}, type is {:?}.",
       self.komi.load(Ordering::Relaxed),
       self.config.komi_type
     );
-    let ratched = AtomicIsize::new(isize::max_value());
+    let ratched = AtomicIsize::new(isize::MAX);
     #[cfg(not(target_arch = "wasm32"))]
-    let iterations = {
+    let iterations = if self.config.deterministic {
+      self.play_deterministic_simulations(field, player, rng, should_stop, max_iterations_count, &ratched)
+    } else {
+      // Every thread might be expanding a leaf when the arena gets full.
+      let reserve = self.wave_pruning.moves().len() * self.config.threads_count;
+      let mut compaction = true;
       let iterations = AtomicUsize::new(0);
-      crossbeam::scope(|scope| {
-        for _ in 0..self.config.threads_count {
-          let new_rng = R::from_seed(rng.gen());
-          scope.spawn(|_| {
-            let mut local_field = field.clone();
-            let mut local_rng = new_rng;
-            let mut possible_moves = self.wave_pruning.moves().clone();
-            while !should_stop() && iterations.load(Ordering::Relaxed) < max_iterations_count {
-              self.play_simulation(&mut local_field, player, &mut possible_moves, &mut local_rng, &ratched);
-              for _ in 0..local_field.moves_count() - self.moves_count {
-                local_field.undo();
+      loop {
+        let needs_compaction = || compaction && self.is_full(reserve);
+        let is_running = || {
+          !should_stop()
+            && iterations.load(Ordering::Relaxed) < max_iterations_count
+            && !self.is_proven()
+            && !needs_compaction()
+        };
+        crossbeam::scope(|scope| {
+          for _ in 0..self.config.threads_count {
+            let new_rng = R::from_seed(rng.gen());
+            scope.spawn(|_| {
+              let mut local_field = field.clone();
+              let mut local_rng = new_rng;
+              let mut possible_moves = self.wave_pruning.moves().clone();
+              while is_running() {
+                self.play_simulation(&mut local_field, player, &mut possible_moves, &mut local_rng, &ratched);
+                for _ in 0..local_field.moves_count() - self.moves_count {
+                  local_field.undo();
+                }
+                iterations.fetch_add(1, Ordering::Relaxed);
               }
-              iterations.fetch_add(1, Ordering::Relaxed);
-            }
-          });
+            });
+          }
+        })
+        .expect("UCT best_move_generic panic");
+        if !needs_compaction()
+          || should_stop()
+          || iterations.load(Ordering::Relaxed) >= max_iterations_count
+          || self.is_proven()
+        {
+          break;
         }
-      })
-      .expect("UCT best_move_generic panic");
+        compaction = self.compact_full();
+      }
       info!("Iterations count: {}.", iterations.load(Ordering::Relaxed));
       iterations.load(Ordering::Relaxed)
     };
@@ -644,7 +1649,11 @@ impl UctRoot {
       let mut iterations = 0;
       let mut local_field = field.clone();
       let mut possible_moves = self.wave_pruning.moves().clone();
-      while !should_stop() && iterations < max_iterations_count {
+      let mut compaction = true;
+      while !should_stop() && iterations < max_iterations_count && !self.is_proven() {
+        if compaction && self.is_full(possible_moves.len()) {
+          compaction = self.compact_full();
+        }
         self.play_simulation(&mut local_field, player, &mut possible_moves, rng, &ratched);
         for _ in 0..local_field.moves_count() - self.moves_count {
           local_field.undo();
@@ -655,32 +1664,67 @@ impl UctRoot {
       iterations
     };
     let mut moves = Vec::new();
-    let winrate = if let Some(ref root) = self.node {
-      let mut next = root.get_child_ref();
+    let winrate = if let Some(root) = self.root() {
       let root_visits_ln = (root.get_visits() as f64).ln();
-      while let Some(next_node) = next {
-        let uct_value = if next_node.get_visits() > 0 {
-          self.ucb(root_visits_ln, next_node, UcbType::Winrate)
+      let root_score = -root.get_mean_score();
+      let proof = root.get_proof();
+      if let Some(proof) = proof {
+        info!("Position is proven: {:?} for the previous move.", proof);
+      }
+      for next_node in self.arena.children(root) {
+        let uct_value = if next_node.get_proof() == Some(Proof::Win) {
+          1f64
+        } else if next_node.get_visits() > 0 {
+          self.ucb(root_visits_ln, next_node, UcbType::Winrate) + self.score_utility(next_node, root_score)
         } else {
           0f64
         };
         let pos = next_node.get_pos();
         info!(
-          "Uct for move ({}, {}) is {}, {} wins, {} draws, {} visits.",
+          "Uct for move ({}, {}) is {}, {} wins, {} draws, {} visits, score {} ± {}, proof {:?}.",
           field.to_x(pos),
           field.to_y(pos),
           uct_value,
           next_node.get_wins(),
           next_node.get_draws(),
-          next_node.get_visits()
+          next_node.get_visits(),
+          next_node.get_mean_score(),
+          next_node.get_score_variance().sqrt(),
+          next_node.get_proof()
         );
-        moves.push((pos, uct_value));
-        next = next_node.get_sibling_ref();
+        // Only proven winning moves are suggested when there are any.
+        if proof != Some(Proof::Loss) || next_node.get_proof() == Some(Proof::Win) {
+          moves.push((pos, uct_value));
+        }
+      }
+      match proof {
+        Some(Proof::Win) => 1.0,
+        Some(Proof::Loss) => 0.0,
+        Some(Proof::Draw) => 0.5,
+        None => (root.get_wins() as f64 + root.get_draws() as f64 / 2.0) / root.get_visits() as f64,
       }
-      (root.get_wins() as f64 + root.get_draws() as f64 / 2.0) / root.get_visits() as f64
     } else {
       0.0
     };
+    info!(
+      "Tree has {} nodes of {}, {} bytes are allocated.",
+      self.arena.len(),
+      self.arena.capacity(),
+      self.arena.memory()
+    );
     (moves, iterations, winrate)
   }
 }
+
+impl<P: PlayoutPolicy> UctRoot<P> {
+  /// Creates UCT with the given playout policy.
+  pub fn with_policy(config: UctConfig, length: Pos, policy: P) -> Self {
+    UctRoot::with_policies(config, length, policy, NoPrior)
+  }
+}
+
+impl UctRoot {
+  pub fn new(config: UctConfig, length: Pos) -> Self {
+    UctRoot::with_policy(config, length, UniformPolicy)
+  }
+}
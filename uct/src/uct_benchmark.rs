@@ -1,7 +1,7 @@
 #[macro_use]
 extern crate criterion;
 
-use criterion::{Bencher, Criterion};
+use criterion::{Bencher, BenchmarkId, Criterion};
 use oppai_field::construct_field::construct_field;
 use oppai_field::field;
 use oppai_field::player::Player;
@@ -14,6 +14,8 @@ const SEED: u64 = 7;
 const UCT_CONFIG: UctConfig = UctConfig {
   threads_count: 1,
   radius: 3,
+  empty_radius: 0,
+  recent_radius: 0,
   ucb_type: UcbType::Ucb1Tuned,
   draw_weight: 0.4,
   uctk: 1.0,
@@ -24,9 +26,21 @@ const UCT_CONFIG: UctConfig = UctConfig {
   green: 0.5,
   komi_min_iterations: 3_000,
   fpu: 1.1,
+  rave_equivalence: 300.0,
+  score_weight: 0.0,
+  score_scale: 10.0,
+  prior_weight: 1.0,
+  widening_base: 0.0,
+  widening_exponent: 0.5,
+  memory: 1024,
+  deterministic: false,
+  virtual_loss: 3,
+  forbid_losing_ladders: false,
 };
 
-fn find_best_move(bencher: &mut Bencher) {
+const THREADS_COUNTS: [usize; 7] = [1, 2, 4, 8, 16, 32, 64];
+
+fn find_best_move(bencher: &mut Bencher, config: UctConfig) {
   let mut rng = Xoshiro256PlusPlus::seed_from_u64(SEED);
   let field = construct_field(
     &mut rng,
@@ -43,14 +57,30 @@ fn find_best_move(bencher: &mut Bencher) {
   );
   let length = field::length(field.width(), field.height());
   bencher.iter(|| {
-    let mut uct = UctRoot::new(UCT_CONFIG, length);
+    let mut uct = UctRoot::new(config.clone(), length);
     uct.best_moves(&field, Player::Red, &mut rng.clone(), &|| false, 100_000)
   });
 }
 
 fn uct() {
   let mut c = Criterion::default().sample_size(10).configure_from_args();
-  c.bench_function("uct", find_best_move);
+  c.bench_function("uct", |bencher| find_best_move(bencher, UCT_CONFIG));
+  let mut group = c.benchmark_group("uct_threads");
+  for threads_count in THREADS_COUNTS {
+    for virtual_loss in [0, UCT_CONFIG.virtual_loss] {
+      let config = UctConfig {
+        threads_count,
+        virtual_loss,
+        ..UCT_CONFIG
+      };
+      group.bench_with_input(
+        BenchmarkId::new(format!("virtual_loss_{}", virtual_loss), threads_count),
+        &config,
+        |bencher, config| find_best_move(bencher, config.clone()),
+      );
+    }
+  }
+  group.finish();
 }
 
 criterion_main!(uct);
@@ -1,14 +1,23 @@
-use crate::uct::{UcbType, UctConfig, UctKomiType, UctRoot};
+use crate::playout::{GuidedPolicy, PlayoutPolicy, UniformPolicy};
+use crate::prior::PriorPolicy;
+use crate::uct::{UcbType, UctConfig, UctKomiType, UctNodeInfo, UctRoot};
+use crate::wave_pruning::Region;
 use oppai_field::construct_field::construct_field;
-use oppai_field::field::NonZeroPos;
+use oppai_field::field::{Field, NonZeroPos, Pos};
 use oppai_field::player::Player;
 use oppai_test_images::*;
-use rand::SeedableRng;
+use rand::{Rng, SeedableRng};
 use rand_xoshiro::Xoshiro256PlusPlus;
+use std::sync::{
+  atomic::{AtomicBool, Ordering},
+  Arc,
+};
 
 const UCT_CONFIG: UctConfig = UctConfig {
   threads_count: 1,
   radius: 3,
+  empty_radius: 0,
+  recent_radius: 0,
   ucb_type: UcbType::Ucb1Tuned,
   draw_weight: 0.4,
   uctk: 1.0,
@@ -19,28 +28,75 @@ const UCT_CONFIG: UctConfig = UctConfig {
   green: 0.5,
   komi_min_iterations: 3_000,
   fpu: 1.1,
+  rave_equivalence: 300.0,
+  score_weight: 0.0,
+  score_scale: 10.0,
+  prior_weight: 1.0,
+  widening_base: 0.0,
+  widening_exponent: 0.5,
+  memory: 1024,
+  deterministic: false,
+  virtual_loss: 3,
+  forbid_losing_ladders: false,
+};
+
+const UCT_RAVE_CONFIG: UctConfig = UctConfig {
+  ucb_type: UcbType::Rave,
+  ..UCT_CONFIG
+};
+
+const UCT_SCORE_CONFIG: UctConfig = UctConfig {
+  score_weight: 0.2,
+  ..UCT_CONFIG
+};
+
+const UCT_WIDENING_CONFIG: UctConfig = UctConfig {
+  widening_base: 2.0,
+  ..UCT_CONFIG
+};
+
+const UCT_ADAPTIVE_CONFIG: UctConfig = UctConfig {
+  empty_radius: 1,
+  recent_radius: 0,
+  ..UCT_CONFIG
 };
 
+/// Optional features that should not break the search on tactical positions.
+const UCT_FEATURE_CONFIGS: [UctConfig; 4] = [
+  UCT_RAVE_CONFIG,
+  UCT_SCORE_CONFIG,
+  UCT_WIDENING_CONFIG,
+  UCT_ADAPTIVE_CONFIG,
+];
+
 macro_rules! uct_test {
   ($(#[$($attr:meta),+])* $name:ident, $image:ident, $iterations:expr, $seed:expr) => {
+    uct_test!($(#[$($attr),+])* $name, UniformPolicy, $image, $iterations, $seed);
+  };
+  ($(#[$($attr:meta),+])* $name:ident, $policy:expr, $image:ident, $iterations:expr, $seed:expr) => {
+    uct_test!($(#[$($attr),+])* $name, [UCT_CONFIG], $policy, $image, $iterations, $seed);
+  };
+  ($(#[$($attr:meta),+])* $name:ident, $configs:expr, $policy:expr, $image:ident, $iterations:expr, $seed:expr) => {
     #[test]
     $(#[$($attr),+])*
     fn $name() {
       env_logger::try_init().ok();
-      let mut rng = Xoshiro256PlusPlus::seed_from_u64($seed);
-      let field = construct_field(&mut rng, $image.image);
-      let mut uct = UctRoot::new(UCT_CONFIG, field.length());
-      let (moves, _, _) = uct.best_moves(&field, Player::Red, &mut rng, &|| false, $iterations);
-      let pos = moves.into_iter()
-      .reduce(
-        |(pos1, value1), (pos2, value2)| if value1 >= value2 {
-          (pos1, value1)
-        } else {
-          (pos2, value2)
-        }
-      )
-      .and_then(|(pos, _)| NonZeroPos::new(pos));
-      assert_eq!(pos, NonZeroPos::new(field.to_pos($image.solution.0, $image.solution.1)));
+      for config in $configs {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64($seed);
+        let field = construct_field(&mut rng, $image.image);
+        let mut uct = UctRoot::with_policy(config.clone(), field.length(), $policy);
+        let (moves, _, _) = uct.best_moves(&field, Player::Red, &mut rng, &|| false, $iterations);
+        let pos = moves.into_iter()
+        .reduce(
+          |(pos1, value1), (pos2, value2)| if value1 >= value2 {
+            (pos1, value1)
+          } else {
+            (pos2, value2)
+          }
+        )
+        .and_then(|(pos, _)| NonZeroPos::new(pos));
+        assert_eq!(pos, NonZeroPos::new(field.to_pos($image.solution.0, $image.solution.1)), "{:?}", config);
+      }
     }
   }
 }
@@ -78,3 +134,469 @@ uct_test!(
 uct_test!(uct_12, IMAGE_12, 100_000, 7);
 uct_test!(uct_13, IMAGE_13, 100_000, 7);
 uct_test!(uct_14, IMAGE_14, 100_000, 13);
+
+uct_test!(uct_guided_5, GuidedPolicy::default(), IMAGE_5, 100_000, 7);
+uct_test!(uct_guided_2, GuidedPolicy::default(), IMAGE_2, 100_000, 7);
+uct_test!(uct_guided_3, GuidedPolicy::default(), IMAGE_3, 100_000, 7);
+uct_test!(uct_guided_4, GuidedPolicy::default(), IMAGE_4, 100_000, 7);
+uct_test!(uct_features_2, UCT_FEATURE_CONFIGS, UniformPolicy, IMAGE_2, 100_000, 7);
+uct_test!(
+  uct_features_12,
+  UCT_FEATURE_CONFIGS,
+  UniformPolicy,
+  IMAGE_12,
+  100_000,
+  7
+);
+uct_test!(
+  uct_features_13,
+  UCT_FEATURE_CONFIGS,
+  UniformPolicy,
+  IMAGE_13,
+  100_000,
+  7
+);
+
+#[test]
+fn uct_deterministic() {
+  env_logger::try_init().ok();
+  let config = UctConfig {
+    threads_count: 4,
+    deterministic: true,
+    ..UCT_CONFIG
+  };
+  let best_moves = || {
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+    let field = construct_field(&mut rng, IMAGE_1.image);
+    let mut uct = UctRoot::new(config.clone(), field.length());
+    uct.best_moves(&field, Player::Red, &mut rng, &|| false, 20_000)
+  };
+  assert_eq!(best_moves(), best_moves());
+}
+
+#[test]
+fn uct_deterministic_threads() {
+  env_logger::try_init().ok();
+  let best_moves = |threads_count| {
+    let config = UctConfig {
+      threads_count,
+      deterministic: true,
+      ..UCT_CONFIG
+    };
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(5);
+    let field = construct_field(&mut rng, IMAGE_1.image);
+    let mut uct = UctRoot::new(config, field.length());
+    let result = uct.best_moves(&field, Player::Red, &mut rng, &|| false, 100_000);
+    let best_move = result
+      .0
+      .iter()
+      .max_by(|(_, a), (_, b)| a.total_cmp(b))
+      .map(|&(pos, _)| (field.to_x(pos), field.to_y(pos)));
+    (result, best_move)
+  };
+  let (result, best_move) = best_moves(1);
+  assert_eq!(best_move, Some(IMAGE_1.solution));
+  assert_eq!(best_moves(4).0, result);
+}
+
+#[test]
+fn uct_solver() {
+  env_logger::try_init().ok();
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+  let field = construct_field(
+    &mut rng,
+    "
+    .a.a...
+    aAaA...
+    .......
+    .......
+    ",
+  );
+  let mut uct = UctRoot::new(UCT_CONFIG, field.length());
+  let (moves, iterations, estimation) = uct.best_moves(&field, Player::Red, &mut rng, &|| false, 100_000);
+  assert_eq!(moves.len(), 1);
+  assert_eq!(moves[0].0, field.to_pos(1, 2));
+  assert!(iterations < 100_000);
+  assert_eq!(estimation, 0.0);
+}
+
+#[test]
+fn uct_memory_limit() {
+  env_logger::try_init().ok();
+  let config = UctConfig {
+    memory: 1,
+    ..UCT_CONFIG
+  };
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+  let field = construct_field(&mut rng, IMAGE_2.image);
+  let mut uct = UctRoot::new(config, field.length());
+  let (moves, iterations, _) = uct.best_moves(&field, Player::Red, &mut rng, &|| false, 100_000);
+  assert_eq!(iterations, 100_000);
+  assert!(uct.memory_usage() <= 1024 * 1024);
+  let pos = moves
+    .into_iter()
+    .reduce(|(pos1, value1), (pos2, value2)| {
+      if value1 >= value2 {
+        (pos1, value1)
+      } else {
+        (pos2, value2)
+      }
+    })
+    .map(|(pos, _)| pos);
+  assert_eq!(pos, Some(field.to_pos(IMAGE_2.solution.0, IMAGE_2.solution.1)));
+  // The tree is compacted during the search whenever it gets full, and before
+  // the next search if it takes more than the half of the limit.
+  let nodes_count = uct.nodes_count();
+  uct.best_moves(&field, Player::Red, &mut rng, &|| false, 0);
+  assert!(uct.nodes_count() > 1);
+  assert!(uct.nodes_count() <= nodes_count);
+}
+
+#[test]
+fn uct_deterministic_memory_limit() {
+  env_logger::try_init().ok();
+  let best_moves = |threads_count| {
+    let config = UctConfig {
+      threads_count,
+      memory: 1,
+      deterministic: true,
+      ..UCT_CONFIG
+    };
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+    let field = construct_field(&mut rng, IMAGE_2.image);
+    let mut uct = UctRoot::new(config, field.length());
+    let result = uct.best_moves(&field, Player::Red, &mut rng, &|| false, 100_000);
+    assert!(uct.memory_usage() <= 1024 * 1024);
+    result
+  };
+  // The tree is compacted during the search at the same points regardless of
+  // the threads count.
+  assert_eq!(best_moves(1), best_moves(4));
+}
+
+#[test]
+fn uct_compaction() {
+  env_logger::try_init().ok();
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+  let mut field = construct_field(&mut rng, IMAGE_1.image);
+  let mut uct = UctRoot::new(UCT_CONFIG, field.length());
+  let (moves, _, _) = uct.best_moves(&field, Player::Red, &mut rng, &|| false, 20_000);
+  let nodes_count = uct.nodes_count();
+  let pos = moves
+    .into_iter()
+    .reduce(|(pos1, value1), (pos2, value2)| {
+      if value1 >= value2 {
+        (pos1, value1)
+      } else {
+        (pos2, value2)
+      }
+    })
+    .map(|(pos, _)| pos)
+    .unwrap();
+  assert!(field.put_point(pos, Player::Red));
+  uct.best_moves(&field, Player::Black, &mut rng, &|| false, 0);
+  assert!(uct.nodes_count() > 1);
+  assert!(uct.nodes_count() < nodes_count);
+}
+
+/// Prefers the given move, as a perfect move ordering would do.
+struct MovePrior(Pos);
+
+impl PriorPolicy for MovePrior {
+  fn priors(&self, _: &Field, _: Player, moves: &[Pos]) -> Option<Vec<f64>> {
+    Some(
+      moves
+        .iter()
+        .map(|&pos| if pos == self.0 { 1.0 } else { 0.01 })
+        .collect(),
+    )
+  }
+}
+
+#[test]
+fn uct_prior() {
+  env_logger::try_init().ok();
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+  let field = construct_field(&mut rng, IMAGE_3.image);
+  let solution = field.to_pos(IMAGE_3.solution.0, IMAGE_3.solution.1);
+  let mut uct = UctRoot::with_policies(UCT_WIDENING_CONFIG, field.length(), UniformPolicy, MovePrior(solution));
+  let (moves, _, _) = uct.best_moves(&field, Player::Red, &mut rng, &|| false, 100_000);
+  let pos = moves
+    .into_iter()
+    .reduce(|(pos1, value1), (pos2, value2)| {
+      if value1 >= value2 {
+        (pos1, value1)
+      } else {
+        (pos2, value2)
+      }
+    })
+    .map(|(pos, _)| pos);
+  assert_eq!(pos, Some(solution));
+}
+
+#[test]
+fn uct_inspect() {
+  env_logger::try_init().ok();
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+  let field = construct_field(&mut rng, IMAGE_1.image);
+  let mut uct = UctRoot::new(UCT_CONFIG, field.length());
+  assert_eq!(uct.inspect(1), None);
+  uct.best_moves(&field, Player::Red, &mut rng, &|| false, 10_000);
+  let tree = uct.inspect(1).unwrap();
+  assert_eq!(tree.player, Player::Red);
+  assert_eq!(tree.moves_count, field.moves_count());
+  assert_eq!(tree.root.visits, 10_000);
+  assert!(!tree.root.children.is_empty());
+  assert!(tree.root.children.iter().map(|child| child.visits).sum::<usize>() <= tree.root.visits);
+  assert!(tree.root.children.iter().all(|child| child.children.is_empty()));
+  let tree = uct.inspect(2).unwrap();
+  assert!(tree.root.children.iter().any(|child| !child.children.is_empty()));
+}
+
+#[test]
+fn uct_region() {
+  env_logger::try_init().ok();
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+  let field = construct_field(&mut rng, IMAGE_14.image);
+  let only = (0..5)
+    .flat_map(|x| (6..12).map(move |y| (x, y)))
+    .map(|(x, y)| field.to_pos(x, y))
+    .collect::<Vec<_>>();
+  let extra = field.to_pos(9, 0);
+  let mut uct = UctRoot::new(UCT_CONFIG, field.length());
+  uct.set_region(Region {
+    only: Some(only.iter().copied().chain(std::iter::once(extra)).collect()),
+    extra: vec![extra],
+  });
+  let (moves, _, _) = uct.best_moves(&field, Player::Red, &mut rng, &|| false, 10_000);
+  assert!(!moves.is_empty());
+  assert!(moves.iter().all(|&(pos, _)| pos == extra || only.contains(&pos)));
+  assert!(moves.iter().any(|&(pos, _)| pos == extra));
+}
+
+/// Guided policy that records whether playouts leave possible moves.
+struct CheckedGuidedPolicy {
+  policy: GuidedPolicy,
+  violated: Arc<AtomicBool>,
+}
+
+impl PlayoutPolicy for CheckedGuidedPolicy {
+  fn play<R: Rng>(&self, field: &mut Field, player: Player, possible_moves: &mut [Pos], rng: &mut R) -> i32 {
+    let moves_count = field.moves_count();
+    let possible = possible_moves.to_vec();
+    let score = self.policy.play(field, player, possible_moves, rng);
+    if field.moves()[moves_count..].iter().any(|pos| !possible.contains(pos)) {
+      self.violated.store(true, Ordering::Relaxed);
+    }
+    score
+  }
+}
+
+#[test]
+fn uct_guided_region() {
+  env_logger::try_init().ok();
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+  let field = construct_field(&mut rng, IMAGE_14.image);
+  let only = (0..5)
+    .flat_map(|x| (6..12).map(move |y| (x, y)))
+    .map(|(x, y)| field.to_pos(x, y))
+    .collect::<Vec<_>>();
+  let violated = Arc::new(AtomicBool::new(false));
+  let policy = CheckedGuidedPolicy {
+    policy: GuidedPolicy::default(),
+    violated: violated.clone(),
+  };
+  let mut uct = UctRoot::with_policy(UCT_CONFIG, field.length(), policy);
+  uct.set_region(Region {
+    only: Some(only.clone()),
+    extra: Vec::new(),
+  });
+  let (moves, _, _) = uct.best_moves(&field, Player::Red, &mut rng, &|| false, 10_000);
+  assert!(moves.iter().all(|&(pos, _)| only.contains(&pos)));
+  assert!(!violated.load(Ordering::Relaxed));
+}
+
+#[test]
+fn uct_virtual_loss() {
+  env_logger::try_init().ok();
+  let config = UctConfig {
+    threads_count: 4,
+    virtual_loss: 3,
+    ..UCT_CONFIG
+  };
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+  let field = construct_field(&mut rng, IMAGE_2.image);
+  let mut uct = UctRoot::new(config, field.length());
+  uct.best_moves(&field, Player::Red, &mut rng, &|| false, 20_000);
+  let tree = uct.inspect(2).unwrap();
+  // Virtual losses are reverted, so children have no more visits than their
+  // parents.
+  assert!(tree.root.visits >= 20_000);
+  assert!(tree.root.children.iter().map(|child| child.visits).sum::<usize>() <= tree.root.visits);
+  for child in &tree.root.children {
+    assert!(child.children.iter().map(|child| child.visits).sum::<usize>() <= child.visits);
+  }
+}
+
+#[test]
+fn uct_virtual_loss_expansion() {
+  env_logger::try_init().ok();
+  let config = UctConfig {
+    threads_count: 4,
+    virtual_loss: 3,
+    ..UCT_CONFIG
+  };
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+  let field = construct_field(&mut rng, IMAGE_2.image);
+  let mut uct = UctRoot::new(config, field.length());
+  uct.best_moves(&field, Player::Red, &mut rng, &|| false, 20_000);
+  // Virtual losses don't count as visits for expansion, so a node gets
+  // children only on its visit after `when_create_children` real ones.
+  fn check(node: &UctNodeInfo, when_create_children: usize) {
+    for child in &node.children {
+      // Lost nodes are reported without visits.
+      assert!(child.children.is_empty() || child.visits == 0 || child.visits > when_create_children);
+      check(child, when_create_children);
+    }
+  }
+  check(&uct.inspect(u32::MAX).unwrap().root, UCT_CONFIG.when_create_children);
+}
+
+#[test]
+fn uct_forbid_losing_ladders() {
+  env_logger::try_init().ok();
+  let config = UctConfig {
+    forbid_losing_ladders: true,
+    ..UCT_CONFIG
+  };
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+  let field = construct_field(
+    &mut rng,
+    "
+    .........
+    ......a..
+    .........
+    ...a.....
+    ..aA.....
+    .aAAa....
+    ..aa.....
+    .........
+    ",
+  );
+  let escape = field.to_pos(4, 4);
+  let mut uct = UctRoot::new(config, field.length());
+  let (moves, _, _) = uct.best_moves(&field, Player::Black, &mut rng, &|| false, 10_000);
+  assert!(!moves.is_empty());
+  assert!(moves.iter().all(|&(pos, _)| pos != escape));
+  // The forbidden move is excluded from the reused tree as well.
+  let (moves, _, _) = uct.best_moves(&field, Player::Black, &mut rng, &|| false, 10_000);
+  assert!(moves.iter().all(|&(pos, _)| pos != escape));
+}
+
+#[test]
+fn uct_rave_amaf() {
+  env_logger::try_init().ok();
+  let amaf_visits = |config| {
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+    let field = construct_field(&mut rng, IMAGE_2.image);
+    let mut uct = UctRoot::new(config, field.length());
+    uct.best_moves(&field, Player::Red, &mut rng, &|| false, 10_000);
+    let tree = uct.inspect(1).unwrap();
+    tree.root.children.iter().map(|child| child.amaf_visits).sum::<usize>()
+  };
+  assert_eq!(amaf_visits(UCT_CONFIG), 0);
+  // Every simulation credits all root children played later by the player.
+  assert!(amaf_visits(UCT_RAVE_CONFIG) > 10_000);
+  assert!(
+    amaf_visits(UctConfig {
+      threads_count: 4,
+      deterministic: true,
+      ..UCT_RAVE_CONFIG
+    }) > 10_000
+  );
+}
+
+#[test]
+fn uct_deterministic_rave_threads() {
+  env_logger::try_init().ok();
+  let best_moves = |threads_count| {
+    let config = UctConfig {
+      threads_count,
+      deterministic: true,
+      ..UCT_RAVE_CONFIG
+    };
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+    let field = construct_field(&mut rng, IMAGE_2.image);
+    let mut uct = UctRoot::new(config, field.length());
+    let result = uct.best_moves(&field, Player::Red, &mut rng, &|| false, 20_000);
+    let tree = uct.inspect(1).unwrap();
+    let stats = tree
+      .root
+      .children
+      .iter()
+      .map(|child| (child.pos, child.visits, child.amaf_visits))
+      .collect::<Vec<_>>();
+    (result, stats)
+  };
+  assert_eq!(best_moves(1), best_moves(4));
+}
+
+#[test]
+fn uct_score_won_position() {
+  env_logger::try_init().ok();
+  let config = UctConfig {
+    komi_type: UctKomiType::None,
+    ..UCT_SCORE_CONFIG
+  };
+  let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+  let field = construct_field(
+    &mut rng,
+    "
+    .a..a...aa..........
+    aAaaAa.aAAa.........
+    .a......a...........
+    ....................
+    ..aa................
+    .aAAa...............
+    ..aa................
+    ....................
+    ....................
+    ....................
+    ....................
+    ....................
+    ....................
+    ....................
+    ....................
+    ",
+  );
+  let mut uct = UctRoot::new(config, field.length());
+  let (moves, _, _) = uct.best_moves(&field, Player::Red, &mut rng, &|| false, 100_000);
+  let (pos, value) = moves
+    .into_iter()
+    .reduce(|(pos1, value1), (pos2, value2)| {
+      if value1 >= value2 {
+        (pos1, value1)
+      } else {
+        (pos2, value2)
+      }
+    })
+    .unwrap();
+  // Red wins almost every game, so only the score utility prefers capturing
+  // two points to capturing one.
+  assert_eq!(pos, field.to_pos(9, 2));
+  assert!(value > 1.0);
+}
+
+#[test]
+fn uct_adaptive_radius() {
+  env_logger::try_init().ok();
+  let root_moves = |config| {
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+    let field = construct_field(&mut rng, IMAGE_1.image);
+    let mut uct = UctRoot::new(config, field.length());
+    let (moves, _, _) = uct.best_moves(&field, Player::Red, &mut rng, &|| false, 1_000);
+    moves.len()
+  };
+  // The radius grows on the mostly empty field.
+  assert!(root_moves(UCT_ADAPTIVE_CONFIG) > root_moves(UCT_CONFIG));
+}
@@ -1,17 +1,31 @@
 use oppai_field::field::{self, Field, Pos};
 use std::iter;
 
+/// Explicit control over the points considered by the search.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Region {
+  /// If specified, only these points are considered, e.g. to analyze a
+  /// single corner.
+  pub only: Option<Vec<Pos>>,
+  /// Points that are considered regardless of their distance to moves.
+  pub extra: Vec<Pos>,
+}
+
 #[derive(Clone)]
 pub struct WavePruning {
   moves: Vec<Pos>,
   moves_field: Vec<Pos>,
+  allowed: Option<Vec<bool>>,
+  extra: Vec<Pos>,
 }
 
 impl WavePruning {
   pub fn new(length: Pos) -> WavePruning {
     WavePruning {
       moves: Vec::with_capacity(length),
-      moves_field: iter::repeat(0).take(length).collect(),
+      moves_field: iter::repeat_n(0, length).collect(),
+      allowed: None,
+      extra: Vec::new(),
     }
   }
 
@@ -26,34 +40,70 @@ impl WavePruning {
     }
   }
 
-  pub fn init(&mut self, field: &Field, radius: u32) {
+  /// Sets the region of the search. Takes effect on the next initialization.
+  pub fn set_region(&mut self, region: Region) {
+    self.allowed = region.only.map(|only| {
+      let mut allowed = vec![false; self.moves_field.len()];
+      for pos in only {
+        allowed[pos] = true;
+      }
+      allowed
+    });
+    self.extra = region.extra;
+  }
+
+  fn is_allowed(&self, pos: Pos) -> bool {
+    self.allowed.as_ref().is_none_or(|allowed| allowed[pos])
+  }
+
+  /// Adds points within `radius` from `start_pos` reachable through free
+  /// points.
+  fn add_wave(&mut self, field: &Field, start_pos: Pos, radius: u32, added_moves: &mut Vec<Pos>) {
     let width = field.width();
-    for &start_pos in field.moves() {
-      field::wave(width, start_pos, |pos| {
-        if pos == start_pos && self.moves_field[pos] == 0 {
-          self.moves_field[pos] = 1;
-          true
-        } else if self.moves_field[pos] != start_pos
-          && field.cell(pos).is_putting_allowed()
-          && field::manhattan(width, start_pos, pos) <= radius
-        {
-          if self.moves_field[pos] == 0 {
-            self.moves.push(pos);
-          }
-          self.moves_field[pos] = start_pos;
-          true
-        } else {
-          false
+    let allowed = &self.allowed;
+    let moves_field = &mut self.moves_field;
+    let moves = &mut self.moves;
+    field::wave(width, start_pos, |pos| {
+      if pos == start_pos && moves_field[pos] == 0 {
+        moves_field[pos] = 1;
+        true
+      } else if moves_field[pos] != start_pos
+        && field.cell(pos).is_putting_allowed()
+        && field::manhattan(width, start_pos, pos) <= radius
+      {
+        if moves_field[pos] == 0 && pos != start_pos && allowed.as_ref().is_none_or(|allowed| allowed[pos]) {
+          moves.push(pos);
+          added_moves.push(pos);
         }
-      });
-      self.moves_field[start_pos] = 0;
+        moves_field[pos] = start_pos;
+        true
+      } else {
+        false
+      }
+    });
+    moves_field[start_pos] = 0;
+  }
+
+  /// Considers points within `radius` of every move of the field.
+  pub fn init<F: Fn(Pos) -> u32>(&mut self, field: &Field, radius: F) {
+    let mut added_moves = Vec::new();
+    for &start_pos in field.moves() {
+      self.add_wave(field, start_pos, radius(start_pos), &mut added_moves);
+    }
+    for i in 0..self.extra.len() {
+      let pos = self.extra[i];
+      if self.moves_field[pos] == 0 && field.cell(pos).is_putting_allowed() && self.is_allowed(pos) {
+        self.moves.push(pos);
+        self.moves_field[pos] = Pos::MAX;
+      }
     }
   }
 
-  pub fn update(&mut self, field: &Field, last_moves_count: usize, radius: u32) -> Vec<Pos> {
+  /// Considers points within `radius` of moves made after `last_moves_count`
+  /// and returns the added points.
+  pub fn update<F: Fn(Pos) -> u32>(&mut self, field: &Field, last_moves_count: usize, radius: F) -> Vec<Pos> {
     let moves_field = &mut self.moves_field;
-    let moves = &mut self.moves;
-    moves.retain(|&pos| {
+    self.moves.retain(|&pos| {
       if field.cell(pos).is_putting_allowed() {
         true
       } else {
@@ -61,28 +111,9 @@ impl WavePruning {
         false
       }
     });
-    let width = field.width();
     let mut added_moves = Vec::new();
     for &next_pos in field.moves().iter().skip(last_moves_count) {
-      field::wave(width, next_pos, |pos| {
-        if pos == next_pos && moves_field[pos] == 0 {
-          moves_field[pos] = 1;
-          true
-        } else if moves_field[pos] != next_pos
-          && field.cell(pos).is_putting_allowed()
-          && field::manhattan(width, next_pos, pos) <= radius
-        {
-          if moves_field[pos] == 0 && pos != next_pos {
-            moves.push(pos);
-            added_moves.push(pos);
-          }
-          moves_field[pos] = next_pos;
-          true
-        } else {
-          false
-        }
-      });
-      moves_field[next_pos] = 0;
+      self.add_wave(field, next_pos, radius(next_pos), &mut added_moves);
     }
     added_moves
   }
@@ -1,12 +1,12 @@
-#[cfg(feature = "term-render")]
-use clap::ArgAction;
-use clap::{Arg, Command};
+use clap::{value_parser, Arg, ArgAction, Command};
 
 pub struct Config {
   pub ai1: String,
   pub ai2: String,
   pub ai1_args: Vec<String>,
   pub ai2_args: Vec<String>,
+  pub games: Option<u32>,
+  pub adjudicate: bool,
   #[cfg(feature = "term-render")]
   pub ascii: bool,
 }
@@ -43,6 +43,19 @@ pub fn cli_parse() -> Config {
         .help("Args for the first AI, separated by ','")
         .num_args(1..)
         .value_delimiter(','),
+    )
+    .arg(
+      Arg::new("games")
+        .long("games")
+        .help("Number of games to play. Games are played infinitely if not specified")
+        .num_args(1)
+        .value_parser(value_parser!(u32)),
+    )
+    .arg(
+      Arg::new("adjudicate")
+        .long("adjudicate")
+        .help("Ask AIs whether they want to resign or offer a draw after each move")
+        .action(ArgAction::SetTrue),
     );
   #[cfg(feature = "term-render")]
   let command = command.arg(
@@ -64,6 +77,8 @@ pub fn cli_parse() -> Config {
       .get_many::<String>("ai2-args")
       .map(|args| args.cloned().collect())
       .unwrap_or_default(),
+    games: matches.get_one("games").copied(),
+    adjudicate: matches.get_flag("adjudicate"),
     #[cfg(feature = "term-render")]
     ascii: matches.get_flag("ascii"),
   }
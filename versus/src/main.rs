@@ -1,6 +1,5 @@
 mod config;
 
-use std::cmp::Ordering;
 use std::fmt;
 #[cfg(feature = "term-render")]
 use std::io::Error;
@@ -15,7 +14,7 @@ use crossterm::{
   terminal::{Clear, ClearType},
   QueueableCommand,
 };
-use oppai_client::{Client, Constraint};
+use oppai_client::{Client, Constraint, Decision};
 use oppai_field::extended_field::ExtendedField;
 use oppai_field::field::{NonZeroPos, Pos};
 use oppai_field::player::Player;
@@ -73,12 +72,22 @@ impl Stats {
     loses: 0,
     draws: 1,
   };
+
+  fn games(&self) -> u32 {
+    self.wins + self.loses + self.draws
+  }
+
+  /// Share of points scored by the first AI, counting draws as half of a win.
+  fn score(&self) -> f64 {
+    (self.wins as f64 + self.draws as f64 / 2.0) / self.games() as f64
+  }
 }
 
 struct Game {
   field: ExtendedField,
   client1: Client,
   client2: Client,
+  adjudicate: bool,
 }
 
 impl Game {
@@ -118,23 +127,44 @@ impl Game {
     self.field.field.is_game_over()
   }
 
-  fn stats(&self, swap: bool) -> Stats {
-    match self.field.field.score(Player::Red).cmp(&0) {
-      Ordering::Less => {
+  /// Asks the AI that made the last move whether it wants to resign or offer a
+  /// draw. Returns the winner if the game is finished this way.
+  async fn adjudicate(&mut self, player: Player, swap: bool) -> Result<Option<Option<Player>>> {
+    let (client, opponent) = if swap {
+      (&mut self.client2, &mut self.client1)
+    } else {
+      (&mut self.client1, &mut self.client2)
+    };
+    Ok(match client.decision().await? {
+      Decision::Play => None,
+      Decision::Resign => Some(Some(player.next())),
+      Decision::OfferDraw => {
+        if opponent.draw_offer().await? {
+          Some(None)
+        } else {
+          None
+        }
+      }
+    })
+  }
+
+  fn stats(winner: Option<Player>, swap: bool) -> Stats {
+    match winner {
+      Some(Player::Black) => {
         if swap {
           Stats::WIN
         } else {
           Stats::LOOSE
         }
       }
-      Ordering::Greater => {
+      Some(Player::Red) => {
         if swap {
           Stats::LOOSE
         } else {
           Stats::WIN
         }
       }
-      Ordering::Equal => Stats::DRAW,
+      None => Stats::DRAW,
     }
   }
 
@@ -173,6 +203,7 @@ impl Game {
 
   async fn play(&mut self, mut player: Player, swap: bool, stats: &mut Stats, ascii: bool) -> Result<()> {
     let mut cur_swap = swap;
+    let mut adjudication = None;
     self.draw(stats, ascii)?;
     while let Some(pos) = self.best_move(player, cur_swap).await? {
       if !self.put_point(pos.get(), player).await? {
@@ -182,10 +213,17 @@ impl Game {
       if self.is_game_over() {
         break;
       }
+      if self.adjudicate {
+        adjudication = self.adjudicate(player, cur_swap).await?;
+        if adjudication.is_some() {
+          break;
+        }
+      }
       player = player.next();
       cur_swap = !cur_swap;
     }
-    *stats = *stats + self.stats(swap);
+    let winner = adjudication.unwrap_or_else(|| self.field.field.winner());
+    *stats = *stats + Game::stats(winner, swap);
     Ok(())
   }
 
@@ -214,6 +252,7 @@ fn main() -> Result<()> {
     field: ExtendedField::new_from_rng(WIDTH, HEIGHT, &mut rng),
     client1: Client::spawn(config.ai1, config.ai1_args)?,
     client2: Client::spawn(config.ai2, config.ai2_args)?,
+    adjudicate: config.adjudicate,
   };
 
   let player = Player::default();
@@ -225,13 +264,16 @@ fn main() -> Result<()> {
   #[cfg(not(feature = "term-render"))]
   let ascii = true;
   let future = async {
-    loop {
+    while config.games.is_none_or(|games| stats.games() < games) {
       game.init().await?;
       game.place_initial_position(player, INITIAL_POSITION).await?;
       game.play(player, swap, &mut stats, ascii).await?;
       swap = !swap;
     }
+    Ok::<(), std::io::Error>(())
   };
 
-  futures::executor::block_on(future)
+  futures::executor::block_on(future)?;
+  println!("Score of the first AI: {:.3}", stats.score());
+  Ok(())
 }
@@ -225,7 +225,7 @@ fn episode_winning_game() {
 
   assert!(examples.policies.iter().all(|p| (p.sum() - 1.0).abs() < 0.001));
 
-  for (value, input) in examples.values.into_iter().zip(examples.inputs.into_iter()) {
+  for (value, input) in examples.values.into_iter().zip(examples.inputs) {
     assert!(if input[(0, center_y, center_x)] > 0.0 {
       value > 0.0
     } else {
@@ -139,7 +139,7 @@ where
     for eta in dirichlet.iter_mut() {
       *eta = *eta / sum;
     }
-    for (child, eta) in self.children.iter_mut().zip(dirichlet.into_iter()) {
+    for (child, eta) in self.children.iter_mut().zip(dirichlet) {
       child.policy = child.policy * (N::one() - epsilon) + epsilon * eta;
     }
   }